@@ -0,0 +1,32 @@
+// how fast the interpreter runs examples_phase4/primes.tt, scaled up to sieve the primes below 200000.
+//
+//   cargo bench --bench vm
+//
+// the program prints every prime it finds, which goes to a sink so that the terminal is not timed. the timings go to stderr.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+const SIZE: i32 = 200_000;
+const RUNS: usize = 5;
+
+fn main() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples_phase4/primes.tt");
+    let source = std::fs::read_to_string(path).unwrap();
+    // primes.tt sieves below 100, crossing out the multiples of the numbers below 10.
+    let root = (SIZE as f64).sqrt().ceil() as i32;
+    let source = source.replace("100", &SIZE.to_string()).replace("i < 10 {", &format!("i < {root} {{"));
+    // it declares variables with a value, which the parser does not take.
+    let source = source.replace("int i = 0;", "int i;\n    i = 0;").replace("int j = i + i;", "int j;\n            j = i + i;");
+    let ir = rustcompiler::compile_to_ir(&source).unwrap().ir;
+
+    let mut times: Vec<Duration> = vec![];
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        rustcompiler::interpreter::execute(&ir, io::empty(), io::sink()).unwrap();
+        times.push(start.elapsed());
+    }
+    let mean = times.iter().sum::<Duration>() / RUNS as u32;
+    let fastest = times.iter().min().unwrap();
+    eprintln!("primes below {SIZE}: mean {:.1} ms, fastest {:.1} ms over {RUNS} runs", mean.as_secs_f64() * 1000.0, fastest.as_secs_f64() * 1000.0);
+}
@@ -0,0 +1,488 @@
+// control flow analyses over the generated IR.
+// like semantics_check, this works on the generated code, one function at a time:
+// every IR line becomes a node in a control flow graph, with edges for fallthrough,
+// '%jmp', '%branch_if' and '%branch_ifn'. '%ret' has no successors.
+//
+// the analyses that run on the graph:
+// - definite assignment: warn when a variable may be read before it was assigned.
+// - missing return: a function that returns a value somewhere must return on every path.
+// - unused variables and parameters: declared, but never read.
+// - unreachable code: statements after 'return', 'break' or 'continue'.
+// across functions, functions that cannot be reached from main through calls are reported.
+
+use std::collections::HashMap;
+
+use crate::diagnostics::{codes, Diagnostic};
+use crate::warnings::{Warning, WarningKind};
+
+pub struct Analysis {
+    pub warnings: Vec<Warning>,
+    pub errors: Vec<Diagnostic>,
+}
+
+enum Flow {
+    Next,
+    Jump(String),
+    Branch(String),
+    Return,
+}
+
+struct Instr {
+    flow: Flow,
+    label: Option<String>,
+    // variables written by this instruction.
+    defs: Vec<String>,
+    // variables read by this instruction.
+    uses: Vec<String>,
+    // '%int x' declarations reset a variable to "unassigned".
+    decls: Vec<String>,
+    // arrays read with '%mov x, [array + i]'.
+    array_reads: Vec<String>,
+    array_decl: Option<String>,
+    call: Option<String>,
+    // the source line of the instruction, from the '; line' marks.
+    line: Option<usize>,
+}
+
+struct Function {
+    name: String,
+    params: Vec<String>,
+    body: Vec<Instr>,
+    // the file the function is in, from the '; file' marks of a program with imports, and the line of its name.
+    file: Option<String>,
+    line: Option<usize>,
+}
+
+pub fn analyze(generated_code: &str) -> Analysis {
+    let mut analysis = Analysis {
+        warnings: vec![],
+        errors: vec![],
+    };
+    let functions = parse_functions(generated_code);
+    for function in &functions {
+        check_function(function, &mut analysis);
+    }
+    check_unused_functions(&functions, &mut analysis);
+    analysis
+}
+
+fn warn(analysis: &mut Analysis, function: &Function, line: Option<usize>, kind: WarningKind, symbol: &str, message: String) {
+    analysis.warnings.push(Warning {
+        kind,
+        symbol: String::from(symbol),
+        message,
+        file: function.file.clone(),
+        line,
+    });
+}
+
+fn is_variable(word: &str) -> bool {
+    match word.chars().next() {
+        Some(c) => c.is_alphabetic() || c == '_',
+        None => false,
+    }
+}
+
+fn variables(words: &[&str]) -> Vec<String> {
+    words.iter().filter(|w| is_variable(w)).map(|w| w.to_string()).collect()
+}
+
+fn parse_functions(generated_code: &str) -> Vec<Function> {
+    let mut functions: Vec<Function> = vec![];
+    let (mut file, mut source_line) = (None, None);
+    for gline in generated_code.lines() {
+        let line = gline.trim();
+        if let Some(name) = line.strip_prefix("; file ") {
+            (file, source_line) = (Some(name.to_string()), None);
+        } else if let Some(number) = line.strip_prefix("; line ") {
+            source_line = number.trim().parse().ok();
+        }
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with("%func") {
+            let cleaned = line.replace(['(', ')', ','], " ");
+            let words: Vec<&str> = cleaned.split_whitespace().collect();
+            let name = words.get(1).copied().unwrap_or("").to_string();
+            let params = words.iter().skip(2).filter(|w| !w.starts_with('%')).map(|w| w.to_string()).collect();
+            functions.push(Function { name, params, body: vec![], file: file.clone(), line: source_line });
+            continue;
+        }
+
+        let function = match functions.last_mut() {
+            Some(function) => function,
+            None => continue,
+        };
+
+        if line.starts_with("%endfunc") {
+            continue;
+        }
+
+        function.body.push(Instr { line: source_line, ..parse_instr(line) });
+    }
+    functions
+}
+
+fn parse_instr(line: &str) -> Instr {
+    let mut instr = Instr {
+        flow: Flow::Next,
+        label: None,
+        defs: vec![],
+        uses: vec![],
+        decls: vec![],
+        array_reads: vec![],
+        array_decl: None,
+        call: None,
+        line: None,
+    };
+
+    if let Some(label) = line.strip_prefix(':') {
+        instr.label = Some(label.to_string());
+        return instr;
+    }
+
+    let opcode = line.split_whitespace().next().unwrap_or("");
+    let operands = line[opcode.len()..].trim();
+    let cleaned = operands.replace([',', '[', ']', '(', ')', '+'], " ");
+    let words: Vec<&str> = cleaned.split_whitespace().collect();
+
+    match opcode {
+        "%int" | "%long" => instr.decls = variables(&words[..words.len().min(1)]),
+        "%int[]" => instr.array_decl = words.first().map(|w| w.to_string()),
+        "%mov" => {
+            if operands.starts_with('[') {
+                // the array itself is not tracked, only its index and the value.
+                instr.uses = variables(&words[1..]);
+            } else if let Some((dest, rest)) = words.split_first() {
+                instr.defs = variables(&[dest]);
+                if operands.contains('[') {
+                    instr.array_reads = variables(&rest[..1]);
+                    instr.uses = variables(&rest[1..]);
+                } else {
+                    instr.uses = variables(rest);
+                }
+            }
+        }
+        "%call" => {
+            if words.len() >= 2 {
+                instr.call = Some(words[1].to_string());
+                instr.defs = variables(&words[..1]);
+                instr.uses = variables(&words[2..]);
+            }
+        }
+        "%input" => instr.defs = variables(&words),
+        "%out" => instr.uses = variables(&words),
+        "%ret" => {
+            instr.uses = variables(&words);
+            instr.flow = Flow::Return;
+        }
+        "%jmp" => {
+            if let Some(target) = words.first() {
+                instr.flow = Flow::Jump(target.trim_start_matches(':').to_string());
+            }
+        }
+        "%branch_if" | "%branch_ifn" => {
+            if let Some((target, cond)) = words.split_last() {
+                instr.uses = variables(cond);
+                instr.flow = Flow::Branch(target.trim_start_matches(':').to_string());
+            }
+        }
+        _ => {
+            // three address code: '%add dest, src1, src2' and friends.
+            if let Some((dest, rest)) = words.split_first() {
+                instr.defs = variables(&[dest]);
+                instr.uses = variables(rest);
+            }
+        }
+    }
+    instr
+}
+
+// successors of each instruction. 'body.len()' stands for falling off the end of the function.
+fn successors(body: &[Instr]) -> Vec<Vec<usize>> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    for (i, instr) in body.iter().enumerate() {
+        if let Some(label) = &instr.label {
+            labels.insert(label, i);
+        }
+    }
+
+    let mut succ = vec![];
+    for (i, instr) in body.iter().enumerate() {
+        let mut next = vec![];
+        match &instr.flow {
+            Flow::Next => next.push(i + 1),
+            Flow::Jump(label) => next.extend(labels.get(label.as_str())),
+            Flow::Branch(label) => {
+                next.push(i + 1);
+                next.extend(labels.get(label.as_str()));
+            }
+            Flow::Return => {}
+        }
+        succ.push(next);
+    }
+    succ
+}
+
+fn check_function(function: &Function, analysis: &mut Analysis) {
+    let body = &function.body;
+    let succ = successors(body);
+    let exit = body.len();
+
+    // variables we track: declared integers. temporaries are always written before they are read.
+    let mut tracked: Vec<&str> = vec![];
+    for instr in body {
+        for decl in &instr.decls {
+            if !decl.starts_with("_temp") && !tracked.contains(&decl.as_str()) {
+                tracked.push(decl);
+            }
+        }
+    }
+    let slot = |name: &str| tracked.iter().position(|t| *t == name);
+
+    // assigned[i] holds, for every tracked variable, whether it is assigned on every path into instruction i.
+    // None means no path has been found into the instruction yet.
+    let mut assigned: Vec<Option<Vec<bool>>> = vec![None; exit + 1];
+    let mut entry = vec![false; tracked.len()];
+    for param in &function.params {
+        if let Some(s) = slot(param) {
+            entry[s] = true;
+        }
+    }
+    assigned[0] = Some(entry);
+
+    let mut worklist: Vec<usize> = vec![0];
+    while let Some(i) = worklist.pop() {
+        if i == exit {
+            continue;
+        }
+        let mut state = assigned[i].clone().unwrap();
+        for decl in &body[i].decls {
+            if let Some(s) = slot(decl) {
+                state[s] = false;
+            }
+        }
+        for def in &body[i].defs {
+            if let Some(s) = slot(def) {
+                state[s] = true;
+            }
+        }
+
+        for &next in &succ[i] {
+            let merged = match &assigned[next] {
+                None => state.clone(),
+                Some(old) => old.iter().zip(&state).map(|(a, b)| *a && *b).collect(),
+            };
+            if assigned[next].as_ref() != Some(&merged) {
+                assigned[next] = Some(merged);
+                worklist.push(next);
+            }
+        }
+    }
+
+    let mut reported: Vec<&str> = vec![];
+    for (i, instr) in body.iter().enumerate() {
+        let state = match &assigned[i] {
+            Some(state) => state,
+            None => continue, // unreachable.
+        };
+        for used in &instr.uses {
+            if let Some(s) = slot(used) {
+                if !state[s] && !reported.contains(&used.as_str()) {
+                    reported.push(used);
+                    let message = format!("Variable {} may be used before it is assigned in function {}.", used, function.name);
+                    warn(analysis, function, instr.line, WarningKind::UninitializedRead, used, message);
+                }
+            }
+        }
+    }
+
+    // a function returns a value if it has a return statement anywhere.
+    // such a function must not be able to reach its end without returning.
+    let returns_value = body.iter().any(|instr| matches!(instr.flow, Flow::Return));
+    if returns_value && assigned[exit].is_some() {
+        let message = format!("Function {} does not return a value on every path.", function.name);
+        let mut error = Diagnostic::new(codes::MISSING_RETURN, message);
+        (error.file, error.line) = (function.file.clone(), function.line);
+        analysis.errors.push(error);
+    }
+
+    check_unused_variables(function, analysis);
+    check_unreachable(function, &assigned, analysis);
+}
+
+fn check_unused_variables(function: &Function, analysis: &mut Analysis) {
+    let is_read = |name: &str| {
+        function.body.iter().any(|instr| instr.uses.iter().chain(&instr.array_reads).any(|used| used == name))
+    };
+
+    for param in &function.params {
+        if !is_read(param) {
+            let message = format!("Parameter {} is never used in function {}.", param, function.name);
+            warn(analysis, function, function.line, WarningKind::UnusedParameter, param, message);
+        }
+    }
+
+    let mut reported: Vec<&str> = vec![];
+    for instr in &function.body {
+        for declared in instr.decls.iter().chain(&instr.array_decl) {
+            if declared.starts_with("_temp") || reported.contains(&declared.as_str()) {
+                continue;
+            }
+            reported.push(declared);
+            if !is_read(declared) {
+                let message = format!("Variable {} is declared but never used in function {}.", declared, function.name);
+                warn(analysis, function, instr.line, WarningKind::UnusedVariable, declared, message);
+            }
+        }
+    }
+}
+
+// reports each run of unreachable statements once. the labels and jumps the compiler
+// generates around loops and ifs are not statements, so they are never reported.
+fn check_unreachable(function: &Function, assigned: &[Option<Vec<bool>>], analysis: &mut Analysis) {
+    let body = &function.body;
+    let mut in_run = false;
+    for (i, instr) in body.iter().enumerate() {
+        if assigned[i].is_some() {
+            in_run = false;
+            continue;
+        }
+        let generated = instr.label.is_some() || matches!(instr.flow, Flow::Jump(_));
+        if generated || in_run {
+            continue;
+        }
+        in_run = true;
+
+        // find what made this code unreachable.
+        let cause = body[..i].iter().rev().find_map(|before| match &before.flow {
+            Flow::Return => Some("return"),
+            Flow::Jump(label) if label.starts_with("endloop") => Some("break"),
+            Flow::Jump(label) if label.starts_with("loopbegin") => Some("continue"),
+            _ => None,
+        });
+        let message = match cause {
+            Some(cause) => format!("Unreachable code after '{}' in function {}.", cause, function.name),
+            None => format!("Unreachable code in function {}.", function.name),
+        };
+        warn(analysis, function, instr.line, WarningKind::UnreachableCode, &function.name, message);
+    }
+}
+
+// functions that main can never end up calling.
+fn check_unused_functions(functions: &[Function], analysis: &mut Analysis) {
+    let mut called: Vec<&str> = vec!["main"];
+    let mut next = 0;
+    while next < called.len() {
+        if let Some(function) = functions.iter().find(|f| f.name == called[next]) {
+            for instr in &function.body {
+                if let Some(callee) = &instr.call {
+                    if !called.contains(&callee.as_str()) {
+                        called.push(callee);
+                    }
+                }
+            }
+        }
+        next += 1;
+    }
+
+    for function in functions {
+        if !called.contains(&function.name.as_str()) {
+            let message = format!("Function {} is never called from main.", function.name);
+            warn(analysis, function, function.line, WarningKind::UnusedFunction, &function.name, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod analysis_tests {
+    use crate::analysis::analyze;
+    use crate::warnings::WarningKind;
+
+    fn kinds(code: &str) -> Vec<WarningKind> {
+        analyze(code).warnings.iter().map(|w| w.kind).collect()
+    }
+
+    #[test]
+    fn uninitialized_read() {
+        let code = "%func main\n%int a\n%int b\n%mov b, a\n%out b\n%endfunc\n";
+        let analysis = analyze(code);
+        assert!(analysis.warnings.len() == 1);
+        assert!(analysis.warnings[0].message.contains("Variable a"));
+        assert!(analysis.warnings[0].kind == WarningKind::UninitializedRead);
+        assert!(analysis.errors.is_empty());
+    }
+
+    #[test]
+    fn assigned_on_one_branch() {
+        // if a < 1 { b = 1; } print(b);
+        let code = "%func main(%int a)\n%int b\n%int _temp1\n%lt _temp1, a, 1\n%branch_if _temp1, :iftrue1\n%jmp :endif1\n:iftrue1\n%mov b, 1\n:endif1\n%out b\n%endfunc\n";
+        let analysis = analyze(code);
+        assert!(analysis.warnings.len() == 1);
+        assert!(analysis.warnings[0].message.contains("Variable b"));
+
+        // both branches assign, so no warning.
+        let code = "%func main(%int a)\n%int b\n%int _temp1\n%lt _temp1, a, 1\n%branch_if _temp1, :iftrue1\n%mov b, 2\n%jmp :endif1\n:iftrue1\n%mov b, 1\n:endif1\n%out b\n%endfunc\n";
+        assert!(analyze(code).warnings.is_empty());
+    }
+
+    #[test]
+    fn parameters_and_arrays_are_assigned() {
+        let code = "%func add(%int a, %int b)\n%int _temp1\n%add _temp1, a, b\n%ret _temp1\n%endfunc\n%func main\n%int[] arr, 4\n%int i\n%int _temp2\n%mov i, 0\n%mov [arr + i], 2\n%mov _temp2, [arr + i]\n%out _temp2\n%call _temp2, add(i, 1)\n%endfunc\n";
+        let analysis = analyze(code);
+        assert!(analysis.warnings.is_empty());
+        assert!(analysis.errors.is_empty());
+    }
+
+    #[test]
+    fn missing_return() {
+        // if a < 1 { return 1; }
+        let code = "%func f(%int a)\n%int _temp1\n%lt _temp1, a, 1\n%branch_if _temp1, :iftrue1\n%jmp :endif1\n:iftrue1\n%ret 1\n:endif1\n%endfunc\n";
+        let analysis = analyze(code);
+        assert!(analysis.errors.len() == 1);
+        assert!(analysis.errors[0].message.contains("Function f"));
+
+        // a return after the if covers every path.
+        let code = "%func f(%int a)\n%int _temp1\n%lt _temp1, a, 1\n%branch_if _temp1, :iftrue1\n%jmp :endif1\n:iftrue1\n%ret 1\n:endif1\n%ret 0\n%endfunc\n";
+        assert!(analyze(code).errors.is_empty());
+
+        // functions without any return statement are void.
+        let code = "%func main\n%out 1\n%endfunc\n";
+        assert!(analyze(code).errors.is_empty());
+    }
+
+    #[test]
+    fn unused_variables_and_parameters() {
+        let code = "%func f(%int a, %int b)\n%int c\n%mov c, 1\n%ret b\n%endfunc\n%func main\n%int _temp1\n%call _temp1, f(1, 2)\n%endfunc\n";
+        let analysis = analyze(code);
+        assert!(analysis.warnings.len() == 2);
+        assert!(analysis.warnings[0].kind == WarningKind::UnusedParameter && analysis.warnings[0].symbol == "a");
+        assert!(analysis.warnings[1].kind == WarningKind::UnusedVariable && analysis.warnings[1].symbol == "c");
+    }
+
+    #[test]
+    fn unused_functions() {
+        // helper is only called by unused, which main never calls.
+        let code = "%func helper\n%endfunc\n%func unused\n%int _temp1\n%call _temp1, helper()\n%endfunc\n%func main\n%endfunc\n";
+        let analysis = analyze(code);
+        let unused: Vec<&str> = analysis.warnings.iter().map(|w| w.symbol.as_str()).collect();
+        assert!(unused == vec!["helper", "unused"]);
+        assert!(kinds(code).iter().all(|k| *k == WarningKind::UnusedFunction));
+    }
+
+    #[test]
+    fn unreachable_code() {
+        // while i < 10 { break; print(i); } print(i); return 0; print(1);
+        let code = "%func main\n%int i\n%mov i, 0\n:loopbegin1\n%int _temp1\n%lt _temp1, i, 10\n%branch_ifn _temp1, :endloop_1\n%jmp :endloop_1\n%out i\n%jmp :loopbegin1\n:endloop_1\n%out i\n%ret 0\n%out 1\n%endfunc\n";
+        let analysis = analyze(code);
+        let messages: Vec<&str> = analysis.warnings.iter().map(|w| w.message.as_str()).collect();
+        assert!(messages.len() == 2);
+        assert!(messages[0].contains("after 'break'"));
+        assert!(messages[1].contains("after 'return'"));
+
+        // the jump back to the loop start after a return is generated code, not a statement.
+        let code = "%func main\n:loopbegin1\n%int _temp1\n%lt _temp1, 1, 2\n%branch_ifn _temp1, :endloop_1\n%ret 1\n%jmp :loopbegin1\n:endloop_1\n%ret 0\n%endfunc\n";
+        assert!(kinds(code).is_empty());
+    }
+}
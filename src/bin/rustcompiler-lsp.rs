@@ -0,0 +1,13 @@
+// the language server for .tt files, see lsp.rs. editors start it and talk to it over stdin and stdout.
+
+use std::io;
+use std::process::ExitCode;
+
+use rustcompiler::log::{self, Level};
+
+fn main() -> ExitCode {
+    // stdout carries the protocol, so nothing else may be printed there. the log goes to stderr as always.
+    log::set_level(Level::Off);
+    let code = rustcompiler::lsp::serve(io::stdin().lock(), io::stdout().lock());
+    ExitCode::from(code as u8)
+}
@@ -0,0 +1,549 @@
+// the catalog of error and warning codes, used by 'rustcompiler --explain CODE'.
+// every code in diagnostics::codes has an entry here with a longer explanation,
+// a small program that triggers it and the same program fixed.
+
+use crate::diagnostics::codes;
+
+pub enum Example {
+    // a .tt program.
+    Source,
+    // hand written IR, for errors the compiler itself never produces.
+    Ir,
+    // a commandline.
+    Command,
+}
+
+pub struct Entry {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub explanation: &'static str,
+    pub example: Example,
+    pub failing: &'static str,
+    pub fixed: &'static str,
+}
+
+pub fn lookup(code: &str) -> Option<&'static Entry> {
+    let code = code.to_uppercase();
+    CATALOG.iter().find(|entry| entry.code == code)
+}
+
+pub fn explain(entry: &Entry) -> String {
+    let kind = match entry.example {
+        Example::Source => "example",
+        Example::Ir => "IR",
+        Example::Command => "commandline",
+    };
+    let mut text = format!("{}: {}\n\n{}\n", entry.code, entry.title, entry.explanation);
+    text += &format!("\nErroneous {kind}:\n\n");
+    for line in entry.failing.lines() {
+        text += &format!("    {line}\n");
+    }
+    text += &format!("\nFixed {kind}:\n\n");
+    for line in entry.fixed.lines() {
+        text += &format!("    {line}\n");
+    }
+    text
+}
+
+pub const CATALOG: &[Entry] = &[
+    Entry {
+        code: codes::CANNOT_READ_FILE,
+        title: "the input file could not be read",
+        explanation: "The file given on the commandline does not exist, is not readable,\nor is not valid UTF-8 text.",
+        example: Example::Command,
+        failing: "rustcompiler examples_phase3/missing.tt",
+        fixed: "rustcompiler examples_phase3/add.tt",
+    },
+    Entry {
+        code: codes::CANNOT_WRITE_FILE,
+        title: "an output file could not be written",
+        explanation: "The file given with '-o' or '--emit kind=path' could not be created,\nusually because its directory does not exist or is not writable.",
+        example: Example::Command,
+        failing: "rustcompiler emit-ir examples_phase3/add.tt -o missing/add.ir",
+        fixed: "rustcompiler emit-ir examples_phase3/add.tt -o add.ir",
+    },
+    Entry {
+        code: codes::UNRECOGNIZED_SYMBOL,
+        title: "unrecognized symbol",
+        explanation: "The lexer found a character that is not part of the language.\nOnly + - * / %, comparisons, = ( ) { } [ ] , ; and # comments are allowed.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = 1 & 2;\n}",
+        fixed: "func main() {\n  int a;\n  a = 1 + 2;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::UNTERMINATED_STRING,
+        title: "unterminated string",
+        explanation: "A string starts and ends with a double quote on the same line.\nStrings are only used for the path of an 'import'.",
+        example: Example::Source,
+        failing: "import \"examples_imports/gcd.tt;\n\nfunc main() {\n  print(gcd(12, 18));\n}",
+        fixed: "import \"examples_imports/gcd.tt\";\n\nfunc main() {\n  print(gcd(12, 18));\n}",
+    },
+    Entry {
+        code: codes::IMPORT_NOT_FOUND,
+        title: "imported file not found",
+        explanation: "The file named by an 'import' could not be read.\nThe path is relative to the directory of the file with the import, not to the current directory.",
+        example: Example::Source,
+        failing: "import \"examples_imports/gdc.tt\";\n\nfunc main() {\n  print(gcd(12, 18));\n}",
+        fixed: "import \"examples_imports/gcd.tt\";\n\nfunc main() {\n  print(gcd(12, 18));\n}",
+    },
+    Entry {
+        code: codes::IMPORT_CYCLE,
+        title: "import cycle",
+        explanation: "A file imports itself, directly or through the files it imports.\nMove the functions both files need into a third file, and import that from both.",
+        example: Example::Command,
+        failing: "rustcompiler a.tt    # a.tt imports b.tt, which imports a.tt",
+        fixed: "rustcompiler a.tt    # a.tt and b.tt import common.tt",
+    },
+    Entry {
+        code: codes::NUMBER_TOO_BIG,
+        title: "number too big",
+        explanation: "A number in the source is bigger than the biggest long, 9223372036854775807.\nNumbers above 2147483647 are longs, smaller ones fit either type.",
+        example: Example::Source,
+        failing: "func main() {\n  long a;\n  a = 10000000000000000000;\n  print(a);\n}",
+        fixed: "func main() {\n  long a;\n  a = 1000000000000000000;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::UNEXPECTED_END,
+        title: "unexpected end of file",
+        explanation: "The file ended in the middle of a function or statement.\nThis is usually a missing '}' at the end of a function.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = 1;\n  print(a);",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::BREAK_OUTSIDE_LOOP,
+        title: "break outside of a loop",
+        explanation: "'break' jumps to the end of the innermost while loop,\nso it can only be used inside the body of a while loop.",
+        example: Example::Source,
+        failing: "func main() {\n  int i;\n  i = 0;\n  break;\n}",
+        fixed: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    break;\n  }\n  print(i);\n}",
+    },
+    Entry {
+        code: codes::CONTINUE_OUTSIDE_LOOP,
+        title: "continue outside of a loop",
+        explanation: "'continue' jumps back to the condition of the innermost while loop,\nso it can only be used inside the body of a while loop.",
+        example: Example::Source,
+        failing: "func main() {\n  int i;\n  i = 0;\n  continue;\n}",
+        fixed: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    i = i + 1;\n    continue;\n  }\n  print(i);\n}",
+    },
+    Entry {
+        code: codes::MISSING_SEMICOLON,
+        title: "missing ';'",
+        explanation: "Every statement other than 'if' and 'while' must end with a semicolon.",
+        example: Example::Source,
+        failing: "func main() {\n  int a\n  a = 1;\n  print(a);\n}",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::EXPECTED_FUNC,
+        title: "expected a function",
+        explanation: "A program is a list of functions, and every function starts with the 'func' keyword.\nStatements cannot appear outside of a function.",
+        example: Example::Source,
+        failing: "main() {\n  print(1);\n}",
+        fixed: "func main() {\n  print(1);\n}",
+    },
+    Entry {
+        code: codes::EXPECTED_FUNCTION_NAME,
+        title: "expected a function name",
+        explanation: "'func' must be followed by the name of the function.",
+        example: Example::Source,
+        failing: "func (int a) {\n  return a;\n}\n\nfunc main() {\n}",
+        fixed: "func id(int a) {\n  return a;\n}\n\nfunc main() {\n  print(id(1));\n}",
+    },
+    Entry {
+        code: codes::INVALID_PARAMETERS,
+        title: "invalid parameter list",
+        explanation: "Function parameters are written as 'int name' and separated by commas.",
+        example: Example::Source,
+        failing: "func add(a, b) {\n  return a + b;\n}\n\nfunc main() {\n  print(add(1, 2));\n}",
+        fixed: "func add(int a, int b) {\n  return a + b;\n}\n\nfunc main() {\n  print(add(1, 2));\n}",
+    },
+    Entry {
+        code: codes::MISSING_PAREN,
+        title: "missing '(' or ')'",
+        explanation: "Function headers, calls, 'print' and 'read' need their parentheses,\nand every '(' in an expression must be closed by a ')'.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = 1;\n  print a;\n}",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::MISSING_CURLY,
+        title: "missing '{' or '}'",
+        explanation: "Function bodies, and the bodies of 'if', 'else' and 'while', are blocks surrounded by curly braces,\neven when they only contain a single statement.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = 1;\n  if a < 2 print(a);\n}",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  if a < 2 {\n    print(a);\n  }\n}",
+    },
+    Entry {
+        code: codes::MISSING_BRACKET,
+        title: "missing ']'",
+        explanation: "Array sizes and array indexes are written between square brackets, like 'int [4] array;' and 'array[0]'.",
+        example: Example::Source,
+        failing: "func main() {\n  int [4 array;\n}",
+        fixed: "func main() {\n  int [4] array;\n  array[0] = 2;\n  print(array[0]);\n}",
+    },
+    Entry {
+        code: codes::EXPECTED_IDENTIFIER,
+        title: "expected a variable name",
+        explanation: "A declaration needs the name of the variable after 'int' or 'int [size]'.",
+        example: Example::Source,
+        failing: "func main() {\n  int 5;\n}",
+        fixed: "func main() {\n  int a;\n  a = 5;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::UNEXPECTED_AFTER_IDENTIFIER,
+        title: "unexpected token after identifier",
+        explanation: "A statement that starts with a name must be an assignment such as 'a = 1;' or 'array[0] = 1;'.\nThis is also reported for misspelled keywords, such as 'whle' or 'pritn'.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = 1;\n  pritn(a);\n}",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::INVALID_STATEMENT,
+        title: "invalid statement",
+        explanation: "Statements are declarations, assignments, 'print', 'read', 'return', 'if', 'while', 'break' and 'continue'.\nAn expression on its own, or the left side of an assignment being a number, is not a statement.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  5 = a;\n}",
+        fixed: "func main() {\n  int a;\n  a = 5;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::INVALID_EXPRESSION,
+        title: "invalid expression",
+        explanation: "An expression is made of numbers, variables, array elements, function calls and parentheses,\njoined by + - * / %. An operator is missing one of its sides.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = * 2;\n}",
+        fixed: "func main() {\n  int a;\n  a = 3 * 2;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::EXPECTED_COMPARISON,
+        title: "expected a comparison",
+        explanation: "The condition of 'if' and 'while' must be a comparison using < <= > >= == or !=.\nA variable on its own is not a condition.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  a = 1;\n  if a {\n    print(a);\n  }\n}",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  if a != 0 {\n    print(a);\n  }\n}",
+    },
+    Entry {
+        code: codes::EXPECTED_IMPORT_PATH,
+        title: "expected the path of an import",
+        explanation: "'import' is followed by the path of a .tt file in double quotes, and a ';'.",
+        example: Example::Source,
+        failing: "import gcd;\n\nfunc main() {\n  print(gcd(12, 18));\n}",
+        fixed: "import \"examples_imports/gcd.tt\";\n\nfunc main() {\n  print(gcd(12, 18));\n}",
+    },
+    Entry {
+        code: codes::UNDECLARED_VARIABLE,
+        title: "use of an undeclared variable",
+        explanation: "Every variable must be declared with 'int' in the function that uses it before it is used.\nVariables of other functions are not visible.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  int b;\n  a = 100;\n  b = 50;\n  c = a + b;\n}",
+        fixed: "func main() {\n  int a;\n  int b;\n  int c;\n  a = 100;\n  b = 50;\n  c = a + b;\n  print(c);\n}",
+    },
+    Entry {
+        code: codes::UNDEFINED_FUNCTION,
+        title: "call to an undefined function",
+        explanation: "Only functions defined somewhere in the program with 'func' can be called.",
+        example: Example::Source,
+        failing: "func add(int a, int b) {\n  return a + b;\n}\n\nfunc main() {\n  int a;\n  a = error(1);\n}",
+        fixed: "func add(int a, int b) {\n  return a + b;\n}\n\nfunc main() {\n  int a;\n  a = add(1, 2);\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::MISSING_MAIN,
+        title: "main function not defined",
+        explanation: "Programs start running at 'main', so every program needs a function called main.",
+        example: Example::Source,
+        failing: "func notmain() {\n}",
+        fixed: "func main() {\n}",
+    },
+    Entry {
+        code: codes::DUPLICATE_FUNCTION,
+        title: "function defined more than once",
+        explanation: "Two functions have the same name. Function names must be unique in a program.",
+        example: Example::Source,
+        failing: "func f() {\n  return 1;\n}\n\nfunc f() {\n  return 2;\n}\n\nfunc main() {\n  print(f());\n}",
+        fixed: "func f() {\n  return 1;\n}\n\nfunc g() {\n  return 2;\n}\n\nfunc main() {\n  print(f());\n  print(g());\n}",
+    },
+    Entry {
+        code: codes::DUPLICATE_PARAMETER,
+        title: "parameter declared more than once",
+        explanation: "Two parameters of the same function have the same name.",
+        example: Example::Source,
+        failing: "func add(int a, int a) {\n  return a + a;\n}\n\nfunc main() {\n  print(add(1, 2));\n}",
+        fixed: "func add(int a, int b) {\n  return a + b;\n}\n\nfunc main() {\n  print(add(1, 2));\n}",
+    },
+    Entry {
+        code: codes::DUPLICATE_VARIABLE,
+        title: "variable declared more than once",
+        explanation: "A variable can only be declared once per function, even inside different blocks.\nThis also applies to a variable with the same name as a parameter.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  int a;\n}",
+        fixed: "func main() {\n  int a;\n  int b;\n  a = 1;\n  b = a;\n  print(b);\n}",
+    },
+    Entry {
+        code: codes::INVALID_ARRAY_SIZE,
+        title: "array size must be greater than zero",
+        explanation: "Arrays have a fixed size, given when they are declared, and it must be at least 1.",
+        example: Example::Source,
+        failing: "func main() {\n  int [0] array;\n}",
+        fixed: "func main() {\n  int [8] array;\n  array[0] = 1;\n  print(array[0]);\n}",
+    },
+    Entry {
+        code: codes::NOT_AN_ARRAY,
+        title: "integer used as an array",
+        explanation: "Only variables declared as arrays with 'int [size] name' can be indexed.",
+        example: Example::Source,
+        failing: "func main() {\n  int number;\n  number[0] = 100;\n}",
+        fixed: "func main() {\n  int [1] number;\n  number[0] = 100;\n  print(number[0]);\n}",
+    },
+    Entry {
+        code: codes::ARRAY_AS_INTEGER,
+        title: "array used as an integer",
+        explanation: "An array holds many integers, so it cannot be assigned, printed or computed with as a whole.\nUse an element such as 'array[0]' instead.",
+        example: Example::Source,
+        failing: "func main() {\n  int [8] array;\n  array = 8;\n}",
+        fixed: "func main() {\n  int [8] array;\n  array[0] = 8;\n  print(array[0]);\n}",
+    },
+    Entry {
+        code: codes::MISSING_RETURN,
+        title: "function does not return a value on every path",
+        explanation: "A function that returns a value with 'return' somewhere must return a value on every path,\notherwise the caller would silently get 0.\nFunctions without any 'return' statement do not return a value and are not affected.",
+        example: Example::Source,
+        failing: "func sign(int a) {\n  if a < 0 {\n    return 0;\n  }\n}\n\nfunc main() {\n  print(sign(5));\n}",
+        fixed: "func sign(int a) {\n  if a < 0 {\n    return 0;\n  }\n  return 1;\n}\n\nfunc main() {\n  print(sign(5));\n}",
+    },
+    Entry {
+        code: codes::TYPE_MISMATCH,
+        title: "int and long mixed",
+        explanation: "An int and a long are never mixed implicitly, not in assignments, arithmetic, comparisons,\narguments or return values. Convert with 'long(value)', which always fits,\nor 'int(value)', which overflows like arithmetic does when the long is too big.",
+        example: Example::Source,
+        failing: "func main() {\n  long big;\n  int small;\n  small = 5;\n  big = small * 1000000;\n  print(big);\n}",
+        fixed: "func main() {\n  long big;\n  int small;\n  small = 5;\n  big = long(small) * 1000000;\n  print(big);\n}",
+    },
+    Entry {
+        code: codes::INVALID_IR,
+        title: "invalid IR instruction",
+        explanation: "The IR could not be read: an instruction has an unknown opcode or the wrong operands.\nThe compiler never generates this; it comes from hand written or foreign IR.",
+        example: Example::Ir,
+        failing: "%func main\n%int a\n%move a, 1\n%endfunc",
+        fixed: "%func main\n%int a\n%mov a, 1\n%out a\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_DUPLICATE_FUNCTION,
+        title: "IR function defined more than once",
+        explanation: "Two '%func' blocks in the IR have the same name.",
+        example: Example::Ir,
+        failing: "%func main\n%endfunc\n%func main\n%endfunc",
+        fixed: "%func main\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_MISSING_MAIN,
+        title: "IR has no main function",
+        explanation: "The interpreter starts running at '%func main', so the IR must define it.",
+        example: Example::Ir,
+        failing: "%func start\n%out 1\n%endfunc",
+        fixed: "%func main\n%out 1\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_PARAMETER_COUNT,
+        title: "wrong number of arguments in a call",
+        explanation: "A '%call' passes a different number of arguments than the called function has parameters.",
+        example: Example::Ir,
+        failing: "%func add(%int a, %int b)\n%int c\n%add c, a, b\n%ret c\n%endfunc\n%func main\n%int r\n%call r, add(1)\n%endfunc",
+        fixed: "%func add(%int a, %int b)\n%int c\n%add c, a, b\n%ret c\n%endfunc\n%func main\n%int r\n%call r, add(1, 2)\n%out r\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_UNDECLARED_FUNCTION,
+        title: "IR calls an undeclared function",
+        explanation: "A '%call' names a function that is not defined anywhere in the IR.",
+        example: Example::Ir,
+        failing: "%func main\n%int r\n%call r, missing()\n%endfunc",
+        fixed: "%func one\n%ret 1\n%endfunc\n%func main\n%int r\n%call r, one()\n%out r\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_INVALID_LABEL,
+        title: "undefined or duplicate label",
+        explanation: "A jump or branch targets a label that is not declared in the same function,\nor the same label is declared twice.",
+        example: Example::Ir,
+        failing: "%func main\n%jmp :end\n%endfunc",
+        fixed: "%func main\n%jmp :end\n:end\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_UNDECLARED_IDENTIFIER,
+        title: "IR uses an undeclared identifier",
+        explanation: "Every IR variable must be declared with '%int', '%long' or '%int[]', or be a parameter, before it is used.",
+        example: Example::Ir,
+        failing: "%func main\n%mov a, 1\n%endfunc",
+        fixed: "%func main\n%int a\n%mov a, 1\n%out a\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_TYPE_MISMATCH,
+        title: "IR mixes up arrays and integers, or ints and longs",
+        explanation: "An array was used where an integer is expected, or the other way around.\nArrays can only be accessed through '[array + index]'.\nAn instruction, a call or a return mixes '%int' and '%long' values, convert them with '%widen' or '%narrow'.",
+        example: Example::Ir,
+        failing: "%func main\n%int[] array, 4\n%out array\n%endfunc",
+        fixed: "%func main\n%int[] array, 4\n%int a\n%mov a, [array + 0]\n%out a\n%endfunc",
+    },
+    Entry {
+        code: codes::IR_DUPLICATE_DECLARATION,
+        title: "IR identifier declared more than once",
+        explanation: "An IR variable or parameter is declared twice in the same function.",
+        example: Example::Ir,
+        failing: "%func main\n%int a\n%int a\n%endfunc",
+        fixed: "%func main\n%int a\n%endfunc",
+    },
+    Entry {
+        code: codes::DIVIDE_BY_ZERO,
+        title: "division by zero",
+        explanation: "The program divided by zero, or took the remainder of a division by zero, while running.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  int b;\n  a = 10;\n  b = 0;\n  a = a / b;\n  print(a);\n}",
+        fixed: "func main() {\n  int a;\n  int b;\n  a = 10;\n  b = 0;\n  if b != 0 {\n    a = a / b;\n  }\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::ARRAY_OUT_OF_BOUNDS,
+        title: "array index out of bounds",
+        explanation: "The program read or wrote an array element with an index below 0 or not smaller than the array size.\nAn array declared as 'int [4] array' has the elements array[0] to array[3].",
+        example: Example::Source,
+        failing: "func main() {\n  int [4] array;\n  array[4] = 1;\n}",
+        fixed: "func main() {\n  int [4] array;\n  array[3] = 1;\n  print(array[3]);\n}",
+    },
+    Entry {
+        code: codes::INVALID_BRANCH_VALUE,
+        title: "branch on a value that is not 0 or 1",
+        explanation: "'%branch_if' and '%branch_ifn' only accept 0 (false) and 1 (true).\nThe compiler always branches on the result of a comparison; hand written IR may not.",
+        example: Example::Ir,
+        failing: "%func main\n%branch_if 2, :end\n:end\n%endfunc",
+        fixed: "%func main\n%int t\n%neq t, 2, 0\n%branch_if t, :end\n:end\n%endfunc",
+    },
+    Entry {
+        code: codes::CALL_DEPTH_EXCEEDED,
+        title: "maximum call depth exceeded",
+        explanation: "More calls were running at once than the interpreter allows, 10000 unless '--max-call-depth' says otherwise.\nThis is usually a recursive function that never reaches the case where it stops calling itself.\nThe error lists the chain of calls that led there.",
+        example: Example::Source,
+        failing: "func down(int n) {\n  return down(n - 1);\n}\n\nfunc main() {\n  print(down(10));\n}",
+        fixed: "func down(int n) {\n  if n == 0 {\n    return 0;\n  }\n  return down(n - 1);\n}\n\nfunc main() {\n  print(down(10));\n}",
+    },
+    Entry {
+        code: codes::INSTRUCTION_LIMIT_EXCEEDED,
+        title: "instruction limit exceeded",
+        explanation: "The program ran more instructions of the IR than '--max-instructions' allows.\nThis is usually a while loop whose condition never becomes false.",
+        example: Example::Source,
+        failing: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    print(i);\n  }\n}",
+        fixed: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    print(i);\n    i = i + 1;\n  }\n}",
+    },
+    Entry {
+        code: codes::MEMORY_LIMIT_EXCEEDED,
+        title: "array memory limit exceeded",
+        explanation: "The arrays of the calls running at once took more bytes than '--max-memory' allows, 4 bytes for every element.\nEvery call of a function has arrays of its own, so a recursive function with arrays adds up quickly.",
+        example: Example::Source,
+        failing: "func main() {\n  int [1000000] squares;\n  squares[999] = 999 * 999;\n  print(squares[999]);\n}",
+        fixed: "func main() {\n  int [1000] squares;\n  squares[999] = 999 * 999;\n  print(squares[999]);\n}",
+    },
+    Entry {
+        code: codes::OUTPUT_LIMIT_EXCEEDED,
+        title: "output limit exceeded",
+        explanation: "The program printed more bytes than '--max-output' allows, counting the newline after every number.",
+        example: Example::Source,
+        failing: "func main() {\n  int i;\n  i = 0;\n  while i < 1000000 {\n    print(i);\n    i = i + 1;\n  }\n}",
+        fixed: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    print(i);\n    i = i + 1;\n  }\n}",
+    },
+    Entry {
+        code: codes::INTEGER_OVERFLOW,
+        title: "integer overflow",
+        explanation: "With '--overflow trap', the result of '+', '-', '*' or '/' did not fit in an int, -2147483648 to 2147483647.\nBy default it wraps around instead, and '--overflow saturate' makes it the closest int.\nThe only division that overflows is -2147483648 / -1.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  int i;\n  a = 1;\n  i = 0;\n  while i < 40 {\n    a = a * 2;\n    i = i + 1;\n  }\n  print(a);\n}",
+        fixed: "func main() {\n  int a;\n  int i;\n  a = 1;\n  i = 0;\n  while i < 30 {\n    a = a * 2;\n    i = i + 1;\n  }\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::END_OF_INPUT,
+        title: "read past the end of the input",
+        explanation: "'read' needs a line with a number, but the input ended: the file given with '--input', or stdin.\nA program that reads until some number comes, such as a 0, fails when the input does not have it.\nReading first how many numbers follow avoids that.",
+        example: Example::Source,
+        failing: "func main() {\n  int x;\n  int sum;\n  sum = 0;\n  x = 1;\n  while x != 0 {\n    read(x);\n    sum = sum + x;\n  }\n  print(sum);\n}",
+        fixed: "func main() {\n  int n;\n  int x;\n  int sum;\n  read(n);\n  sum = 0;\n  while n > 0 {\n    read(x);\n    sum = sum + x;\n    n = n - 1;\n  }\n  print(sum);\n}",
+    },
+    Entry {
+        code: codes::UNINITIALIZED,
+        title: "variable may be used before it is assigned",
+        explanation: "On some path through the function, the variable is read before any value was assigned to it.\nIt will read as 0, which is rarely what was meant.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  int b;\n  b = a + 1;\n  print(b);\n}",
+        fixed: "func main() {\n  int a;\n  int b;\n  a = 0;\n  b = a + 1;\n  print(b);\n}",
+    },
+    Entry {
+        code: codes::UNUSED_VARIABLE,
+        title: "unused variable",
+        explanation: "The variable is declared, and maybe assigned, but its value is never read.\nSilence it with '-A unused-variable:NAME'.",
+        example: Example::Source,
+        failing: "func main() {\n  int a;\n  int b;\n  a = 1;\n  b = 2;\n  print(a);\n}",
+        fixed: "func main() {\n  int a;\n  a = 1;\n  print(a);\n}",
+    },
+    Entry {
+        code: codes::UNUSED_PARAMETER,
+        title: "unused parameter",
+        explanation: "The parameter is never read inside the function.\nSilence it with '-A unused-parameter:NAME'.",
+        example: Example::Source,
+        failing: "func first(int a, int b) {\n  return a;\n}\n\nfunc main() {\n  print(first(1, 2));\n}",
+        fixed: "func first(int a) {\n  return a;\n}\n\nfunc main() {\n  print(first(1));\n}",
+    },
+    Entry {
+        code: codes::UNUSED_FUNCTION,
+        title: "unused function",
+        explanation: "The function is never called from main, directly or through other functions.\nSilence it with '-A unused-function:NAME'.",
+        example: Example::Source,
+        failing: "func helper() {\n  return 1;\n}\n\nfunc main() {\n  print(2);\n}",
+        fixed: "func helper() {\n  return 1;\n}\n\nfunc main() {\n  print(helper());\n  print(2);\n}",
+    },
+    Entry {
+        code: codes::UNREACHABLE_CODE,
+        title: "unreachable code",
+        explanation: "The statements come right after a 'return', 'break' or 'continue', so they never run.",
+        example: Example::Source,
+        failing: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    break;\n    print(i);\n  }\n}",
+        fixed: "func main() {\n  int i;\n  i = 0;\n  while i < 10 {\n    print(i);\n    break;\n  }\n}",
+    },
+];
+
+#[cfg(test)]
+mod catalog_tests {
+    use crate::catalog::*;
+    use crate::diagnostics::Diagnostic;
+    use crate::compile_to_ir;
+
+    // compiles a program, returning the first error or the codes of all warnings.
+    fn compile(code: &str) -> Result<Vec<&'static str>, Diagnostic> {
+        match compile_to_ir(code) {
+            Ok(compiled) => Ok(compiled.warnings.iter().map(|w| w.kind.code()).collect()),
+            Err(errors) => Err(errors.into_iter().next().unwrap()),
+        }
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        for (i, entry) in CATALOG.iter().enumerate() {
+            assert!(CATALOG[i + 1..].iter().all(|other| other.code != entry.code), "{}", entry.code);
+            assert!(lookup(&entry.code.to_lowercase()).is_some());
+        }
+    }
+
+    #[test]
+    fn source_examples() {
+        for entry in CATALOG {
+            if !matches!(entry.example, Example::Source) {
+                continue;
+            }
+            let fixed = compile(entry.fixed);
+            assert!(fixed.as_ref().is_ok_and(|w| !w.contains(&entry.code)), "{}: fixed example reports {:?}", entry.code, fixed);
+
+            let failing = compile(entry.failing);
+            if entry.code.starts_with('W') {
+                assert!(failing.as_ref().is_ok_and(|w| w.contains(&entry.code)), "{}: failing example reports {:?}", entry.code, failing);
+            } else if entry.code.starts_with("E04") {
+                // runtime errors compile fine.
+                assert!(failing.is_ok(), "{}: failing example reports {:?}", entry.code, failing);
+            } else {
+                assert!(failing.as_ref().is_err_and(|e| e.code == entry.code), "{}: failing example reports {:?}", entry.code, failing);
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+// the debugger: 'rustcompiler debug <file>'.
+// runs a program an instruction at a time and stops where it is told to, reading commands from a prompt:
+//
+//   main, line 8 (IR line 16): int [3] values;
+//   (debug) break 14
+//   (debug) continue
+//   Breakpoint. main, line 14 (IR line 31): total = add(total, i);
+//   (debug) print total
+//   total = 0
+//
+// lines are lines of the source when the IR has the marks of parse_with_source_map(), and lines of the IR otherwise.
+// the output of the program goes where the prompt goes, and what it reads is the next line after the last command.
+
+use std::cell::RefCell;
+use std::io::{self, BufRead, Read};
+use std::rc::Rc;
+
+use crate::diagnostics::Diagnostic;
+use crate::interpreter::{self, FunctionBytecode, Limits, Local, Location, Vm};
+
+const HELP: &str = "\
+break <line>     stop when the program gets to a line
+break :<label>   stop at a label of the IR, such as ':loopbegin1'
+delete <where>   remove the breakpoint at a line or label
+breakpoints      list the breakpoints
+step             run to the next line, going into calls
+next             run to the next line, stepping over calls
+finish           run until the current call returns
+continue         run to the next breakpoint, or to the end of the program
+print <name>     show a variable of the current call
+locals           show the variables of the current call
+backtrace        show the calls that are running, innermost first
+quit             stop the program
+an empty line repeats the last command.
+";
+
+// the program reads the lines the commands come from, and writes where the debugger does.
+type ProgramVm<'a> = Vm<'a, Lines<'a>, &'a mut dyn io::Write>;
+
+// the lines the commands are read from, handed to '%input' as the program asks for them.
+struct Lines<'a> {
+    lines: Rc<RefCell<dyn Iterator<Item = String> + 'a>>,
+    // the line being read, and how much of it was.
+    line: Vec<u8>,
+    read: usize,
+}
+
+impl Read for Lines<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Lines<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.read == self.line.len() {
+            // a line is one number for '%input', whether or not it came with its newline.
+            let line = self.lines.borrow_mut().next().map(|line| line.trim_end_matches('\n').to_string() + "\n");
+            self.line = line.map(String::into_bytes).unwrap_or_default();
+            self.read = 0;
+        }
+        Ok(&self.line[self.read..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.read += amount;
+    }
+}
+
+enum Resume {
+    Step,
+    Next,
+    Finish,
+    Continue,
+}
+
+struct Debugger<'a> {
+    calls: &'a [FunctionBytecode],
+    // the lines of the program, to show where it stopped. None when the lines are not those of one file.
+    source: Option<Vec<&'a str>>,
+    ir: Vec<&'a str>,
+    // a line or a label as it was given to 'break', and the function and instruction of every place it stops at.
+    breakpoints: Vec<(String, Vec<(&'a str, usize)>)>,
+}
+
+// runs IR under the debugger, reading commands until the program ends or is quit.
+// the lines of commands are also where the program reads its input, so nothing but them reads stdin.
+// returns what main returned, or None when the program was stopped before it did.
+pub fn run(ir: &str, source: Option<&str>, limits: &Limits, commands: impl Iterator<Item = String>, out: &mut impl io::Write) -> Result<Option<i32>, Box<Diagnostic>> {
+    let calls = interpreter::load(ir)?;
+    let commands = Rc::new(RefCell::new(commands));
+    let input = Lines { lines: commands.clone(), line: vec![], read: 0 };
+    let mut vm = Vm::new(input, out as &mut dyn io::Write, &calls, limits)?;
+    let mut debugger = Debugger { calls: &calls, source: source.map(|source| source.lines().collect()), ir: ir.lines().collect(), breakpoints: vec![] };
+
+    let mut text = format!("Debugging. 'help' lists the commands.\n{}", debugger.position(&vm));
+    let mut last = String::new();
+    loop {
+        // the output is only for the user, there is nothing to do when it cannot be written.
+        let _ = write!(vm.output(), "{text}(debug) ");
+        let _ = vm.output().flush();
+        let Some(line) = commands.borrow_mut().next() else {
+            return Ok(None);
+        };
+        let line = match line.trim() {
+            "" => last.clone(),
+            line => line.to_string(),
+        };
+        last = line.clone();
+
+        let (command, argument) = line.split_once(' ').map(|(c, a)| (c, a.trim())).unwrap_or((&line, ""));
+        let resume = match command {
+            "s" | "step" => Resume::Step,
+            "n" | "next" => Resume::Next,
+            "f" | "finish" => Resume::Finish,
+            "c" | "continue" => Resume::Continue,
+            "q" | "quit" => return Ok(None),
+            _ => {
+                text = match command {
+                    "b" | "break" => debugger.add_breakpoint(argument),
+                    "d" | "delete" => debugger.delete_breakpoint(argument),
+                    "breakpoints" => debugger.breakpoints.iter().map(|(place, _)| format!("{place}\n")).collect(),
+                    "p" | "print" => print(&vm, argument),
+                    "locals" => print(&vm, ""),
+                    "bt" | "backtrace" => vm.backtrace().iter().enumerate().map(|(i, call)| format!("#{i} {call}\n")).collect(),
+                    "h" | "help" => String::from(HELP),
+                    _ => format!("Unknown command '{command}'. 'help' lists the commands.\n"),
+                };
+                continue;
+            }
+        };
+
+        if let Some(value) = debugger.resume(&mut vm, resume)? {
+            let _ = writeln!(vm.output(), "The program returned {value}.");
+            return Ok(Some(value));
+        }
+        text = debugger.position(&vm);
+        if debugger.at_breakpoint(&vm) {
+            text = format!("Breakpoint. {text}");
+        }
+    }
+}
+
+impl<'a> Debugger<'a> {
+    // runs until the program gets where it was told to go, a breakpoint or the end of main.
+    fn resume(&self, vm: &mut ProgramVm, how: Resume) -> Result<Option<i32>, Box<Diagnostic>> {
+        let depth = vm.depth();
+        let (function, instruction) = vm.position(0);
+        let start = line(function.location(instruction));
+        loop {
+            if let Some(value) = vm.step()? {
+                return Ok(Some(value));
+            }
+            let (function, instruction) = vm.position(0);
+            let now = vm.depth();
+            // a while loop goes back to the line of the while, which counts as the same line.
+            let new_line = function.starts_line(instruction) && (now != depth || line(function.location(instruction)) != start);
+            let stop = match how {
+                Resume::Step => new_line,
+                Resume::Next => now <= depth && new_line,
+                Resume::Finish => now < depth,
+                Resume::Continue => false,
+            };
+            if stop || self.at_breakpoint(vm) {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn at_breakpoint(&self, vm: &ProgramVm) -> bool {
+        let (function, instruction) = vm.position(0);
+        self.breakpoints.iter().any(|(_, places)| places.contains(&(function.name(), instruction)))
+    }
+
+    // where the program is, and the line it is at.
+    fn position(&self, vm: &ProgramVm) -> String {
+        let (function, instruction) = vm.position(0);
+        let location = function.location(instruction);
+        let text = match (location.source, &self.source) {
+            (Some(line), Some(source)) => source.get(line - 1),
+            _ => self.ir.get(location.ir - 1),
+        };
+        format!("{}, {}: {}\n", function.name(), location, text.map_or("", |text| text.trim()))
+    }
+
+    fn add_breakpoint(&mut self, place: &str) -> String {
+        if self.breakpoints.iter().any(|(p, _)| p == place) {
+            return format!("There already is a breakpoint at {place}.\n");
+        }
+        let places: Vec<(&'a str, usize)> = if place.starts_with(':') {
+            self.calls.iter().filter_map(|function| Some((function.name(), function.label(place)?))).collect()
+        } else {
+            let Ok(line) = place.parse::<usize>() else {
+                return format!("Expected a line, or a label such as ':loopbegin1', not '{place}'.\n");
+            };
+            self.calls.iter().filter_map(|function| Some((function.name(), function.line(line)?))).collect()
+        };
+        if places.is_empty() {
+            return format!("There is no code at {place}.\n");
+        }
+        self.breakpoints.push((place.to_string(), places));
+        format!("Breakpoint at {place}.\n")
+    }
+
+    fn delete_breakpoint(&mut self, place: &str) -> String {
+        match self.breakpoints.iter().position(|(p, _)| p == place) {
+            Some(i) => {
+                self.breakpoints.remove(i);
+                format!("Deleted the breakpoint at {place}.\n")
+            }
+            None => format!("There is no breakpoint at {place}.\n"),
+        }
+    }
+}
+
+// the line of source of an instruction, or its line of IR when the IR has no marks.
+fn line(location: Location) -> usize {
+    location.source.unwrap_or(location.ir)
+}
+
+// one variable of the current call, or every one but the temporaries of the compiler when no name is given.
+fn print(vm: &ProgramVm, name: &str) -> String {
+    let value = |local: &Local| match local {
+        Local::Int(value) => value.to_string(),
+        Local::Long(value) => value.to_string(),
+        Local::Array(values) => format!("{values:?}"),
+    };
+    let locals = vm.locals(0);
+    if name.is_empty() {
+        return locals.iter().filter(|(name, _)| !name.starts_with("_temp")).map(|(name, local)| format!("{name} = {}\n", value(local))).collect();
+    }
+    match locals.iter().find(|(n, _)| n == name) {
+        Some((_, local)) => format!("{name} = {}\n", value(local)),
+        None => format!("There is no variable '{name}' in {}.\n", vm.position(0).0.name()),
+    }
+}
+
+#[cfg(test)]
+mod debugger_tests {
+    use crate::compile_to_ir;
+    use crate::debugger::*;
+
+    const SOURCE: &str = "func add(int a, int b) {
+    int s;
+    s = a + b;
+    return s;
+}
+
+func main() {
+    int [3] values;
+    int i;
+    int total;
+    i = 0;
+    while i < 3 {
+        values[i] = i;
+        total = add(total, i);
+        i = i + 1;
+    }
+    return total;
+}
+";
+
+    // runs SOURCE in the debugger with commands, returning what it printed and what main returned.
+    fn debug(commands: &[&str]) -> (String, Option<i32>) {
+        let ir = compile_to_ir(SOURCE).unwrap().ir;
+        let mut out = vec![];
+        let returned = run(&ir, Some(SOURCE), &Limits::default(), commands.iter().map(|c| c.to_string()), &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), returned)
+    }
+
+    #[test]
+    fn breakpoints_and_variables() {
+        let (out, returned) = debug(&["break 14", "c", "print i", "c", "locals", "delete 14", "break :endloop_1", "c", "p values", "bt", "c"]);
+        assert!(returned == Some(3));
+        let stops: Vec<&str> = out.lines().filter(|line| line.starts_with("(debug) Breakpoint. ")).collect();
+        assert!(stops.len() == 3 && stops[0].ends_with("main, line 14 (IR line 31): total = add(total, i);"));
+        assert!(out.contains("i = 0\n") && out.contains("i = 1\ntotal = 0\nvalues = [0, 1, 0]\n"));
+        // the end of the loop belongs to the line of the while.
+        assert!(stops[2].ends_with("main, line 12 (IR line 40): while i < 3 {"));
+        assert!(out.contains("values = [0, 1, 2]\n(debug) #0 in main, line 12 (IR line 40)\n"));
+        assert!(out.ends_with("The program returned 3.\n"));
+    }
+
+    #[test]
+    fn stepping() {
+        let (out, returned) = debug(&["break 14", "c", "step", "", "finish", "next", "next", "next", "quit"]);
+        assert!(returned.is_none());
+        let lines: Vec<&str> = out.lines().filter_map(|line| line.strip_prefix("(debug) ")).collect();
+        // into add, a line further in add, back to main in the middle of line 14, then over the rest of the loop.
+        assert!(lines[2].starts_with("add, line 2 ") && lines[3].starts_with("add, line 3 "));
+        assert!(lines[4].starts_with("main, line 14 ") && lines[5].starts_with("main, line 15 "));
+        assert!(lines[6].starts_with("main, line 12 ") && lines[7].starts_with("main, line 13 "));
+
+        let (out, _) = debug(&["break 40", "break x", "break :nowhere", "print nothing", "jump"]);
+        assert!(out.contains("There is no code at 40.") && out.contains("not 'x'") && out.contains("There is no code at :nowhere."));
+        assert!(out.contains("There is no variable 'nothing' in main.") && out.contains("Unknown command 'jump'."));
+    }
+
+    #[test]
+    fn input_is_the_line_after_the_command() {
+        let source = "func main() {\n    int x;\n    read(x);\n    print(x);\n    return x * 2;\n}\n";
+        let ir = compile_to_ir(source).unwrap().ir;
+        let mut out = vec![];
+        let lines = ["continue", "21"].iter().map(|line| line.to_string());
+        assert!(run(&ir, Some(source), &Limits::default(), lines, &mut out) == Ok(Some(42)));
+        // what the program prints goes to the same place as the prompt.
+        assert!(String::from_utf8(out).unwrap().ends_with("(debug) 21\nThe program returned 42.\n"));
+    }
+}
@@ -0,0 +1,209 @@
+// diagnostics reported by the compiler.
+// every error carries a stable code from the catalog (see catalog.rs), so that
+// 'rustcompiler --explain E0101' can tell the user more about it.
+
+use std::fmt;
+
+use crate::json::Value;
+
+// where in the source a diagnostic points to, as byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    // the file the error is in, for programs of several files. see imports.rs.
+    pub file: Option<String>,
+    // for a runtime error, the calls that were running and where, innermost first.
+    pub trace: Vec<String>,
+    // the line of source a runtime error happened on, or a semantic error was found on, when the IR has the marks of the lines.
+    pub line: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            code,
+            message: message.into(),
+            span: None,
+            file: None,
+            trace: vec![],
+            line: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn in_file(mut self, file: &str) -> Diagnostic {
+        self.file = Some(file.to_string());
+        self
+    }
+}
+
+// 1-based line and column of a byte offset.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(newline) => before[newline + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+    (line, column)
+}
+
+// the span of a line of source, without the indentation and the newline.
+pub fn line_span(source: &str, line: usize) -> Option<Span> {
+    let start: usize = source.split_inclusive('\n').take(line.checked_sub(1)?).map(str::len).sum();
+    let text = source[start..].lines().next()?;
+    let indent = text.len() - text.trim_start().len();
+    Some(Span { start: start + indent, end: start + text.trim_end().len() })
+}
+
+// one diagnostic as a JSON object, for '--diagnostics=json':
+// {"code":"E0201","severity":"error","message":"...","file":"a.tt",
+//  "span":{"line":6,"column":3,"end_line":6,"end_column":4}}
+// span is null when the diagnostic does not point into the source.
+pub fn to_json(code: &str, severity: &str, message: &str, file: &str, span: Option<Span>, source: &str) -> Value {
+    let span = match span {
+        Some(span) => {
+            let (line, column) = line_col(source, span.start);
+            let (end_line, end_column) = line_col(source, span.end);
+            Value::object(vec![
+                ("line", Value::Num(line as i64)),
+                ("column", Value::Num(column as i64)),
+                ("end_line", Value::Num(end_line as i64)),
+                ("end_column", Value::Num(end_column as i64)),
+            ])
+        }
+        None => Value::Null,
+    };
+    Value::object(vec![
+        ("code", Value::str(code)),
+        ("severity", Value::str(severity)),
+        ("message", Value::str(message)),
+        ("file", Value::str(file)),
+        ("span", span),
+    ])
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "Error[{}] in {}: {}", self.code, file, self.message),
+            None => write!(f, "Error[{}]: {}", self.code, self.message),
+        }?;
+        for call in &self.trace {
+            write!(f, "\n  {call}")?;
+        }
+        Ok(())
+    }
+}
+
+// error codes.
+pub mod codes {
+    // E00xx: reading and lexing the source, and writing the output.
+    pub const CANNOT_READ_FILE: &str = "E0001";
+    pub const UNRECOGNIZED_SYMBOL: &str = "E0002";
+    pub const CANNOT_WRITE_FILE: &str = "E0003";
+    pub const UNTERMINATED_STRING: &str = "E0004";
+    pub const IMPORT_NOT_FOUND: &str = "E0005";
+    pub const IMPORT_CYCLE: &str = "E0006";
+    pub const NUMBER_TOO_BIG: &str = "E0007";
+
+    // E01xx: parsing.
+    pub const UNEXPECTED_END: &str = "E0100";
+    pub const BREAK_OUTSIDE_LOOP: &str = "E0101";
+    pub const CONTINUE_OUTSIDE_LOOP: &str = "E0102";
+    pub const MISSING_SEMICOLON: &str = "E0103";
+    pub const EXPECTED_FUNC: &str = "E0104";
+    pub const EXPECTED_FUNCTION_NAME: &str = "E0105";
+    pub const INVALID_PARAMETERS: &str = "E0106";
+    pub const MISSING_PAREN: &str = "E0107";
+    pub const MISSING_CURLY: &str = "E0108";
+    pub const MISSING_BRACKET: &str = "E0109";
+    pub const EXPECTED_IDENTIFIER: &str = "E0110";
+    pub const UNEXPECTED_AFTER_IDENTIFIER: &str = "E0111";
+    pub const INVALID_STATEMENT: &str = "E0112";
+    pub const INVALID_EXPRESSION: &str = "E0113";
+    pub const EXPECTED_COMPARISON: &str = "E0114";
+    pub const EXPECTED_IMPORT_PATH: &str = "E0115";
+
+    // E02xx: semantic checks.
+    pub const UNDECLARED_VARIABLE: &str = "E0201";
+    pub const UNDEFINED_FUNCTION: &str = "E0202";
+    pub const MISSING_MAIN: &str = "E0203";
+    pub const DUPLICATE_FUNCTION: &str = "E0204";
+    pub const DUPLICATE_PARAMETER: &str = "E0205";
+    pub const DUPLICATE_VARIABLE: &str = "E0206";
+    pub const INVALID_ARRAY_SIZE: &str = "E0207";
+    pub const NOT_AN_ARRAY: &str = "E0208";
+    pub const ARRAY_AS_INTEGER: &str = "E0209";
+    pub const MISSING_RETURN: &str = "E0210";
+    pub const TYPE_MISMATCH: &str = "E0211";
+
+    // E03xx: loading the IR.
+    pub const INVALID_IR: &str = "E0300";
+    pub const IR_DUPLICATE_FUNCTION: &str = "E0301";
+    pub const IR_MISSING_MAIN: &str = "E0302";
+    pub const IR_PARAMETER_COUNT: &str = "E0303";
+    pub const IR_UNDECLARED_FUNCTION: &str = "E0304";
+    pub const IR_INVALID_LABEL: &str = "E0305";
+    pub const IR_UNDECLARED_IDENTIFIER: &str = "E0306";
+    pub const IR_TYPE_MISMATCH: &str = "E0307";
+    pub const IR_DUPLICATE_DECLARATION: &str = "E0308";
+
+    // E04xx: running the program.
+    pub const DIVIDE_BY_ZERO: &str = "E0401";
+    pub const ARRAY_OUT_OF_BOUNDS: &str = "E0402";
+    pub const INVALID_BRANCH_VALUE: &str = "E0403";
+    pub const CALL_DEPTH_EXCEEDED: &str = "E0404";
+    pub const INSTRUCTION_LIMIT_EXCEEDED: &str = "E0405";
+    pub const MEMORY_LIMIT_EXCEEDED: &str = "E0406";
+    pub const OUTPUT_LIMIT_EXCEEDED: &str = "E0407";
+    pub const END_OF_INPUT: &str = "E0408";
+    pub const INTEGER_OVERFLOW: &str = "E0409";
+
+    // Wxxxx: warnings. these never stop compilation.
+    pub const UNINITIALIZED: &str = "W0001";
+    pub const UNUSED_VARIABLE: &str = "W0002";
+    pub const UNUSED_PARAMETER: &str = "W0003";
+    pub const UNUSED_FUNCTION: &str = "W0004";
+    pub const UNREACHABLE_CODE: &str = "W0005";
+}
+
+#[cfg(test)]
+mod diagnostics_tests {
+    use crate::diagnostics::*;
+
+    #[test]
+    fn lines_and_columns() {
+        let source = "func main() {\n  int a;\n}";
+        assert!(line_col(source, 0) == (1, 1));
+        assert!(line_col(source, 5) == (1, 6));
+        assert!(line_col(source, 16) == (2, 3));
+        assert!(line_col(source, source.len()) == (3, 2));
+
+        assert!(line_span(source, 2) == Some(Span { start: 16, end: 22 }));
+        assert!(line_span(source, 3) == Some(Span { start: 23, end: 24 }));
+        assert!(line_span(source, 0).is_none() && line_span(source, 4).is_none());
+    }
+
+    #[test]
+    fn json() {
+        let source = "func main() {\n  c = 1;\n}";
+        let value = to_json("E0201", "error", "Undeclared variable used: c", "a.tt", Some(Span { start: 16, end: 17 }), source);
+        assert!(value.to_string() == r#"{"code":"E0201","severity":"error","message":"Undeclared variable used: c","file":"a.tt","span":{"line":2,"column":3,"end_line":2,"end_column":4}}"#);
+
+        let value = to_json("W0004", "warning", "m", "a.tt", None, source);
+        assert!(value.to_string().ends_with(r#""span":null}"#));
+    }
+}
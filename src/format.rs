@@ -0,0 +1,291 @@
+// the formatter: 'rustcompiler fmt'.
+// prints a program back out in one layout, whatever indentation it was written with:
+// a statement per line, four spaces for every open '{', spaces around operators and after commas,
+// '} else {' on one line and a blank line between functions.
+// comments are kept where they were, on their own line or after code, and so is a single blank line between statements.
+//
+// the lexer throws comments and whitespace away, so they are picked up from the source between the tokens.
+
+use crate::diagnostics::Diagnostic;
+use crate::{lex_with_spans, parse_syntax, Token};
+
+const INDENT: &str = "    ";
+
+// what was found in the source between two tokens.
+enum Gap<'a> {
+    // the number of line breaks in a stretch of whitespace.
+    Space(usize),
+    Comment(&'a str),
+}
+
+struct Printer {
+    out: String,
+    // the line being built, without its indentation.
+    line: String,
+    depth: usize,
+    // a blank line goes before the next line written.
+    blank: bool,
+}
+
+impl Printer {
+    fn end_line(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        // no blank lines at the start of the file or of a block, nor at the end of a block.
+        if self.blank && !self.out.is_empty() && !self.out.ends_with("{\n") && !self.line.starts_with('}') {
+            self.out.push('\n');
+        }
+        self.blank = false;
+        for _ in 0..self.depth {
+            self.out += INDENT;
+        }
+        self.out += self.line.trim_end();
+        self.out.push('\n');
+        self.line.clear();
+    }
+
+    // a comment after code stays on the line of that code.
+    fn trailing_comment(&mut self, comment: &str) {
+        if !self.line.is_empty() {
+            self.line += "  ";
+            self.line += comment;
+            self.end_line();
+        } else if self.out.ends_with('\n') {
+            self.out.pop();
+            self.out += "  ";
+            self.out += comment;
+            self.out.push('\n');
+        } else {
+            self.own_line_comment(comment);
+        }
+    }
+
+    fn own_line_comment(&mut self, comment: &str) {
+        self.end_line();
+        self.line += comment;
+        self.end_line();
+    }
+
+    fn push(&mut self, text: &str, space: bool) {
+        if space && !self.line.is_empty() {
+            self.line.push(' ');
+        }
+        self.line += text;
+    }
+}
+
+// formats a program. its syntax has to be right, so that nothing is lost or moved around in a program the formatter
+// does not understand. it does not have to compile: a break outside of a loop is formatted like any other statement.
+pub fn format(source: &str) -> Result<String, Box<Diagnostic>> {
+    let (tokens, spans) = lex_with_spans(source)?;
+    parse_syntax(&tokens, &spans)?;
+
+    let mut printer = Printer { out: String::new(), line: String::new(), depth: 0, blank: false };
+    let mut end = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        comments(&mut printer, &source[end..spans[i].start]);
+        end = spans[i].end;
+
+        let previous = if printer.line.is_empty() { None } else { i.checked_sub(1).map(|i| &tokens[i]) };
+        match token {
+            Token::LeftCurly => {
+                printer.push("{", true);
+                printer.end_line();
+                printer.depth += 1;
+            }
+            Token::RightCurly => {
+                printer.end_line();
+                printer.depth = printer.depth.saturating_sub(1);
+                printer.push("}", false);
+                if !matches!(tokens.get(i + 1), Some(Token::Else)) {
+                    printer.end_line();
+                }
+                if printer.depth == 0 {
+                    printer.blank = true;
+                }
+            }
+            Token::Semicolon => {
+                printer.push(";", false);
+                printer.end_line();
+            }
+            _ => printer.push(&text(token), space_before(previous, token)),
+        }
+    }
+    comments(&mut printer, &source[end..]);
+    printer.end_line();
+    Ok(printer.out)
+}
+
+fn comments(printer: &mut Printer, between: &str) {
+    let mut breaks = 0;
+    for gap in gaps(between) {
+        match gap {
+            Gap::Space(n) => {
+                breaks = n;
+                // blank lines in the middle of a statement are dropped.
+                if n > 1 && printer.line.is_empty() {
+                    printer.blank = true;
+                }
+            }
+            Gap::Comment(comment) if breaks == 0 => printer.trailing_comment(comment),
+            Gap::Comment(comment) => printer.own_line_comment(comment),
+        }
+    }
+}
+
+// splits what lies between two tokens into whitespace and comments.
+fn gaps(mut between: &str) -> Vec<Gap<'_>> {
+    let mut gaps = vec![];
+    while !between.is_empty() {
+        let space = between.find('#').unwrap_or(between.len());
+        gaps.push(Gap::Space(between[..space].matches('\n').count()));
+        between = &between[space..];
+        if between.is_empty() {
+            break;
+        }
+        let comment = between.find('\n').unwrap_or(between.len());
+        gaps.push(Gap::Comment(between[..comment].trim_end()));
+        between = &between[comment..];
+    }
+    gaps
+}
+
+fn text(token: &Token) -> String {
+    let text = match token {
+        Token::Num(n) => return n.to_string(),
+        Token::Ident(name) => return name.clone(),
+        Token::Str(text) => return format!("\"{text}\""),
+        Token::Plus => "+",
+        Token::Subtract => "-",
+        Token::Multiply => "*",
+        Token::Divide => "/",
+        Token::Modulus => "%",
+        Token::Assign => "=",
+        Token::If => "if",
+        Token::While => "while",
+        Token::Read => "read",
+        Token::Func => "func",
+        Token::Return => "return",
+        Token::Int => "int",
+        Token::Long => "long",
+        Token::Print => "print",
+        Token::Else => "else",
+        Token::Break => "break",
+        Token::Continue => "continue",
+        Token::Import => "import",
+        Token::LeftParen => "(",
+        Token::RightParen => ")",
+        Token::LeftCurly => "{",
+        Token::RightCurly => "}",
+        Token::LeftBracket => "[",
+        Token::RightBracket => "]",
+        Token::Comma => ",",
+        Token::Semicolon => ";",
+        Token::Less => "<",
+        Token::LessEqual => "<=",
+        Token::Greater => ">",
+        Token::GreaterEqual => ">=",
+        Token::Equality => "==",
+        Token::NotEqual => "!=",
+        Token::NotToken => "",
+    };
+    text.to_string()
+}
+
+// whether a token is written with a space after the token before it on the same line.
+fn space_before(previous: Option<&Token>, token: &Token) -> bool {
+    match (previous, token) {
+        (None, _) => false,
+        (Some(Token::LeftParen | Token::LeftBracket), _) => false,
+        (_, Token::RightParen | Token::RightBracket | Token::Comma) => false,
+        // calls, conversions and indexing, but 'int [4] array' and 'if (a < b)'.
+        (Some(Token::Ident(_) | Token::Print | Token::Read | Token::Int | Token::Long), Token::LeftParen) => false,
+        (Some(Token::Ident(_)), Token::LeftBracket) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod format_tests {
+    use crate::format::*;
+    use crate::{compile_to_ir, lex};
+
+    const MESSY: &str = "import   \"lib.tt\" ;
+# adds two numbers
+func add(int a,int b){
+  return a+b;   # the sum
+}
+func main()
+{
+        int [4]array;
+  int x;x=add( 1 , 2 );
+
+
+  # print it
+  if x<=3 { print(x) ;}
+  else{
+     array[x]=x  *  2;
+  }
+  while x!=0 { x = x - 1; # count down
+  }
+
+}
+";
+
+    const FORMATTED: &str = "import \"lib.tt\";
+# adds two numbers
+func add(int a, int b) {
+    return a + b;  # the sum
+}
+
+func main() {
+    int [4] array;
+    int x;
+    x = add(1, 2);
+
+    # print it
+    if x <= 3 {
+        print(x);
+    } else {
+        array[x] = x * 2;
+    }
+    while x != 0 {
+        x = x - 1;  # count down
+    }
+}
+";
+
+    #[test]
+    fn canonical_layout() {
+        assert!(format(MESSY).unwrap() == FORMATTED);
+        assert!(format(FORMATTED).unwrap() == FORMATTED);
+        assert!(format("").unwrap().is_empty());
+        assert!(format("func main() {\n  x = ;\n}\n").is_err());
+        // a program that does not compile is formatted as long as its syntax is right.
+        assert!(format("func main() {\n  break ;\n}\n").unwrap() == "func main() {\n    break;\n}\n");
+    }
+
+    // the IR without the marks of source lines, which move with the formatting.
+    fn unmarked(ir: &str) -> Vec<&str> {
+        ir.lines().filter(|line| !line.starts_with(';')).collect()
+    }
+
+    // formatting only moves whitespace and comments, so every example compiles to the same IR.
+    #[test]
+    fn examples_keep_their_meaning() {
+        for dir in ["examples", "examples_phase2", "examples_phase3", "examples_phase4", "examples_imports"] {
+            for entry in std::fs::read_dir(format!("{}/{dir}", env!("CARGO_MANIFEST_DIR"))).unwrap() {
+                let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+                let Ok(formatted) = format(&source) else {
+                    continue;
+                };
+                assert!(format!("{:?}", lex(&source)) == format!("{:?}", lex(&formatted)));
+                assert!(format(&formatted).unwrap() == formatted);
+                if let Ok(compiled) = compile_to_ir(&source) {
+                    assert!(unmarked(&compile_to_ir(&formatted).unwrap().ir) == unmarked(&compiled.ir));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// programs in several files.
+// 'import "path.tt";' at the top level of a file makes the functions of another file callable,
+// the path is relative to the directory of the file with the import:
+//
+//   import "lib/gcd.tt";
+//
+//   func main() {
+//       print(gcd(12, 18));
+//   }
+//
+// every file is lexed and parsed on its own, after the files it imports so that it knows the types of their functions,
+// then the IR of all of them is checked and run as one program, with the functions of imported files before the functions of the files importing them.
+// a file imported several times is only read once, and a file that ends up importing itself is an error.
+// once a program has imports, every error and warning names the file it was found in.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::analysis::Analysis;
+use crate::diagnostics::{codes, Diagnostic, Span};
+use crate::{check, lex_with_spans, parse_importing, signatures, Compiled, Signature, Token};
+
+// one file of a program.
+struct File {
+    // the file as it was found on disk, to tell whether two imports are the same file.
+    key: PathBuf,
+    // the path as the user would write it, for diagnostics.
+    name: String,
+    ir: String,
+    // the functions it defines, and where their names are.
+    functions: Vec<(String, Span)>,
+}
+
+pub struct Program {
+    // imported files come before the files that import them, the file that was compiled is last.
+    files: Vec<File>,
+}
+
+impl Program {
+    // the IR of the whole program. once it has imports, the IR of every file starts with a mark naming it:
+    //   ; file lib/gcd.tt
+    // which names the file of a warning, and of a runtime error in its stack trace.
+    pub fn ir(&self) -> String {
+        if !self.has_imports() {
+            return self.files[0].ir.clone();
+        }
+        self.files.iter().map(|file| format!("; file {}\n{}", file.name, file.ir)).collect()
+    }
+
+    // whether the program is more than the file that was compiled.
+    pub fn has_imports(&self) -> bool {
+        self.files.len() > 1
+    }
+
+    // the semantic checks and the control flow analysis of the whole program, like lib.rs' check().
+    // check() names the file of everything it finds in the IR of a file, the rest is about the file that was compiled, such as a missing main.
+    pub fn check(&self) -> Analysis {
+        let mut analysis = check(&self.ir());
+        if self.has_imports() {
+            let root = &self.files[self.files.len() - 1].name;
+            for error in analysis.errors.iter_mut().filter(|error| error.file.is_none()) {
+                error.file = Some(root.clone());
+            }
+        }
+        analysis
+    }
+}
+
+// reads, lexes and parses a program and every file it imports.
+// path is where source was read from, or None for source that is not in a file, whose imports are relative to the current directory.
+pub fn load(path: Option<&Path>, source: &str) -> Result<Program, Box<Diagnostic>> {
+    let mut loader = Loader { files: vec![], loading: vec![], signatures: HashMap::new() };
+    let (key, name) = match path {
+        Some(path) => (fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()), path.display().to_string()),
+        None => (PathBuf::new(), String::from("<input>")),
+    };
+    let directory = path.and_then(Path::parent).unwrap_or(Path::new("")).to_path_buf();
+    loader.load(key, name, &directory, source)?;
+
+    let program = Program { files: loader.files };
+    if program.files.len() > 1 {
+        check_duplicates(&program)?;
+    }
+    Ok(program)
+}
+
+// lexes, parses and checks a program and the files it imports, like lib.rs' compile_to_ir().
+pub fn compile(path: Option<&Path>, source: &str) -> Result<Compiled, Vec<Diagnostic>> {
+    let program = load(path, source).map_err(|error| vec![*error])?;
+    let ir = program.ir();
+    let analysis = program.check();
+    if !analysis.errors.is_empty() {
+        return Err(analysis.errors);
+    }
+    Ok(Compiled { ir, warnings: analysis.warnings })
+}
+
+struct Loader {
+    files: Vec<File>,
+    // the files being loaded, each one imported by the one before it.
+    loading: Vec<(PathBuf, String)>,
+    // the functions of the files loaded so far.
+    signatures: HashMap<String, Signature>,
+}
+
+impl Loader {
+    fn load(&mut self, key: PathBuf, name: String, directory: &Path, source: &str) -> Result<(), Box<Diagnostic>> {
+        // errors only name their file once the program has more than one.
+        let in_file = |error: Diagnostic, imports: bool| if imports { error.in_file(&name) } else { error };
+        let nested = !self.loading.is_empty();
+        let (tokens, spans) = lex_with_spans(source).map_err(|error| in_file(error, nested))?;
+        let imports = imports(&tokens, &spans);
+        let nested = nested || !imports.is_empty();
+
+        self.loading.push((key.clone(), name.clone()));
+        for (path, span) in imports {
+            let imported = directory.join(&path);
+            let imported_name = imported.display().to_string();
+            let (imported_key, text) = match fs::canonicalize(&imported).and_then(|key| Ok((key, fs::read_to_string(&imported)?))) {
+                Ok(found) => found,
+                Err(error) => {
+                    let error = Diagnostic::new(codes::IMPORT_NOT_FOUND, format!("Cannot import \"{path}\" ({imported_name}): {error}"));
+                    return Err(Box::new(error.with_span(span).in_file(&name)));
+                }
+            };
+            if let Some(start) = self.loading.iter().position(|(key, _)| *key == imported_key) {
+                let cycle: Vec<&str> = self.loading[start..].iter().map(|(_, name)| name.as_str()).chain([imported_name.as_str()]).collect();
+                let error = Diagnostic::new(codes::IMPORT_CYCLE, format!("Import cycle: {}.", cycle.join(" imports ")));
+                return Err(Box::new(error.with_span(span).in_file(&name)));
+            }
+            if self.files.iter().any(|file| file.key == imported_key) {
+                continue;
+            }
+            let imported_directory = imported.parent().unwrap_or(Path::new("")).to_path_buf();
+            self.load(imported_key, imported_name, &imported_directory, &text)?;
+        }
+        self.loading.pop();
+        let ir = parse_importing(&tokens, &spans, source, &self.signatures).map_err(|error| in_file(error, nested))?;
+        self.signatures.extend(signatures(&tokens));
+
+        let functions = tokens.windows(2).zip(&spans[1..]).filter_map(|(pair, span)| match pair {
+            [Token::Func, Token::Ident(function)] => Some((function.clone(), *span)),
+            _ => None,
+        });
+        self.files.push(File { key, name, ir, functions: functions.collect() });
+        Ok(())
+    }
+}
+
+// the paths of the imports of a file, and where they are.
+fn imports(tokens: &[Token], spans: &[Span]) -> Vec<(String, Span)> {
+    let mut imports = vec![];
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match (token, tokens.get(i + 1)) {
+            (Token::LeftCurly, _) => depth += 1,
+            (Token::RightCurly, _) => depth -= 1,
+            (Token::Import, Some(Token::Str(path))) if depth == 0 => imports.push((path.clone(), spans[i + 1])),
+            _ => {}
+        }
+    }
+    imports
+}
+
+// a function defined in two files. the semantic checks find functions defined twice in one file.
+fn check_duplicates(program: &Program) -> Result<(), Box<Diagnostic>> {
+    for (i, file) in program.files.iter().enumerate() {
+        for (function, span) in &file.functions {
+            let earlier = program.files[..i].iter().find(|earlier| earlier.functions.iter().any(|(f, _)| f == function));
+            if let Some(earlier) = earlier {
+                let message = format!("Function {function} is defined in both {} and {}.", earlier.name, file.name);
+                return Err(Box::new(Diagnostic::new(codes::DUPLICATE_FUNCTION, message).with_span(*span).in_file(&file.name)));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod imports_tests {
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use crate::diagnostics::codes;
+    use crate::imports::*;
+    use crate::interpreter;
+
+    // writes files into a directory of their own, and returns the path of the first.
+    fn files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("rustcompiler-imports-{}-{test}", std::process::id()));
+        for (name, text) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        directory.join(files[0].0)
+    }
+
+    fn compile_file(path: &Path) -> Result<Compiled, Vec<Diagnostic>> {
+        compile(Some(path), &fs::read_to_string(path).unwrap())
+    }
+
+    #[test]
+    fn imports_are_relative() {
+        let main = files("relative", &[
+            ("main.tt", "import \"lib/twice.tt\";\nimport \"lib/gcd.tt\";\n\nfunc main() {\n    print(twice(gcd(12, 18)));\n}\n"),
+            ("lib/twice.tt", "import \"gcd.tt\";\n\nfunc twice(int a) {\n    return gcd(a, a) * 2;\n}\n"),
+            ("lib/gcd.tt", "func gcd(int a, int b) {\n    int t;\n    while b != 0 {\n        t = b;\n        b = a % b;\n        a = t;\n    }\n    return a;\n}\n"),
+        ]);
+        let compiled = compile_file(&main).unwrap();
+        // gcd.tt is imported twice, but only loaded once, before the file that imports it first.
+        assert!(compiled.ir.matches("%func gcd").count() == 1);
+        assert!(compiled.ir.find("%func gcd") < compiled.ir.find("%func twice"));
+        assert!(compiled.ir.find("%func twice") < compiled.ir.find("%func main"));
+        let mut output = vec![];
+        assert!(interpreter::execute(&compiled.ir, io::empty(), &mut output).is_ok());
+        assert!(output == b"12\n");
+
+        let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples_imports/main.tt");
+        assert!(interpreter::execute(&compile_file(&example).unwrap().ir, io::empty(), io::sink()).is_ok());
+    }
+
+    #[test]
+    fn imported_functions_keep_their_types() {
+        let main = files("types", &[
+            ("main.tt", "import \"square.tt\";\n\nfunc main() {\n    long big;\n    big = square(100000);\n    print(big);\n}\n"),
+            ("square.tt", "func square(long a) long {\n    return a * a;\n}\n"),
+        ]);
+        let compiled = compile_file(&main).unwrap();
+        assert!(compiled.ir.contains("%long _temp1\n%call _temp1, square(100000)\n%mov big, _temp1\n"));
+        let mut output = vec![];
+        assert!(interpreter::execute(&compiled.ir, io::empty(), &mut output) == Ok(0));
+        assert!(output == b"10000000000\n");
+    }
+
+    #[test]
+    fn errors_name_their_file() {
+        let main = files("errors", &[
+            ("main.tt", "import \"lib.tt\";\nfunc main() {\n    print(helper(1));\n}\n"),
+            ("lib.tt", "func helper(int a) {\n    return b;\n}\n"),
+            ("missing.tt", "import \"nowhere.tt\";\nfunc main() {\n}\n"),
+            ("syntax.tt", "import \"broken.tt\";\nfunc main() {\n}\n"),
+            ("broken.tt", "func broken( {\n}\n"),
+        ]);
+        let error = &compile_file(&main).unwrap_err()[0];
+        assert!(error.code == codes::UNDECLARED_VARIABLE && error.file.as_deref().is_some_and(|f| f.ends_with("lib.tt")));
+        assert!(error.to_string().starts_with("Error[E0201] in "));
+
+        let error = &compile_file(&main.with_file_name("missing.tt")).unwrap_err()[0];
+        assert!(error.code == codes::IMPORT_NOT_FOUND && error.file.as_deref().is_some_and(|f| f.ends_with("missing.tt")));
+        assert!(error.span.is_some_and(|span| span.start == 7));
+
+        let error = &compile_file(&main.with_file_name("syntax.tt")).unwrap_err()[0];
+        assert!(error.code == codes::INVALID_PARAMETERS && error.file.as_deref().is_some_and(|f| f.ends_with("broken.tt")));
+
+        // without imports, errors look the way they always did.
+        let error = &compile(None, "func main() {\n    print(b);\n}\n").unwrap_err()[0];
+        assert!(error.file.is_none() && error.to_string().starts_with("Error[E0201]: "));
+    }
+
+    #[test]
+    fn analysis_names_the_file() {
+        let main = files("analysis", &[
+            ("main.tt", "import \"lib.tt\";\nfunc main() {\n    int unused;\n    print(helper(1));\n}\n"),
+            ("lib.tt", "func helper(int a) {\n    int b;\n    if a < 1 {\n        return 1;\n    }\n}\n"),
+            ("nomain.tt", "import \"lib.tt\";\nfunc start() {\n    print(helper(1));\n}\n"),
+        ]);
+        let lib = main.with_file_name("lib.tt").display().to_string();
+        let error = &compile_file(&main).unwrap_err()[0];
+        assert!(error.code == codes::MISSING_RETURN && error.file.as_deref() == Some(lib.as_str()) && error.line == Some(1));
+
+        fs::write(main.with_file_name("lib.tt"), "func helper(int a) {\n    int b;\n    return a;\n}\n").unwrap();
+        let warnings = compile_file(&main).unwrap().warnings;
+        let file_of = |symbol: &str| warnings.iter().find(|warning| warning.symbol == symbol).unwrap().file.clone();
+        assert!(file_of("b") == Some(lib.clone()) && file_of("unused") == Some(main.display().to_string()));
+        assert!(warnings.iter().any(|warning| warning.to_string().starts_with(&format!("Warning[W0002] in {lib}: "))));
+
+        // an error about the whole program is in the file that was compiled.
+        let error = &compile_file(&main.with_file_name("nomain.tt")).unwrap_err()[0];
+        assert!(error.code == codes::MISSING_MAIN && error.file.as_deref().is_some_and(|f| f.ends_with("nomain.tt")));
+    }
+
+    #[test]
+    fn traces_name_the_files_of_their_calls() {
+        let main = files("trace", &[
+            ("main.tt", "import \"divide.tt\";\nfunc main() {\n    print(divide(1, 0));\n}\n"),
+            ("divide.tt", "func divide(int a, int b) {\n    return a / b;\n}\n"),
+        ]);
+        let compiled = compile_file(&main).unwrap();
+        let divide = main.with_file_name("divide.tt").display().to_string();
+        assert!(compiled.ir.starts_with(&format!("; file {divide}\n; line 1\n%func divide")));
+        let error = interpreter::execute(&compiled.ir, io::empty(), io::sink()).unwrap_err();
+        assert!(error.file.as_deref() == Some(divide.as_str()) && error.line == Some(2));
+        assert!(error.trace[0].starts_with(&format!("in divide, {divide} line 2 (IR line ")));
+        assert!(error.trace[1].starts_with(&format!("in main, {} line 3 (IR line ", main.display())));
+
+        // without imports, the IR has no file marks.
+        assert!(!compile(None, "func main() {\n}\n").unwrap().ir.contains("; file"));
+    }
+
+    #[test]
+    fn duplicates_and_cycles() {
+        let main = files("duplicates", &[
+            ("main.tt", "import \"a.tt\";\nfunc helper() {\n    return 1;\n}\nfunc main() {\n    print(helper());\n}\n"),
+            ("a.tt", "func helper() {\n    return 2;\n}\n"),
+            ("cycle.tt", "import \"b.tt\";\nfunc main() {\n}\n"),
+            ("b.tt", "import \"c.tt\";\n"),
+            ("c.tt", "import \"b.tt\";\n"),
+        ]);
+        let error = &compile_file(&main).unwrap_err()[0];
+        assert!(error.code == codes::DUPLICATE_FUNCTION);
+        let a = main.with_file_name("a.tt").display().to_string();
+        assert!(error.message == format!("Function helper is defined in both {a} and {}.", main.display()));
+
+        let error = &compile_file(&main.with_file_name("cycle.tt")).unwrap_err()[0];
+        assert!(error.code == codes::IMPORT_CYCLE && error.file.as_deref().is_some_and(|f| f.ends_with("c.tt")));
+        let b = main.with_file_name("b.tt").display().to_string();
+        let c = main.with_file_name("c.tt").display().to_string();
+        assert!(error.message == format!("Import cycle: {b} imports {c} imports {b}."));
+    }
+}
@@ -1,32 +1,175 @@
-pub fn execute_ir(code: &str) {
+// loads and runs IR, reading the input of the program from input, returning the value main returned.
+// nothing is written to output but the output of the program itself.
+pub fn execute(code: &str, input: impl BufRead, output: impl Write) -> Result<i32, Diagnostic> {
+    let bytecode = load(code)?;
+    run_main(input, output, &bytecode, &Limits::default(), None, None, None)
+}
+
+// runs IR like execute(), also returning the variables of main as they were when it returned.
+// the REPL carries them over from one input to the next.
+pub fn execute_keeping_locals(code: &str, input: impl BufRead, output: impl Write) -> Result<(i32, Vec<(String, Local)>), Diagnostic> {
+    let bytecode = load(code)?;
+    let mut locals = vec![];
+    let value = run_main(input, output, &bytecode, &Limits::default(), None, None, Some(&mut locals))?;
+    Ok((value, locals))
+}
+
+// how far a program may go before it is stopped with a runtime error.
+// every limit has an error code of its own, so a grader can tell a program that ran too long from one that used too much memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    // the most calls that can be running at once, main included.
+    pub call_depth: usize,
+    // the most instructions the program runs. None has no limit.
+    pub instructions: Option<usize>,
+    // the most bytes the arrays of the calls running can take at once, 4 for every element.
+    pub array_memory: Option<usize>,
+    // the most bytes '%out' writes, newlines included.
+    pub output: Option<usize>,
+    // what '%add', '%sub', '%mult', '%div' and '%narrow' do with a result that does not fit in its variable.
+    pub overflow: Overflow,
+}
+
+// what happens when arithmetic overflows, such as 2147483647 + 1 or -2147483648 / -1 in ints.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Overflow {
+    // the result wraps around, like the ints of most processors: 2147483647 + 1 is -2147483648.
+    #[default]
+    Wrap,
+    // the program stops with a runtime error.
+    Trap,
+    // the result is the int closest to it: 2147483647 + 1 is 2147483647.
+    Saturate,
+}
+
+impl Overflow {
+    pub fn from_name(name: &str) -> Option<Overflow> {
+        match name {
+            "wrap" => Some(Overflow::Wrap),
+            "trap" => Some(Overflow::Trap),
+            "saturate" => Some(Overflow::Saturate),
+            _ => None,
+        }
+    }
+
+    // the result of an operation that does not fit in width, from the result computed in 64 bits wrapping and saturating.
+    #[cold]
+    fn overflowed(self, width: Width, wrapped: i64, saturated: i64, operation: fmt::Arguments) -> Result<i64, IRError> {
+        match self {
+        Overflow::Wrap => Ok(width.wrap(wrapped)),
+        Overflow::Saturate => Ok(width.clamp(saturated)),
+        Overflow::Trap => {
+            let e = format!("Runtime Error: integer overflow in {}.", operation);
+            error(MAX_LINE, codes::INTEGER_OVERFLOW, e)
+        }
+        }
+    }
+}
+
+// the integer types of the IR: '%int' variables have 32 bits, '%long' variables 64.
+// every integer is kept in 64 bits while the program runs, an int only ever holds the values of 32.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Width {
+    Int,
+    Long,
+}
+
+impl Width {
+    fn name(self) -> &'static str {
+        match self {
+        Width::Int => "int",
+        Width::Long => "long",
+        }
+    }
+
+    fn holds(self, num: i64) -> bool {
+        self == Width::Long || i32::try_from(num).is_ok()
+    }
+
+    // the low bits of num that fit, as a processor would keep them.
+    fn wrap(self, num: i64) -> i64 {
+        match self {
+        Width::Int => num as i32 as i64,
+        Width::Long => num,
+        }
+    }
+
+    // the value closest to num that fits.
+    fn clamp(self, num: i64) -> i64 {
+        match self {
+        Width::Int => num.clamp(i32::MIN as i64, i32::MAX as i64),
+        Width::Long => num,
+        }
+    }
+}
+
+pub const DEFAULT_CALL_DEPTH: usize = 10000;
+
+// how many calls the stack trace of a runtime error lists before skipping to main.
+const TRACE_CALLS: usize = 20;
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits { call_depth: DEFAULT_CALL_DEPTH, instructions: None, array_memory: None, output: None, overflow: Overflow::Wrap }
+    }
+}
+
+// which instructions '--trace-exec' logs to stderr as they run:
+//   main #12, line 4 (IR line 9): %mult a, a=4, 2 -> a = 8
+// the function and index of the instruction, the values of its operands before it ran, and what it changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecTrace {
+    // the functions whose instructions are logged. every function when empty.
+    pub functions: Vec<String>,
+    // how many instructions are logged before the program goes on running without the trace. None has no end.
+    pub max_steps: Option<usize>,
+}
+
+// execute(), as the commandline tool runs it: invalid IR is listed up to the line with the error.
+// with a trace, the instructions it picks are logged as they run, and a profile is filled with the counts of the run,
+// whether or not the program ends with an error.
+// '%input' reads a line of input for every number, and '%out' writes a line of output for it:
+// the commandline tool passes stdin or the file of '--input', and stdout, and tests pass buffers.
+pub fn execute_ir(code: &str, limits: &Limits, trace: Option<&ExecTrace>, profile: Option<&mut Profile>, input: impl BufRead, output: impl Write) -> Result<i32, Diagnostic> {
     let tokens = lex_ir(code);
     let bytecode = match parse_ir(&tokens, &mut 0) {
     Ok(bytecode) => {
-        println!("Valid IR. Executing Generated Bytecode...");
+        log!(Level::Info, "Valid IR. Executing Generated Bytecode...\n");
         bytecode
     }
 
     Err(e) => {
-        println!("***Error. Invalid Bytecode.");
-        println!("------------------");
-        for (i, l) in code.lines().enumerate() {
-            println!("{:03}:  {}", i+1, l);
-            if i+1 == e.line {
-                break;
+        if log::enabled(Level::Error) {
+            eprintln!("***Error. Invalid Bytecode.");
+            eprintln!("------------------");
+            for (i, l) in code.lines().enumerate() {
+                eprintln!("{:03}:  {}", i+1, l);
+                if i+1 == e.line {
+                    break;
+                }
             }
+            eprintln!("------------------");
         }
-        println!("------------------");
-        println!("{e}");
-        return;
+        return Err(e.into());
     }
 
     };
 
-    let stdin = io::stdin();
-    run_program(&stdin, &bytecode);
+    run_main(input, output, &bytecode, limits, trace, profile, None)
 }
 
-use std::io;
+// lexes and parses IR into bytecode, ready for a Vm.
+pub(crate) fn load(code: &str) -> Result<Vec<FunctionBytecode>, Diagnostic> {
+    let tokens = lex_ir(code);
+    Ok(parse_ir(&tokens, &mut 0)?)
+}
+
+use std::cmp::Ordering;
+use std::io::{self, BufRead, Write};
+
+use crate::diagnostics::{codes, Diagnostic};
+use crate::profile::Profile;
+use crate::log::{self, Level};
 
 fn lex_ir(mut code: &str) -> Vec<IRTok> {
     let mut tokens: Vec<IRTok> = vec![];
@@ -45,13 +188,24 @@ fn lex_ir(mut code: &str) -> Vec<IRTok> {
 const MAX_LINE: usize = 2000000;
 
 fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecode>, IRError> {
+    // numbers the lexer could not read, on the line of the newlines before them.
+    let mut line = 1;
+    for token in tokens {
+        match token {
+        IRTok::EndInstr => line += 1,
+        IRTok::InvalidNumber(num) => return error(line, codes::INVALID_IR, format!("number {num} is out of range. numbers in IR are at most {}.", i64::MAX)),
+        _ => {}
+        }
+    }
     let mut serialized_line: usize = 1;
     let mut vector: Vec<FunctionBytecode> = vec![];
     let mut has_main: bool = false;
-    while let Some(bytecode) = parse_func_ir(&mut serialized_line, tokens, idx)? {
+    // the file of the functions that follow a '; file' mark.
+    let mut file: Option<String> = None;
+    while let Some(bytecode) = parse_func_ir(&mut serialized_line, &mut file, tokens, idx)? {
         for func in &vector {
           if func.name.eq(&bytecode.name) {
-            return error(MAX_LINE, format!("Error. Two functions with the same name {}", func.name));
+            return error(MAX_LINE, codes::IR_DUPLICATE_FUNCTION, format!("Error. Two functions with the same name {}", func.name));
           }
         }
         if bytecode.name.eq("main") {
@@ -61,7 +215,10 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
     }
 
     if has_main == false {
-      return error(MAX_LINE, format!("Error. Generated code does not have a main."));
+      return error(MAX_LINE, codes::IR_MISSING_MAIN, format!("Error. Generated code does not have a main."));
+    }
+    if vector.iter().any(|function| function.name == "main" && function.returns == Width::Long) {
+      return error(MAX_LINE, codes::IR_TYPE_MISMATCH, String::from("Error. main returns the exit code of the program, which is an int, not a long."));
     }
 
     // todo: this is not the correct line numbers. but I dunno how to get better line numbers...
@@ -70,15 +227,21 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
             if let Bytecode::Call(r, call, params) = &vector[func_id].body[instr_id] {
                 if let IRTok::Var(func_name) = &tokens[*call] {
                     if let Some(call_value) = find_func_id(&vector, func_name) {
-                         if params.len() != vector[call_value].parameters {
-                             return error(MAX_LINE, format!("Error. Invalid parameter passing to '{func_name}'. Expected {} number of parameters. Got {} number of parameters.", vector[call_value].parameters, params.len()));
+                         let callee = &vector[call_value];
+                         if params.len() != callee.parameters {
+                             return error(MAX_LINE, codes::IR_PARAMETER_COUNT, format!("Error. Invalid parameter passing to '{func_name}'. Expected {} number of parameters. Got {} number of parameters.", callee.parameters, params.len()));
+                         }
+                         // the parameters are the first slots of the callee.
+                         let caller = &vector[func_id];
+                         if caller.widths[*r] != callee.returns || params.iter().zip(&callee.widths).any(|(p, w)| width(caller, p).is_some_and(|p| p != *w)) {
+                             return error(MAX_LINE, codes::IR_TYPE_MISMATCH, format!("Error. Call of '{func_name}' in {} mixes ints and longs, convert them with '%widen' or '%narrow' first.", caller.name));
                          }
                          vector[func_id].body[instr_id] = Bytecode::Call(*r, call_value, params.to_vec());
                     } else {
-                         return error(MAX_LINE, format!("Error. Undeclared function '{}'", func_name));
+                         return error(MAX_LINE, codes::IR_UNDECLARED_FUNCTION, format!("Error. Undeclared function '{}'", func_name));
                     }
                 } else {
-                    return error(MAX_LINE, format!("Internal Interpreter Error."));
+                    return error(MAX_LINE, codes::INVALID_IR, format!("Internal Interpreter Error."));
                 }
             }
         }
@@ -98,32 +261,50 @@ fn parse_ir(tokens: &Vec<IRTok>, idx: &mut usize) -> Result< Vec<FunctionBytecod
 
 struct IRError {
     line: usize,
+    code: &'static str,
     message: String,
 }
 
 impl fmt::Display for IRError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.line == MAX_LINE {
-            write!(f, "Error. {}", self.message)
+            write!(f, "Error[{}]. {}", self.code, self.message)
         } else {
-            write!(f, "Error at line {}. {}", self.line, self.message)
+            write!(f, "Error[{}] at line {}. {}", self.code, self.line, self.message)
         }
     }
 }
 
-fn error<T>(line: usize, message: String) -> Result<T, IRError> {
-    Err(IRError {line: line, message: message})
+// the line is kept in the message, as the diagnostic itself only points into the source program.
+impl From<IRError> for Diagnostic {
+    fn from(e: IRError) -> Diagnostic {
+        if e.line == MAX_LINE {
+            Diagnostic::new(e.code, e.message)
+        } else {
+            Diagnostic::new(e.code, format!("IR line {}. {}", e.line, e.message))
+        }
+    }
+}
+
+fn error<T>(line: usize, code: &'static str, message: String) -> Result<T, IRError> {
+    Err(IRError { line, code, message })
 }
 
 use std::fmt;
 
-fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Option<FunctionBytecode>, IRError> {
+fn parse_func_ir(serialized_line: &mut usize, file: &mut Option<String>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Option<FunctionBytecode>, IRError> {
     let mut function_bytecode = FunctionBytecode {
         name:String::from(""),
+        file: None,
         parameters:0,
-        id:0,
+        returns: Width::Int,
+        widths: vec![],
+        array_lengths: vec![],
+        array_memory: 0,
         variables: HashMap::new(),
         body: vec![],
+        locations: vec![],
+        labels: HashMap::new(),
     };
 
     loop {
@@ -132,6 +313,15 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
              *idx += 1;
              continue;
         }
+        if let Some(IRTok::SourceLine(_)) = peek(tokens, *idx) {
+             *idx += 1;
+             continue;
+        }
+        if let Some(IRTok::SourceFile(name)) = peek(tokens, *idx) {
+             *file = Some(name.clone());
+             *idx += 1;
+             continue;
+        }
 
         break;
     }
@@ -139,7 +329,7 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
     match next(tokens, idx) {
     Some(token) => {
         if !matches!(token, IRTok::Func) {
-            return error(*serialized_line, format!("func IR must begin with '%func'"));
+            return error(*serialized_line, codes::INVALID_IR, format!("func IR must begin with '%func'"));
         }
     }
  
@@ -150,9 +340,10 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
     match next_result(*serialized_line, tokens, idx)? {
     IRTok::Var(func_ident) => {
         function_bytecode.name = func_ident.clone();
+        function_bytecode.file = file.clone();
     }
 
-    _ => return error(*serialized_line, String::from("func IR must have an identifier name such as '%func main'")),
+    _ => return error(*serialized_line, codes::INVALID_IR, String::from("func IR must have an identifier name such as '%func main'")),
 
     };
 
@@ -162,28 +353,29 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
         loop {
             match next_result(*serialized_line, tokens, idx)? {
             IRTok::RParen => break,
-            IRTok::Int => {
+            IRTok::Int | IRTok::Long => {
+                let width = if matches!(tokens[*idx - 1], IRTok::Long) { Width::Long } else { Width::Int };
                 match next_result(*serialized_line, tokens, idx)? {
                 IRTok::Var(param) => {
                     if let Some(_) = function_bytecode.variables.get(param) {
                         let f = format!("identifier {param} already defined");
-                        return error(*serialized_line, f);
+                        return error(*serialized_line, codes::IR_DUPLICATE_DECLARATION, f);
                     } else {
                         function_bytecode.parameters += 1;
-                        let id = get_id(&mut function_bytecode);
-                        function_bytecode.variables.insert(param.clone(), VariableType::IntVar(id));
+                        let slot = new_int_slot(&mut function_bytecode, width);
+                        function_bytecode.variables.insert(param.clone(), VariableType::IntVar(slot));
                     }
                     if matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::Comma) {
                         *idx += 1;
                     }
                 }
                 _ => {
-                    return error(*serialized_line, String::from("invalid parameters format for functions"));
+                    return error(*serialized_line, codes::INVALID_IR, String::from("invalid parameters format for functions"));
                 }
                 }
             }
             _ => {
-                return error(*serialized_line, String::from("invalid parameters format for functions"));
+                return error(*serialized_line, codes::INVALID_IR, String::from("invalid parameters format for functions"));
             }
 
             }
@@ -193,16 +385,24 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
 
     }
 
+    // a function returning a long says so after its parameters: '%func fact(%long n) %long'.
+    if matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::Long) {
+        function_bytecode.returns = Width::Long;
+        *idx += 1;
+    }
+
     if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::EndInstr) {
-        return error(*serialized_line, String::from("%func header must end with a newline"));
+        return error(*serialized_line, codes::INVALID_IR, String::from("%func header must end with a newline"));
     }
 
     *serialized_line += 1;
 
     let mut labels_hash: HashMap<String, usize> = Default::default();
     let mut line: usize = 0;
+    let mut location = Location { ir: *serialized_line, source: None };
     loop {
-        let bytecode = parse_instruction(serialized_line, line, &mut function_bytecode, &mut labels_hash, tokens, idx)?;
+        let bytecode = parse_instruction(serialized_line, line, &mut location, &mut function_bytecode, &mut labels_hash, tokens, idx)?;
+        function_bytecode.locations.push(location);
         if matches!(bytecode, Bytecode::End) {
             break;
         }
@@ -211,7 +411,7 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
     }
 
     if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::EndFunc) {
-        return error(*serialized_line, String::from("func IR needs to end in '%endfunc'"));
+        return error(*serialized_line, codes::INVALID_IR, String::from("func IR needs to end in '%endfunc'"));
     }
 
     function_bytecode.body.push(Bytecode::End); 
@@ -222,10 +422,10 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
                 if let Some(id) = labels_hash.get(label_name) {
                     function_bytecode.body[i] = Bytecode::Jmp(*id);
                 } else {
-                    return error(i, format!("Error. invalid label {}", label_name));
+                    return error(i, codes::IR_INVALID_LABEL, format!("Error. invalid label {}", label_name));
                 }
             } else {
-                return error(*serialized_line, String::from("Internal Compiler Error."));
+                return error(*serialized_line, codes::INVALID_IR, String::from("Internal Compiler Error."));
             }
         }
         Bytecode::BranchIf(tf, index) => {
@@ -233,10 +433,10 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
                 if let Some(id) = labels_hash.get(label_name) {
                     function_bytecode.body[i] = Bytecode::BranchIf(tf.clone(), *id);
                 } else {
-                    return error(*serialized_line, format!("Error. invalid label {}", label_name));
+                    return error(*serialized_line, codes::IR_INVALID_LABEL, format!("Error. invalid label {}", label_name));
                 }
             } else {
-                return error(*serialized_line, String::from("Internal Compiler Error."));
+                return error(*serialized_line, codes::INVALID_IR, String::from("Internal Compiler Error."));
             }
         }
         Bytecode::BranchIfn(tf, index) => {
@@ -244,42 +444,209 @@ fn parse_func_ir(serialized_line: &mut usize, tokens: &Vec<IRTok>, idx: &mut usi
                 if let Some(id) = labels_hash.get(label_name) {
                     function_bytecode.body[i] = Bytecode::BranchIfn(tf.clone(), *id);
                 } else {
-                    return error(*serialized_line, format!("Error. invalid label {}", label_name));
+                    return error(*serialized_line, codes::IR_INVALID_LABEL, format!("Error. invalid label {}", label_name));
                 }
             } else {
-                return error(*serialized_line, String::from("Internal Compiler Error."));
+                return error(*serialized_line, codes::INVALID_IR, String::from("Internal Compiler Error."));
             }
         }
         _ => {}
         }
     }
+    function_bytecode.labels = labels_hash;
     return Ok(Some(function_bytecode));
 }
 
 use std::collections::HashMap;
 
-struct FunctionBytecode {
+pub(crate) struct FunctionBytecode {
     name: String,
+    // the file the function was compiled from, when the IR has '; file' marks.
+    file: Option<String>,
     parameters: usize,
-    id: i32,
+    returns: Width,
+    // the width of every integer slot in a frame of the function.
+    widths: Vec<Width>,
+    // the length of the array in every array slot.
+    array_lengths: Vec<usize>,
+    // the bytes of all those arrays.
+    array_memory: usize,
     variables: HashMap<String, VariableType>,
     body: Vec<Bytecode>,
+    // where every instruction of body came from.
+    locations: Vec<Location>,
+    // the instruction of every label, such as ':loopbegin1'.
+    labels: HashMap<String, usize>,
+}
+
+impl FunctionBytecode {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    // the number of instructions in the body.
+    pub(crate) fn instructions(&self) -> usize {
+        self.body.len()
+    }
+
+    pub(crate) fn opcode(&self, instruction: usize) -> &'static str {
+        self.body[instruction].opcode()
+    }
+
+    pub(crate) fn location(&self, instruction: usize) -> Location {
+        self.locations[instruction]
+    }
+
+    pub(crate) fn label(&self, label: &str) -> Option<usize> {
+        self.labels.get(label).copied()
+    }
+
+    // whether an instruction is the first of a line of source. without marks, every instruction is a line of its own.
+    pub(crate) fn starts_line(&self, instruction: usize) -> bool {
+        let source = self.locations[instruction].source;
+        source.is_none() || instruction == 0 || self.locations[instruction - 1].source != source
+    }
+
+    // the instruction a line of source starts at, the first time it does, or the instruction on a line of IR without marks.
+    // a while loop comes back to its line at the end of the loop, which is not where the line starts.
+    pub(crate) fn line(&self, line: usize) -> Option<usize> {
+        (0..self.locations.len()).find(|i| match self.locations[*i].source {
+            Some(source) => source == line && self.starts_line(*i),
+            None => self.locations[*i].ir == line,
+        })
+    }
+}
+
+// the line of an instruction in the IR, and the line of source it was compiled from when the IR has marks.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct Location {
+    pub(crate) ir: usize,
+    pub(crate) source: Option<usize>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            Some(line) => write!(f, "line {} (IR line {})", line, self.ir),
+            None => write!(f, "IR line {}", self.ir),
+        }
+    }
 }
 
-fn get_id(function: &mut FunctionBytecode) -> i32 {
-    let id = function.id;
-    function.id += 1;
-    id
+fn new_int_slot(function: &mut FunctionBytecode, width: Width) -> usize {
+    function.widths.push(width);
+    function.widths.len() - 1
 }
 
-fn read_integer_value(variables: &HashMap<i32, i32>, op: &Op) -> i32 {
+// what an element of an array counts for in Limits::array_memory.
+const ARRAY_ELEMENT_BYTES: usize = 4;
+
+fn new_array_slot(function: &mut FunctionBytecode, len: usize) -> usize {
+    function.array_lengths.push(len);
+    function.array_memory += len * ARRAY_ELEMENT_BYTES;
+    function.array_lengths.len() - 1
+}
+
+fn read_integer_value(ints: &[i64], op: &Op) -> i64 {
     match op {
     Op::Num(num) => *num,
-    Op::Var(id) => *variables.get(&id).unwrap(),
+    Op::Var(slot) => ints[*slot],
+    }
+}
+
+// the value of a variable, as returned by execute_keeping_locals.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Local {
+    Int(i32),
+    Long(i64),
+    Array(Vec<i32>),
+}
+
+// the variables of one call of a function, by slot.
+#[derive(Default)]
+struct Frame {
+    // ints and longs.
+    ints: Vec<i64>,
+    arrays: Vec<Vec<i32>>,
+}
+
+impl Frame {
+    // makes the frame ready for a call of function, with every variable 0.
+    // the memory the frame already has is used again.
+    fn reset(&mut self, function: &FunctionBytecode) {
+        self.ints.clear();
+        self.ints.resize(function.widths.len(), 0);
+        self.arrays.resize_with(function.array_lengths.len(), Vec::new);
+        for (array, len) in self.arrays.iter_mut().zip(&function.array_lengths) {
+            array.clear();
+            array.resize(*len, 0);
+        }
+    }
+}
+
+// a call that has not returned yet.
+struct Activation<'a> {
+    function: &'a FunctionBytecode,
+    frame: Frame,
+    instr_pointer: usize,
+    // the slot of the caller that gets the return value.
+    result: usize,
+}
+
+// calls are kept on a stack of their own instead of recursing in Rust,
+// so a deeply recursive program ends with an error rather than overflowing the stack of the interpreter.
+pub(crate) struct Vm<'a, R, W> {
+    // where '%input' reads and '%out' writes.
+    input: R,
+    output: W,
+    calls: &'a [FunctionBytecode],
+    limits: &'a Limits,
+    // main is at the bottom, the running call at the top.
+    stack: Vec<Activation<'a>>,
+    // the frames of calls that returned, kept for the next calls.
+    free_frames: Vec<Frame>,
+    tracer: Option<Tracer<'a>>,
+    profile: Option<&'a mut Profile>,
+    // the instructions the program can still run, the bytes of the arrays of the calls on the stack, and the bytes written.
+    fuel: usize,
+    array_memory: usize,
+    written: usize,
+}
+
+// logs instructions for an ExecTrace.
+struct Tracer<'a> {
+    trace: &'a ExecTrace,
+    logged: usize,
+    out: Box<dyn io::Write + 'a>,
+}
+
+fn run_main(input: impl BufRead, output: impl Write, calls: &[FunctionBytecode], limits: &Limits, trace: Option<&ExecTrace>, mut profile: Option<&mut Profile>, locals: Option<&mut Vec<(String, Local)>>) -> Result<i32, Diagnostic> {
+    let mut vm = Vm::new(input, output, calls, limits)?;
+    vm.tracer = trace.map(|trace| Tracer { trace, logged: 0, out: Box::new(io::stderr()) });
+    if let Some(profile) = profile.as_deref_mut() {
+        profile.start(calls, calls.iter().position(|function| function.name == "main").unwrap());
+    }
+    vm.profile = profile.as_deref_mut();
+    let mut result = vm.run_bytecode().map_err(|e| vm.stack_trace(e));
+    if let (Err(e), Ok(_)) = (vm.output.flush(), &result) {
+        result = Err(Diagnostic::new(codes::CANNOT_WRITE_FILE, format!("Runtime Error: could not write the output: {e}")));
     }
+
+    // main is left on the stack when it returns.
+    if let (Some(locals), Ok(_)) = (locals, &result) {
+        *locals = vm.locals(0);
+    }
+    drop(vm);
+    if let Some(profile) = profile {
+        profile.finish(calls);
+    }
+    result
 }
 
-fn run_program(stdin: &io::Stdin, calls: &Vec<FunctionBytecode>) {
+impl<'a, R: BufRead, W: Write> Vm<'a, R, W> {
+
+// a program about to run the first instruction of main.
+pub(crate) fn new(input: R, output: W, calls: &'a [FunctionBytecode], limits: &'a Limits) -> Result<Vm<'a, R, W>, Diagnostic> {
     let entry_point = {
         let mut result = None;
         for func in calls {
@@ -290,287 +657,576 @@ fn run_program(stdin: &io::Stdin, calls: &Vec<FunctionBytecode>) {
         match result {
         Some(r) => r,
         None => {
-            println!("Runtime Error. No main function declared.");
-            return;
+            return Err(Diagnostic::new(codes::IR_MISSING_MAIN, "Runtime Error. No main function declared."));
         }
         }
     };
-    match run_bytecode(stdin, entry_point, calls, &vec![]) {
-    Ok(n) => println!("Run successful. Exit code {}", n),
-    Err(e) => println!("{}", e),
+    if entry_point.parameters != 0 {
+         let e = format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got 0 parameters", entry_point.parameters);
+         return Err(error::<()>(MAX_LINE, codes::IR_PARAMETER_COUNT, e).unwrap_err().into());
+    }
+
+    if limits.array_memory.is_some_and(|limit| entry_point.array_memory > limit) {
+        let e = format!("Runtime Error: array memory limit of {} bytes exceeded. The arrays of main take {} bytes.", limits.array_memory.unwrap(), entry_point.array_memory);
+        return Err(error::<()>(MAX_LINE, codes::MEMORY_LIMIT_EXCEEDED, e).unwrap_err().into());
     }
+
+    let mut frame = Frame::default();
+    frame.reset(entry_point);
+    let main = Activation { function: entry_point, frame, instr_pointer: 0, result: 0 };
+    Ok(Vm { input, output, calls, limits, stack: vec![main], free_frames: vec![], tracer: None, profile: None,
+        fuel: limits.instructions.unwrap_or(usize::MAX), array_memory: entry_point.array_memory, written: 0 })
 }
 
-fn run_bytecode(stdin: &io::Stdin, function: &FunctionBytecode, calls: &Vec<FunctionBytecode>, parameters: &Vec<i32>) -> Result<i32, IRError>  {
-    let mut variables: HashMap<i32, i32> = HashMap::new();
-    let mut arrays: HashMap<i32, Vec<i32>> = HashMap::new();
+// runs the call on top of the stack until main returns.
+fn run_bytecode(&mut self) -> Result<i32, IRError>  {
+    // the trace and the profile have a loop of their own, so running without them costs nothing.
+    if self.tracer.is_some() || self.profile.is_some() {
+        return self.run_observed();
+    }
+    loop {
+        if let Some(return_value) = self.run_instruction()? {
+            return Ok(return_value);
+        }
+    }
+}
 
-    // setup local variables
-    for (_, vartype) in &function.variables {
-         match vartype {
-         VariableType::IntVar(id) => {
-             variables.insert(*id, 0);
-         }
+// runs one instruction like run_bytecode(), for the debugger.
+// returns what main returned once it has, and a runtime error with its stack trace.
+pub(crate) fn step(&mut self) -> Result<Option<i32>, Diagnostic> {
+    self.run_instruction().map_err(|e| self.stack_trace(e))
+}
 
-         VariableType::ArrayVar(id, len) => {
-             // create an array of zeroes.
-             let arr = vec![0i32; *len as usize];
-             arrays.insert(*id, arr);
-         }
+// where the program writes, for the debugger to write its prompt between the output of the program.
+pub(crate) fn output(&mut self) -> &mut W {
+    &mut self.output
+}
 
-         }
-    }
-    if parameters.len() != function.parameters {
-         let e = format!("Runtime Error. Incorrect number of parameters passed to the function. Expected {}, got {} parameters", function.parameters, parameters.len());
-         return error(MAX_LINE, e);
+// the number of calls running, main included.
+pub(crate) fn depth(&self) -> usize {
+    self.stack.len()
+}
+
+// the function of a call and the instruction it is at. call 0 is the innermost.
+pub(crate) fn position(&self, call: usize) -> (&'a FunctionBytecode, usize) {
+    let activation = &self.stack[self.stack.len() - 1 - call];
+    // the calls below the top have moved past the instruction that called the next one.
+    let instruction = if call == 0 { activation.instr_pointer } else { activation.instr_pointer - 1 };
+    (activation.function, instruction)
+}
+
+// the variables of a call, by name. call 0 is the innermost.
+pub(crate) fn locals(&self, call: usize) -> Vec<(String, Local)> {
+    let activation = &self.stack[self.stack.len() - 1 - call];
+    let frame = &activation.frame;
+    let mut locals = vec![];
+    for (name, vartype) in &activation.function.variables {
+        let local = match vartype {
+        VariableType::IntVar(slot) => match activation.function.widths[*slot] {
+            Width::Int => Local::Int(frame.ints[*slot] as i32),
+            Width::Long => Local::Long(frame.ints[*slot]),
+        },
+        VariableType::ArrayVar(slot) => Local::Array(frame.arrays[*slot].clone()),
+        };
+        locals.push((name.clone(), local));
     }
+    locals.sort_by(|a, b| a.0.cmp(&b.0));
+    locals
+}
 
-    // hopefully this covers everything needed for parameter passing...
-    for (i, value) in parameters.iter().enumerate() {
-        let variable = variables.get_mut(&(i as i32)).unwrap();
-        *variable = *value;
+// where every call is, innermost first:
+//   in divide, line 3 (IR line 6)
+//   in main, line 11 (IR line 23)
+pub(crate) fn backtrace(&self) -> Vec<String> {
+    (0..self.stack.len()).map(|call| self.describe(call)).collect()
+}
+
+fn describe(&self, call: usize) -> String {
+    let (function, instruction) = self.position(call);
+    match &function.file {
+    Some(file) => format!("in {}, {file} {}", function.name, function.locations[instruction]),
+    None => format!("in {}, {}", function.name, function.locations[instruction]),
     }
+}
 
-    // a lot of unwraps, but we already checked everything
-    // so this should work.
-    // execute instructions.
-    let mut instr_pointer: usize = 0;
-    loop {
-        let instr = &function.body[instr_pointer];
-        match instr {
-        Bytecode::End => {
-            break;
+// runs the instruction the call on top of the stack is at.
+// returns what main returned once it has.
+// it is inlined into the loop of run_bytecode(), which every instruction of a program goes through.
+#[inline(always)]
+fn run_instruction(&mut self) -> Result<Option<i32>, IRError> {
+    if self.fuel == 0 {
+        let e = format!("Runtime Error: instruction limit {} exceeded.", self.limits.instructions.unwrap_or(usize::MAX));
+        return error(MAX_LINE, codes::INSTRUCTION_LIMIT_EXCEEDED, e);
+    }
+    self.fuel -= 1;
+    // every slot was checked when the IR was loaded, so indexing the frame cannot fail.
+    let depth = self.stack.len();
+    let Activation { function, frame, instr_pointer, .. } = self.stack.last_mut().unwrap();
+    let function: &'a FunctionBytecode = function;
+    let instr = &function.body[*instr_pointer];
+    match instr {
+    Bytecode::End => {
+        if depth == 1 {
+            return Ok(Some(0));
         }
+        self.return_to_caller(0);
+    }
 
-        Bytecode::Int(id) => {
-            let value = variables.get_mut(id).unwrap();
-            *value = 0;
-            instr_pointer += 1;
+    Bytecode::Int(slot) | Bytecode::Long(slot) => {
+        frame.ints[*slot] = 0;
+        *instr_pointer += 1;
+    }
+
+    Bytecode::IntArray(slot) => {
+        frame.arrays[*slot].fill(0);
+        *instr_pointer += 1;
+    }
+
+    Bytecode::Out(value) => {
+        let line = format!("{}\n", read_integer_value(&frame.ints, value));
+        self.written += line.len();
+        if let Some(limit) = self.limits.output.filter(|limit| self.written > *limit) {
+            let e = format!("Runtime Error: output limit of {} bytes exceeded.", limit);
+            return error(MAX_LINE, codes::OUTPUT_LIMIT_EXCEEDED, e);
         }
+        if let Err(e) = self.output.write_all(line.as_bytes()) {
+            return error(MAX_LINE, codes::CANNOT_WRITE_FILE, format!("Runtime Error: could not write the output: {e}"));
+        }
+        *instr_pointer += 1;
+    }
 
-        Bytecode::IntArray(id, len) => {
-            let array = arrays.get_mut(id).unwrap();
-            for i in 0..*len {
-                 let idx = i as usize;
-                 array[idx] ^= array[idx];
+    Bytecode::In(slot) => {
+        // a line that is not a number is skipped with a message, as a user at a terminal gets to type it again.
+        // the end of the input is an error, there is no number to give the program.
+        let mut buf = String::with_capacity(64);
+        loop {
+            match self.input.read_line(&mut buf) {
+            Ok(0) => {
+                 let e = String::from("Runtime Error: '%input' reached the end of the input.");
+                 return error(MAX_LINE, codes::END_OF_INPUT, e);
             }
-            instr_pointer += 1;
-        }
-
-        Bytecode::Out(value) => {
-            let num = read_integer_value(&variables, value);
-            println!("{}", num);
-            instr_pointer += 1;
-        }
-
-        Bytecode::In(id) => {
-            let mut buf = String::with_capacity(64);
-            loop {
-                match stdin.read_line(&mut buf) {
-                Ok(_) => {
-                     let token = buf.trim_end();
-                     match token.parse::<i32>() {
-                     Ok(num) => {
-                          let dest = variables.get_mut(id).unwrap();
-                          *dest = num;
-                          break;
-                     }
 
-                     Err(_) => {
-                         println!("User Input Error. '{}' is not a valid number.", token);
-                         buf.clear();
-                     }
+            Ok(_) => {
+                 let token = buf.trim();
+                 // a number too big for an int is not a valid one for an int variable.
+                 match token.parse::<i64>() {
+                 Ok(num) if function.widths[*slot].holds(num) => {
+                      frame.ints[*slot] = num;
+                      break;
+                 }
+
+                 _ => {
+                     log!(Level::Error, "User Input Error. '{}' is not a valid number.\n", token);
+                     buf.clear();
+                 }
+
+                 }
+            }
 
-                     }
-                }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
 
-                Err(e) => {
-                     println!("Error. Failed to read from standard input correctly.");
-                     println!("{e}");
-                     println!("Please try again.");
-                }
+            Err(e) => {
+                 return error(MAX_LINE, codes::CANNOT_READ_FILE, format!("Runtime Error: could not read the input: {e}"));
+            }
 
-                }
             }
-            instr_pointer += 1;
         }
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Mov(MemWrite::IntVar(dest), src) => {
-            let num = read_memory(&variables, &arrays, src)?;
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num;
-            instr_pointer += 1;
-        }
+    Bytecode::Mov(MemWrite::IntVar(dest), src) => {
+        frame.ints[*dest] = read_memory(frame, src)?;
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src) => {
-            let num = read_memory(&variables, &arrays, src)?;
-            let dest = arrays.get_mut(dest).unwrap();
-            let index = read_integer_value(&variables, index);
-            if index < 0 {
-                let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", index, dest.len());
-                return error(MAX_LINE, e);
-            }
-            let i = index as usize;
-            if i < dest.len() {
-                dest[i] = num;
-                instr_pointer += 1;
-            } else {
-                let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", i, dest.len());
-                return error(MAX_LINE, e);
-            }
+    Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src) => {
+        let num = read_memory(frame, src)?;
+        let index = read_integer_value(&frame.ints, index);
+        let dest = &mut frame.arrays[*dest];
+        if index < 0 {
+            let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", index, dest.len());
+            return error(MAX_LINE, codes::ARRAY_OUT_OF_BOUNDS, e);
         }
-
-        Bytecode::Add(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 + num2;
-            instr_pointer += 1;
+        let i = index as usize;
+        if i < dest.len() {
+            // the elements of arrays are ints, and the IR was checked to only store ints in them.
+            dest[i] = num as i32;
+            *instr_pointer += 1;
+        } else {
+            let e = format!("Runtime Error: Array out of bounds. Value {}. Array Length {}", i, dest.len());
+            return error(MAX_LINE, codes::ARRAY_OUT_OF_BOUNDS, e);
         }
+    }
 
-        Bytecode::Sub(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 - num2;
-            instr_pointer += 1;
-        }
+    Bytecode::Add(width, dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = match num1.checked_add(num2).filter(|num| width.holds(*num)) {
+            Some(num) => num,
+            None => self.limits.overflow.overflowed(*width, num1.wrapping_add(num2), num1.saturating_add(num2), format_args!("{} + {}", num1, num2))?,
+        };
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Mult(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 * num2;
-            instr_pointer += 1;
-        }
+    Bytecode::Sub(width, dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = match num1.checked_sub(num2).filter(|num| width.holds(*num)) {
+            Some(num) => num,
+            None => self.limits.overflow.overflowed(*width, num1.wrapping_sub(num2), num1.saturating_sub(num2), format_args!("{} - {}", num1, num2))?,
+        };
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Div(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            if num2 == 0 {
-                let e = String::from("Error. Attempt to divide by zero.");
-                return error(MAX_LINE, e);
-            }
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 / num2;
-            instr_pointer += 1;
-        }
+    Bytecode::Mult(width, dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = match num1.checked_mul(num2).filter(|num| width.holds(*num)) {
+            Some(num) => num,
+            None => self.limits.overflow.overflowed(*width, num1.wrapping_mul(num2), num1.saturating_mul(num2), format_args!("{} * {}", num1, num2))?,
+        };
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Mod(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            if num2 == 0 {
-                let e = String::from("Error. Attempt to divide by zero.");
-                return error(MAX_LINE, e);
-            }
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = num1 % num2;
-            instr_pointer += 1;
+    Bytecode::Div(width, dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        if num2 == 0 {
+            let e = String::from("Error. Attempt to divide by zero.");
+            return error(MAX_LINE, codes::DIVIDE_BY_ZERO, e);
         }
+        // only the smallest int or long divided by -1 overflows.
+        frame.ints[*dest] = match num1.checked_div(num2).filter(|num| width.holds(*num)) {
+            Some(num) => num,
+            None => self.limits.overflow.overflowed(*width, num1.wrapping_div(num2), num1.saturating_div(num2), format_args!("{} / {}", num1, num2))?,
+        };
+        *instr_pointer += 1;
+    }
 
-        Bytecode::LessThan(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 < num2) as i32;
-            instr_pointer += 1;
+    Bytecode::Mod(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        if num2 == 0 {
+            let e = String::from("Error. Attempt to divide by zero.");
+            return error(MAX_LINE, codes::DIVIDE_BY_ZERO, e);
         }
+        // -2147483648 % -1 is 0, which fits, though the division it is computed with overflows in an int.
+        frame.ints[*dest] = num1.wrapping_rem(num2);
+        *instr_pointer += 1;
+    }
 
-        Bytecode::LessEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 <= num2) as i32;
-            instr_pointer += 1;
-        }
+    Bytecode::LessThan(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = (num1 < num2) as i64;
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Equal(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 == num2) as i32;
-            instr_pointer += 1;
-        }
+    Bytecode::LessEqual(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = (num1 <= num2) as i64;
+        *instr_pointer += 1;
+    }
 
-        Bytecode::NotEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 != num2) as i32;
-            instr_pointer += 1;
-        }
+    Bytecode::Equal(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = (num1 == num2) as i64;
+        *instr_pointer += 1;
+    }
+
+    Bytecode::NotEqual(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = (num1 != num2) as i64;
+        *instr_pointer += 1;
+    }
+
+    Bytecode::GreaterThan(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = (num1 > num2) as i64;
+        *instr_pointer += 1;
+    }
+
+    Bytecode::GreaterEqual(dest, src1, src2) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        let num2 = read_integer_value(&frame.ints, src2);
+        frame.ints[*dest] = (num1 >= num2) as i64;
+        *instr_pointer += 1;
+    }
+
+    Bytecode::Label(_) => {
+        *instr_pointer += 1;
+    }
+
+    Bytecode::Jmp(jump) => {
+        *instr_pointer = *jump;
+    }
 
-        Bytecode::GreaterThan(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 > num2) as i32;
-            instr_pointer += 1;
+    Bytecode::BranchIf(src1, jump) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        match num1 {
+        0 => {*instr_pointer += 1;}
+        1 => {*instr_pointer = *jump;}
+        _ => {
+            let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
+            return error(MAX_LINE, codes::INVALID_BRANCH_VALUE, e);
+        }
         }
+    }
 
-        Bytecode::GreaterEqual(dest, src1, src2) => {
-            let num1 = read_integer_value(&variables, src1);
-            let num2 = read_integer_value(&variables, src2);
-            let dest = variables.get_mut(dest).unwrap();
-            *dest = (num1 >= num2) as i32;
-            instr_pointer += 1;
+    Bytecode::BranchIfn(src1, jump) => {
+        let num1 = read_integer_value(&frame.ints, src1);
+        match num1 {
+        0 => {*instr_pointer = *jump;}
+        1 => {*instr_pointer += 1;}
+        _ => {
+            let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
+            return error(MAX_LINE, codes::INVALID_BRANCH_VALUE, e);
         }
+        }
+    }
+
+    Bytecode::Widen(dest, src) => {
+        frame.ints[*dest] = read_integer_value(&frame.ints, src);
+        *instr_pointer += 1;
+    }
+
+    Bytecode::Narrow(dest, src) => {
+        let num = read_integer_value(&frame.ints, src);
+        frame.ints[*dest] = if Width::Int.holds(num) {
+            num
+        } else {
+            self.limits.overflow.overflowed(Width::Int, num, num, format_args!("int({})", num))?
+        };
+        *instr_pointer += 1;
+    }
 
-        Bytecode::Label(_) => {
-            instr_pointer += 1;
+    Bytecode::Call(dest, function_index, parameters) => {
+         let callee = &self.calls[*function_index];
+         // the parameters are the first slots of the callee, and their number was checked when the IR was loaded.
+         let mut callee_frame = self.free_frames.pop().unwrap_or_default();
+         callee_frame.reset(callee);
+         for (i, p) in parameters.iter().enumerate() {
+              callee_frame.ints[i] = read_integer_value(&frame.ints, p);
+         }
+         if depth == self.limits.call_depth {
+             let e = format!("Runtime Error: maximum call depth {} exceeded. Call chain: {}", self.limits.call_depth, self.call_chain(callee));
+             return error(MAX_LINE, codes::CALL_DEPTH_EXCEEDED, e);
+         }
+         if let Some(limit) = self.limits.array_memory.filter(|limit| self.array_memory + callee.array_memory > *limit) {
+             let e = format!("Runtime Error: array memory limit of {} bytes exceeded. The calls running have arrays of {} bytes, and {} needs {} more.", limit, self.array_memory, callee.name, callee.array_memory);
+             return error(MAX_LINE, codes::MEMORY_LIMIT_EXCEEDED, e);
+         }
+         self.array_memory += callee.array_memory;
+         *instr_pointer += 1;
+         self.stack.push(Activation { function: callee, frame: callee_frame, instr_pointer: 0, result: *dest });
+    }
+
+    Bytecode::Return(src1) => {
+        let num = read_integer_value(&frame.ints, src1);
+        if depth == 1 {
+            // main returns an int, which was checked when the IR was loaded.
+            return Ok(Some(num as i32));
         }
+        self.return_to_caller(num);
+    }
 
-        Bytecode::Jmp(jump) => {
-            instr_pointer = *jump;
+    }
+    Ok(None)
+}
+
+// a runtime error, with the backtrace of the calls that were running when it happened.
+fn stack_trace(&self, e: IRError) -> Diagnostic {
+    let mut diagnostic: Diagnostic = e.into();
+    if !self.stack.is_empty() {
+        let (function, instruction) = self.position(0);
+        diagnostic.line = function.locations[instruction].source;
+        diagnostic.file = function.file.clone();
+    }
+    let calls = self.stack.len();
+    for call in 0..calls {
+        // a deep recursion only shows its innermost calls, and main.
+        if call == TRACE_CALLS && calls > TRACE_CALLS + 1 {
+            diagnostic.trace.push(format!("... {} more calls", calls - TRACE_CALLS - 1));
         }
+        if call < TRACE_CALLS || call + 1 == calls {
+            diagnostic.trace.push(self.describe(call));
+        }
+    }
+    diagnostic
+}
 
-        Bytecode::BranchIf(src1, jump) => {
-            let num1 = read_integer_value(&variables, src1);
-            match num1 {
-            0 => {instr_pointer += 1;}
-            1 => {instr_pointer = *jump;}
-            _ => {
-                let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
-                return error(MAX_LINE, e);
-            }
-            }
+// ends the call on top of the stack, handing its frame back for the next call.
+fn return_to_caller(&mut self, value: i64) {
+    let finished = self.stack.pop().unwrap();
+    self.array_memory -= finished.function.array_memory;
+    let caller = self.stack.last_mut().unwrap();
+    caller.frame.ints[finished.result] = value;
+    self.free_frames.push(finished.frame);
+}
+
+// run_bytecode() with the trace or the profile, which look at every instruction.
+// the trace logs the instructions of the functions it follows until it has logged enough, the profile counts them all.
+fn run_observed(&mut self) -> Result<i32, IRError> {
+    loop {
+        let (function, instruction) = self.position(0);
+        let depth = self.stack.len();
+        let traced = self.tracer.as_ref().is_some_and(|tracer| tracer.trace.functions.is_empty() || tracer.trace.functions.contains(&function.name));
+        let operands = if traced { self.operands() } else { String::new() };
+        if let Some(profile) = self.profile.as_deref_mut() {
+            profile.count(instruction);
         }
 
-        Bytecode::BranchIfn(src1, jump) => {
-            let num1 = read_integer_value(&variables, src1);
-            match num1 {
-            0 => {instr_pointer = *jump;}
-            1 => {instr_pointer += 1;}
-            _ => {
-                let e = format!("Runtime Error. Branch on a variable that is neither 0 or 1. The value is: {}", num1);
-                return error(MAX_LINE, e);
+        let result = self.run_instruction();
+
+        if let (Some(profile), Ok(None)) = (self.profile.as_deref_mut(), &result) {
+            match (self.stack.len().cmp(&depth), &function.body[instruction]) {
+            (Ordering::Greater, Bytecode::Call(_, callee, _)) => profile.call(*callee),
+            (Ordering::Less, _) => profile.ret(),
+            _ => {}
             }
+        }
+        if traced {
+            let effect = match &result {
+            Ok(_) => self.effect(function, instruction, &result),
+            Err(_) => String::from("a runtime error"),
+            };
+            let tracer = self.tracer.as_mut().unwrap();
+            let instr = &function.body[instruction];
+            let arrow = if effect.is_empty() { "" } else { " -> " };
+            // like the log, the trace is only for the user, and running goes on when it cannot be written.
+            let _ = writeln!(tracer.out, "{} #{}, {}: {}{}{}{}", function.name, instruction, function.locations[instruction], instr.opcode(), operands, arrow, effect);
+            tracer.logged += 1;
+            if Some(tracer.logged) == tracer.trace.max_steps {
+                let _ = writeln!(tracer.out, "... the trace stops after {} instructions, the program goes on.", tracer.logged);
+                self.tracer = None;
             }
         }
 
-        Bytecode::Call(dest, function_index, parameters) => {
-             let function = &calls[*function_index];
-             let mut pass = vec![];
-             for p in parameters {
-                  let num1: i32 = read_integer_value(&variables, p);
-                  pass.push(num1);
-             }
-
-             let eax = run_bytecode(stdin, function, calls, &pass)?;
-             let dest = variables.get_mut(dest).unwrap();
-             *dest = eax;
-             instr_pointer += 1;
+        if let Some(return_value) = result? {
+            return Ok(return_value);
         }
-
-        Bytecode::Return(src1) => {
-            let num = read_integer_value(&variables, src1);
-            return Ok(num);
+        if self.tracer.is_none() && self.profile.is_none() {
+            return self.run_bytecode();
         }
+    }
+}
+
+// the operands of the instruction about to run, with the values of the variables among them: ' a, a=4, 2'.
+fn operands(&self) -> String {
+    let Activation { function, frame, instr_pointer, .. } = self.stack.last().unwrap();
+    let int = |slot: &usize| int_name(function, *slot);
+    let op = |op: &Op| match op {
+        Op::Num(num) => num.to_string(),
+        Op::Var(slot) => format!("{}={}", int(slot), frame.ints[*slot]),
+    };
+    let text = match &function.body[*instr_pointer] {
+    Bytecode::End => String::new(),
+    Bytecode::Label(_) => label_name(function, *instr_pointer).to_string(),
+    Bytecode::Int(slot) | Bytecode::Long(slot) | Bytecode::In(slot) => int(slot).to_string(),
+    Bytecode::IntArray(slot) => array_name(function, *slot).to_string(),
+    Bytecode::Out(src) | Bytecode::Return(src) => op(src),
+    Bytecode::Mov(dest, src) => {
+        let dest = match dest {
+        MemWrite::IntVar(slot) => int(slot).to_string(),
+        MemWrite::ArrayWrite(slot, index) => format!("[{} + {}]", array_name(function, *slot), op(index)),
+        };
+        let src = match src {
+        MemRead::IntVar(slot) => op(&Op::Var(*slot)),
+        MemRead::Number(num) => num.to_string(),
+        MemRead::ArrayRead(slot, index) => match read_memory(frame, src) {
+            Ok(value) => format!("[{} + {}]={}", array_name(function, *slot), op(index), value),
+            Err(_) => format!("[{} + {}]", array_name(function, *slot), op(index)),
+        },
+        };
+        format!("{dest}, {src}")
+    }
+    Bytecode::Widen(dest, src) | Bytecode::Narrow(dest, src) => format!("{}, {}", int(dest), op(src)),
+    Bytecode::Add(_, dest, src1, src2) | Bytecode::Sub(_, dest, src1, src2) | Bytecode::Mult(_, dest, src1, src2)
+    | Bytecode::Div(_, dest, src1, src2) | Bytecode::Mod(dest, src1, src2)
+    | Bytecode::LessThan(dest, src1, src2) | Bytecode::LessEqual(dest, src1, src2) | Bytecode::NotEqual(dest, src1, src2)
+    | Bytecode::Equal(dest, src1, src2) | Bytecode::GreaterEqual(dest, src1, src2) | Bytecode::GreaterThan(dest, src1, src2) => {
+        format!("{}, {}, {}", int(dest), op(src1), op(src2))
+    }
+    Bytecode::Call(dest, callee, parameters) => {
+        let parameters: Vec<String> = parameters.iter().map(op).collect();
+        format!("{}, {}({})", int(dest), self.calls[*callee].name, parameters.join(", "))
+    }
+    Bytecode::Jmp(jump) => label_name(function, *jump).to_string(),
+    Bytecode::BranchIf(src, jump) | Bytecode::BranchIfn(src, jump) => format!("{}, {}", op(src), label_name(function, *jump)),
+    };
+    if text.is_empty() { text } else { format!(" {text}") }
+}
 
+// what an instruction of function that ran without an error changed: 'a = 8', 'jumps', 'calls add'.
+fn effect(&self, function: &FunctionBytecode, instruction: usize, result: &Result<Option<i32>, IRError>) -> String {
+    let Activation { function: now, frame, instr_pointer, .. } = self.stack.last().unwrap();
+    let int = |slot: &usize| format!("{} = {}", int_name(function, *slot), frame.ints[*slot]);
+    match &function.body[instruction] {
+    Bytecode::Label(_) | Bytecode::Out(_) => String::new(),
+    Bytecode::Int(dest) | Bytecode::Long(dest) | Bytecode::In(dest) | Bytecode::Mov(MemWrite::IntVar(dest), _)
+    | Bytecode::Widen(dest, _) | Bytecode::Narrow(dest, _)
+    | Bytecode::Add(_, dest, _, _) | Bytecode::Sub(_, dest, _, _) | Bytecode::Mult(_, dest, _, _)
+    | Bytecode::Div(_, dest, _, _) | Bytecode::Mod(dest, _, _)
+    | Bytecode::LessThan(dest, _, _) | Bytecode::LessEqual(dest, _, _) | Bytecode::NotEqual(dest, _, _)
+    | Bytecode::Equal(dest, _, _) | Bytecode::GreaterEqual(dest, _, _) | Bytecode::GreaterThan(dest, _, _) => int(dest),
+    Bytecode::IntArray(slot) => format!("{} = {:?}", array_name(function, *slot), frame.arrays[*slot]),
+    Bytecode::Mov(MemWrite::ArrayWrite(slot, index), _) => {
+        let index = read_integer_value(&frame.ints, index);
+        format!("[{} + {}] = {}", array_name(function, *slot), index, frame.arrays[*slot][index as usize])
+    }
+    Bytecode::Jmp(_) => String::from("jumps"),
+    Bytecode::BranchIf(src, _) | Bytecode::BranchIfn(src, _) => {
+        // the label can be the next instruction, so whether it jumped is told by the value it branched on.
+        let jumps = read_integer_value(&frame.ints, src) == matches!(function.body[instruction], Bytecode::BranchIf(..)) as i64;
+        String::from(if jumps { "jumps" } else { "does not jump" })
+    }
+    Bytecode::Call(..) => format!("calls {}", now.name),
+    Bytecode::End | Bytecode::Return(_) => match result {
+        Ok(Some(value)) => format!("{} returns {}", function.name, value),
+        // the caller has moved past the call, and the result is in the slot the call names.
+        _ => match &now.body[*instr_pointer - 1] {
+            Bytecode::Call(dest, _, _) => format!("back in {}, {} = {}", now.name, int_name(now, *dest), frame.ints[*dest]),
+            _ => format!("back in {}", now.name),
+        },
+    },
+    }
+}
+
+// the functions on the stack from main up to callee, with a function calling itself over and over written once:
+// 'main -> fib x10000'.
+fn call_chain(&self, callee: &FunctionBytecode) -> String {
+    let mut chain: Vec<(&str, usize)> = vec![];
+    for name in self.stack.iter().map(|call| call.function.name.as_str()).chain([callee.name.as_str()]) {
+        match chain.last_mut() {
+        Some((last, count)) if *last == name => *count += 1,
+        _ => chain.push((name, 1)),
         }
     }
+    // a long chain of functions calling each other only shows its ends.
+    let shown: Vec<String> = chain.iter().map(|(name, count)| if *count == 1 { name.to_string() } else { format!("{name} x{count}") }).collect();
+    if shown.len() > 10 {
+        return format!("{} -> ... {} more ... -> {}", shown[..5].join(" -> "), shown.len() - 10, shown[shown.len() - 5..].join(" -> "));
+    }
+    shown.join(" -> ")
+}
 
-    return Ok(0);
+}
+
+// the names of slots and labels, for the trace.
+fn int_name(function: &FunctionBytecode, slot: usize) -> &str {
+    function.variables.iter().find(|(_, variable)| matches!(variable, VariableType::IntVar(s) if *s == slot)).map_or("?", |(name, _)| name)
+}
+
+fn array_name(function: &FunctionBytecode, slot: usize) -> &str {
+    function.variables.iter().find(|(_, variable)| matches!(variable, VariableType::ArrayVar(s) if *s == slot)).map_or("?", |(name, _)| name)
+}
+
+fn label_name(function: &FunctionBytecode, instruction: usize) -> &str {
+    function.labels.iter().find(|(_, i)| **i == instruction).map_or("?", |(name, _)| name)
 }
 
 fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<Op, IRError> {
@@ -578,66 +1234,102 @@ fn lookup_integer_variable_id(line: usize, function: &FunctionBytecode, ident: &
          match id {
          VariableType::IntVar(id) => Ok(Op::Var(*id)),
 
-         VariableType::ArrayVar(_,_) => {
+         VariableType::ArrayVar(_) => {
              let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
-             return error(line, f);
+             return error(line, codes::IR_TYPE_MISMATCH, f);
          }
 
          }
     } else {
          let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
-         return error(line, f);
+         return error(line, codes::IR_UNDECLARED_IDENTIFIER, f);
     }
 }
 
-fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<i32, IRError> {
+fn lookup_variable_dest_id(line: usize, function: &FunctionBytecode, ident: &String) -> Result<usize, IRError> {
     if let Some(id) = function.variables.get(ident) {
          match id {
          VariableType::IntVar(id) => Ok(*id),
 
-         VariableType::ArrayVar(_,_) => {
+         VariableType::ArrayVar(_) => {
              let f = format!("invalid operand. '{}' is an array, not an integer.", ident);
-             return error(line, f);
+             return error(line, codes::IR_TYPE_MISMATCH, f);
          }
 
          }
     } else {
          let f = format!("invalid instruction. identifier '{}' has not been declared.", ident);
-         return error(line, f);
+         return error(line, codes::IR_UNDECLARED_IDENTIFIER, f);
+    }
+}
+
+// the width of an operand. a number fits either, unless it is too big for an int.
+fn width(function: &FunctionBytecode, op: &Op) -> Option<Width> {
+    match op {
+    Op::Num(num) if i32::try_from(*num).is_err() => Some(Width::Long),
+    Op::Num(_) => None,
+    Op::Var(slot) => Some(function.widths[*slot]),
+    }
+}
+
+// the width an instruction computes in, when its operands are all ints or all longs.
+fn same_width(line: usize, function: &FunctionBytecode, opcode: &str, widths: &[Option<Width>]) -> Result<Width, IRError> {
+    let mut known = widths.iter().flatten();
+    let first = known.next().copied().unwrap_or(Width::Int);
+    if known.any(|width| *width != first) {
+        let f = format!("invalid instruction. '{}' mixes ints and longs in {}, convert them with '%widen' or '%narrow' first.", opcode, function.name);
+        return error(line, codes::IR_TYPE_MISMATCH, f);
+    }
+    Ok(first)
+}
+
+// the elements and indexes of arrays are ints.
+fn int_operand(line: usize, function: &FunctionBytecode, op: &Op) -> Result<(), IRError> {
+    if width(function, op) == Some(Width::Long) {
+        return error(line, codes::IR_TYPE_MISMATCH, String::from("invalid operand. arrays have ints as elements and indexes, not longs."));
     }
+    Ok(())
 }
 
-fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut FunctionBytecode, labels_hash: &mut HashMap<String, usize>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Bytecode, IRError> {
+// location is set to where the instruction is.
+fn parse_instruction(serialized_line: &mut usize, line: usize, location: &mut Location, function: &mut FunctionBytecode, labels_hash: &mut HashMap<String, usize>, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<Bytecode, IRError> {
 
-    // get rid of newlines.
+    // get rid of newlines, and keep the source line of the last mark.
     loop {
-        if matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::EndInstr) {
+        match peek_result(*serialized_line, tokens, *idx)? {
+        IRTok::EndInstr => {
             *serialized_line += 1;
             *idx += 1;
-        } else {
-            break;
+        }
+        IRTok::SourceLine(line) => {
+            location.source = Some(*line);
+            *idx += 1;
+        }
+        _ => break,
         }
     }
+    location.ir = *serialized_line;
 
     let bytecode: Bytecode;
     let opcode = peek_result(*serialized_line, tokens, *idx)?;
     match opcode {
 
     // declarations.
-    IRTok::Int => {
+    IRTok::Int | IRTok::Long => {
+        let width = if matches!(opcode, IRTok::Long) { Width::Long } else { Width::Int };
         *idx += 1;
         let ident = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => ident,
-        _ => return error(*serialized_line, String::from("invalid instruction. expected identifier like '%int variable'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, format!("invalid instruction. expected identifier like '%{} variable'", width.name())),
         };
 
         if let Some(_) = function.variables.get(ident) {
              let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
-             return error(*serialized_line, f);
+             return error(*serialized_line, codes::IR_DUPLICATE_DECLARATION, f);
         } else {
-             let id = get_id(function);
-             function.variables.insert(ident.clone(), VariableType::IntVar(id));
-             bytecode = Bytecode::Int(id);
+             let slot = new_int_slot(function, width);
+             function.variables.insert(ident.clone(), VariableType::IntVar(slot));
+             bytecode = if width == Width::Long { Bytecode::Long(slot) } else { Bytecode::Int(slot) };
         }
     }
 
@@ -645,32 +1337,35 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         *idx += 1;
         let ident = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => ident,
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%int[] array, 10'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%int[] array, 10'")),
         };
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
-            return error(*serialized_line, String::from("invalid instruction. expected format like '%int[] array, 10'"));
+            return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%int[] array, 10'"));
         }
 
         let num = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Num(num) => *num,
         IRTok::Var(ident) => {
-          return error(*serialized_line, format!("array cannot be a variable '{ident}' length. must be a number."));
+          return error(*serialized_line, codes::INVALID_IR, format!("array cannot be a variable '{ident}' length. must be a number."));
         }
-        _ => {return error(*serialized_line, String::from("invalid instruction. expected format like '%int[] array, 10'"));}
+        _ => {return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%int[] array, 10'"));}
         };
 
         if num <= 0 {
-          return error(*serialized_line, String::from("array size cannot be less than or equal to zero."));
+          return error(*serialized_line, codes::INVALID_IR, String::from("array size cannot be less than or equal to zero."));
+        }
+        if num > i32::MAX as i64 {
+          return error(*serialized_line, codes::INVALID_IR, format!("array size cannot be more than {}.", i32::MAX));
         }
 
         if let Some(_) = function.variables.get(ident) {
              let f = format!("invalid instruction. identifier '{}' declared too many times", ident);
-             return error(*serialized_line, f);
+             return error(*serialized_line, codes::IR_DUPLICATE_DECLARATION, f);
         } else {
-             let id = get_id(function);
-             function.variables.insert(ident.clone(), VariableType::ArrayVar(id, num));
-             bytecode = Bytecode::IntArray(id, num);
+             let slot = new_array_slot(function, num as usize);
+             function.variables.insert(ident.clone(), VariableType::ArrayVar(slot));
+             bytecode = Bytecode::IntArray(slot);
         }
     }
 
@@ -679,11 +1374,11 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         *idx += 1;
         let op = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%call value, function(a,b)'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%call value, function(a,b)'")),
         };
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
-            return error(*serialized_line, String::from("invalid instruction. missing comma ',' in between '%call value, function(a,b)'"));
+            return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. missing comma ',' in between '%call value, function(a,b)'"));
         }
 
         let func_id = match peek_result(*serialized_line, tokens, *idx)? {
@@ -692,11 +1387,11 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             *idx += 1;
             func_id 
         }
-        _ => return error(*serialized_line, String::from("invalid instruction. expected function name from '%call value, function(a,b)'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected function name from '%call value, function(a,b)'")),
         };
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::LParen) {
-            return error(*serialized_line, String::from("invalid instruction. expected '(' in between '%call value, function(a,b)'"));
+            return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected '(' in between '%call value, function(a,b)'"));
         }
 
         let mut parameters = vec![];
@@ -719,13 +1414,13 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                     *idx += 1;
                 }
             }
-            _ => return error(*serialized_line, String::from("invalid calling convention. must be in the format '%call value, function(a,b)'")),
+            _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid calling convention. must be in the format '%call value, function(a,b)'")),
 
             }
         }
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::RParen) {
-            return error(*serialized_line, String::from("invalid instruction. missing ')' in between '%call value, function(a,b)'"));
+            return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. missing ')' in between '%call value, function(a,b)'"));
         }
         bytecode = Bytecode::Call(op, func_id, parameters);
     }
@@ -735,8 +1430,9 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         let op = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
         IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%ret variable'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%ret variable'")),
         };
+        same_width(*serialized_line, function, "%ret", &[Some(function.returns), width(function, &op)])?;
         bytecode = Bytecode::Return(op);
     }
 
@@ -746,7 +1442,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         let src = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
         IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%out variable'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%out variable'")),
         };
         bytecode = Bytecode::Out(src);
     }
@@ -755,7 +1451,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         *idx += 1;
         let src = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%input variable'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%input variable'")),
         };
         bytecode = Bytecode::In(src);
     }
@@ -768,24 +1464,27 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             let dest = lookup_variable_dest_id(*serialized_line, function, ident)?;
 
             if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
-                return error(*serialized_line, String::from("invalid instruction. missing comma. expected format like '%mov variable, 10'"));
+                return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. missing comma. expected format like '%mov variable, 10'"));
             }
             
             let src = match next_result(*serialized_line, tokens, idx)? {
             IRTok::Var(ident) => {
                 if let Some(id) = function.variables.get(ident) {
                      match id {
-                     VariableType::IntVar(id) => MemRead::IntVar(*id),
+                     VariableType::IntVar(id) => {
+                         same_width(*serialized_line, function, "%mov", &[Some(function.widths[dest]), Some(function.widths[*id])])?;
+                         MemRead::IntVar(*id)
+                     }
          
-                     VariableType::ArrayVar(_,_) => {
+                     VariableType::ArrayVar(_) => {
                          let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                         return error(*serialized_line, f);
+                         return error(*serialized_line, codes::IR_TYPE_MISMATCH, f);
                      }
          
                      }
                 } else {
                      let f = format!("invalid instruction. identifier '{}' has not been declared", ident);
-                     return error(*serialized_line, f);
+                     return error(*serialized_line, codes::IR_UNDECLARED_IDENTIFIER, f);
                 }
             }
 
@@ -794,48 +1493,54 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                 (IRTok::Var(array), IRTok::Plus, IRTok::Num(index), IRTok::RBrace) => {
                     if let Some(id) = function.variables.get(array) {
                         match id {
-                        VariableType::ArrayVar(id,_) => {
+                        VariableType::ArrayVar(id) => {
+                            int_operand(*serialized_line, function, &Op::Var(dest))?;
                             MemRead::ArrayRead(*id, Op::Num(*index))
                         }
                         _ => {
                            let f = format!("invalid '%mov' statement. {} is an integer, not an array.", array);
-                           return error(*serialized_line, f);
+                           return error(*serialized_line, codes::IR_TYPE_MISMATCH, f);
                         }
 
                         }
                     } else {
                         let f = format!("invalid instruction. no such identifier '{}'.", array);
-                        return error(*serialized_line, f);
+                        return error(*serialized_line, codes::IR_UNDECLARED_IDENTIFIER, f);
                     }
                 }
 
                 (IRTok::Var(array), IRTok::Plus, IRTok::Var(variable), IRTok::RBrace) => {
                     if let Some(id) = function.variables.get(array) {
                         match id {
-                        VariableType::ArrayVar(id,_) => {
-                            
-                            MemRead::ArrayRead(*id, lookup_integer_variable_id(*serialized_line, function, variable)?)
+                        VariableType::ArrayVar(id) => {
+                            let index = lookup_integer_variable_id(*serialized_line, function, variable)?;
+                            int_operand(*serialized_line, function, &Op::Var(dest))?;
+                            int_operand(*serialized_line, function, &index)?;
+                            MemRead::ArrayRead(*id, index)
                         }
                         _ => {
                            let f = format!("invalid '%mov' statement. {} is an integer, not an array.", array);
-                           return error(*serialized_line, f);
+                           return error(*serialized_line, codes::IR_TYPE_MISMATCH, f);
                         }
 
                         }
                     } else {
                         let f = format!("invalid instruction. no such identifier '{}'.", array);
-                        return error(*serialized_line, f);
+                        return error(*serialized_line, codes::IR_UNDECLARED_IDENTIFIER, f);
                     }
                 }
 
-                _ => return error(*serialized_line, String::from("invalid '%mov' statement. expected '%mov var, [array + index]'")),
+                _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid '%mov' statement. expected '%mov var, [array + index]'")),
 
                 }
 
             }
 
-            IRTok::Num(num) => MemRead::Number(*num),
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
+            IRTok::Num(num) => {
+                same_width(*serialized_line, function, "%mov", &[Some(function.widths[dest]), width(function, &Op::Num(*num))])?;
+                MemRead::Number(*num)
+            }
+            _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov variable, 10'")),
             };
 
             bytecode = Bytecode::Mov(MemWrite::IntVar(dest), src);
@@ -848,66 +1553,73 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
                     match id {
                     VariableType::IntVar(_) => {
                         let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                        return error(*serialized_line, f);
+                        return error(*serialized_line, codes::IR_TYPE_MISMATCH, f);
                     }
          
-                    VariableType::ArrayVar(dest,_) => {
+                    VariableType::ArrayVar(dest) => {
                         *dest
                     }
 
                     }
                 } else {
-                    return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
+                    return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
                 }
             }
 
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
+            _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
             };
 
             if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Plus) {
-                return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
+                return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
             }
 
             let index = match next_result(*serialized_line, tokens, idx)? {
             IRTok::Var(id) => lookup_integer_variable_id(*serialized_line, function, id)?,
             IRTok::Num(num) => Op::Num(*num),
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
+            _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov [array + 10], 10'")),
             };
+            int_operand(*serialized_line, function, &index)?;
 
             if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::RBrace) {
-                return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
+                return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
             }
 
             if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
-                return error(*serialized_line, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
+                return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov [array + 10], 10'"));
             }
 
             let src = match next_result(*serialized_line, tokens, idx)? {
             IRTok::Var(ident) => {
                 if let Some(id) = function.variables.get(ident) {
                      match id {
-                     VariableType::IntVar(id) => MemRead::IntVar(*id),
+                     VariableType::IntVar(id) => {
+                         int_operand(*serialized_line, function, &Op::Var(*id))?;
+                         MemRead::IntVar(*id)
+                     }
          
-                     VariableType::ArrayVar(_,_) => {
+                     VariableType::ArrayVar(_) => {
                          let f = format!("invalid '%mov' statement. {} is an array, not an integer.", ident);
-                         return error(*serialized_line, f);
+                         return error(*serialized_line, codes::IR_TYPE_MISMATCH, f);
                      }
          
                      }
                 } else {
                      let f = format!("invalid instruction. identifier '{}' has not been defined.", ident);
-                     return error(*serialized_line, f);
+                     return error(*serialized_line, codes::IR_UNDECLARED_IDENTIFIER, f);
                 }
             }
 
-            IRTok::Num(num) => MemRead::Number(*num),
-            _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
+            IRTok::Num(num) => {
+                int_operand(*serialized_line, function, &Op::Num(*num))?;
+                MemRead::Number(*num)
+            }
+            _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov variable, 10'")),
             };
 
             bytecode = Bytecode::Mov(MemWrite::ArrayWrite(dest, index), src);
         }
 
-        _ => return error(*serialized_line, String::from("invalid instruction. expected format like '%mov variable, 10'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected format like '%mov variable, 10'")),
         }
  
     }
@@ -915,67 +1627,101 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
     IRTok::Add => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Add(dest, src1, src2);
+        let width = same_width(*serialized_line, function, "%add", &[Some(function.widths[dest]), width(function, &src1), width(function, &src2)])?;
+        bytecode = Bytecode::Add(width, dest, src1, src2);
     }
 
     IRTok::Sub => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Sub(dest, src1, src2);
+        let width = same_width(*serialized_line, function, "%sub", &[Some(function.widths[dest]), width(function, &src1), width(function, &src2)])?;
+        bytecode = Bytecode::Sub(width, dest, src1, src2);
     }
 
     IRTok::Mult => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Mult(dest, src1, src2);
+        let width = same_width(*serialized_line, function, "%mult", &[Some(function.widths[dest]), width(function, &src1), width(function, &src2)])?;
+        bytecode = Bytecode::Mult(width, dest, src1, src2);
     }
 
     IRTok::Div => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
-        bytecode = Bytecode::Div(dest, src1, src2);
+        let width = same_width(*serialized_line, function, "%div", &[Some(function.widths[dest]), width(function, &src1), width(function, &src2)])?;
+        bytecode = Bytecode::Div(width, dest, src1, src2);
     }
 
     IRTok::Mod => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%mod", &[Some(function.widths[dest]), width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::Mod(dest, src1, src2);
     }
 
+    // conversions.
+    IRTok::Widen | IRTok::Narrow => {
+        let (opcode, from, to) = if matches!(opcode, IRTok::Widen) { ("%widen", Width::Int, Width::Long) } else { ("%narrow", Width::Long, Width::Int) };
+        *idx += 1;
+        let dest = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(ident) => lookup_variable_dest_id(*serialized_line, function, ident)?,
+        _ => return error(*serialized_line, codes::INVALID_IR, format!("invalid instruction. expected format like '{} dest, src'", opcode)),
+        };
+        if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
+            return error(*serialized_line, codes::INVALID_IR, format!("invalid instruction. expected format like '{} dest, src'", opcode));
+        }
+        let src = match next_result(*serialized_line, tokens, idx)? {
+        IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
+        IRTok::Num(num) => Op::Num(*num),
+        _ => return error(*serialized_line, codes::INVALID_IR, format!("invalid instruction. expected format like '{} dest, src'", opcode)),
+        };
+        if function.widths[dest] != to || width(function, &src) == Some(to) {
+            let f = format!("invalid instruction. '{}' makes a {} from a {}.", opcode, to.name(), from.name());
+            return error(*serialized_line, codes::IR_TYPE_MISMATCH, f);
+        }
+        bytecode = if to == Width::Long { Bytecode::Widen(dest, src) } else { Bytecode::Narrow(dest, src) };
+    }
+
     // comparison operators.
     IRTok::LessThan => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%lt", &[width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::LessThan(dest, src1, src2);
     }
 
     IRTok::LessEqual => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%le", &[width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::LessEqual(dest, src1, src2);
     }
 
     IRTok::NotEqual => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%neq", &[width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::NotEqual(dest, src1, src2);
     }
 
     IRTok::Equal => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%eq", &[width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::Equal(dest, src1, src2);
     }
 
     IRTok::GreaterEqual => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%ge", &[width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::GreaterEqual(dest, src1, src2);
     }
 
     IRTok::GreaterThan => {
         *idx += 1;
         let (dest, src1, src2) = addr_code3(*serialized_line, function, tokens, idx)?;
+        same_width(*serialized_line, function, "%gt", &[width(function, &src1), width(function, &src2)])?;
         bytecode = Bytecode::GreaterThan(dest, src1, src2);
     }
 
@@ -989,7 +1735,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             *idx += 1;
         }
 
-        _ => return error(*serialized_line, String::from("%jmp requires a label such as '%jmp :label'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("%jmp requires a label such as '%jmp :label'")),
         }
     }
 
@@ -998,11 +1744,11 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         if let None = labels_hash.get(name) {
             labels_hash.insert(name.clone(), line);
         } else {
-            return error(*serialized_line, format!("label {} already defined.", name));
+            return error(*serialized_line, codes::IR_INVALID_LABEL, format!("label {} already defined.", name));
         }
         bytecode = Bytecode::Label(line);
         if !matches!(peek_result(*serialized_line, tokens, *idx)?, IRTok::EndInstr) {
-            return error(*serialized_line, format!("invalid opcode '{}'. labels can be declared using ':label'", name));
+            return error(*serialized_line, codes::INVALID_IR, format!("invalid opcode '{}'. labels can be declared using ':label'", name));
         }
     }
 
@@ -1011,11 +1757,11 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         let dest = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
         IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("%branch_if requires an identifier 'TF'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("%branch_if requires an identifier 'TF'")),
         };
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
-            return error(*serialized_line, String::from("missing comma ',' from %branch_if instruction."));
+            return error(*serialized_line, codes::INVALID_IR, String::from("missing comma ',' from %branch_if instruction."));
         }
 
         match peek_result(*serialized_line, tokens, *idx)? {
@@ -1023,7 +1769,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             bytecode = Bytecode::BranchIf(dest, *idx);
             *idx += 1;
         }
-        _ => return error(*serialized_line, String::from("%branch_if requires a label ':label'. (e.g. '%branch_if TF, :label')")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("%branch_if requires a label ':label'. (e.g. '%branch_if TF, :label')")),
         }
     }
 
@@ -1032,11 +1778,11 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
         let dest = match next_result(*serialized_line, tokens, idx)? {
         IRTok::Var(ident) => lookup_integer_variable_id(*serialized_line, function, ident)?,
         IRTok::Num(num) => Op::Num(*num),
-        _ => return error(*serialized_line, String::from("%branch_ifn requires an identifier 'TF'")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("%branch_ifn requires an identifier 'TF'")),
         };
 
         if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::Comma) {
-            return error(*serialized_line, String::from("missing ',' from %branch_ifn instruction."));
+            return error(*serialized_line, codes::INVALID_IR, String::from("missing ',' from %branch_ifn instruction."));
         }
 
         match peek_result(*serialized_line, tokens, *idx)? {
@@ -1044,7 +1790,7 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
             bytecode = Bytecode::BranchIfn(dest, *idx);
             *idx += 1;
         }
-        _ => return error(*serialized_line, String::from("%branch_ifn requires a label ':label'. (e.g. '%branch_ifn TF, :label')")),
+        _ => return error(*serialized_line, codes::INVALID_IR, String::from("%branch_ifn requires a label ':label'. (e.g. '%branch_ifn TF, :label')")),
         }
     }
 
@@ -1054,17 +1800,17 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
     }
 
     IRTok::InvalidInstruction(err) => {
-        return error(*serialized_line, format!("invalid instruction opcode '{err}'. instructions must begin with an opcode such as %mov, %add, %sub"));
+        return error(*serialized_line, codes::INVALID_IR, format!("invalid instruction opcode '{err}'. instructions must begin with an opcode such as %mov, %add, %sub"));
     }
 
     _ => {
-        return error(*serialized_line, String::from("invalid instruction. instructions must begin with an opcode such as %mov, %add, %sub"));
+        return error(*serialized_line, codes::INVALID_IR, String::from("invalid instruction. instructions must begin with an opcode such as %mov, %add, %sub"));
     }
 
     }
 
     if !matches!(next_result(*serialized_line, tokens, idx)?, IRTok::EndInstr) {
-        return error(*serialized_line, String::from("expected newline."));
+        return error(*serialized_line, codes::INVALID_IR, String::from("expected newline."));
     }
 
     *serialized_line += 1;
@@ -1072,30 +1818,30 @@ fn parse_instruction(serialized_line: &mut usize, line: usize, function: &mut Fu
     return Ok(bytecode);
 }
 
-fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(i32, Op, Op), IRError> {
+fn addr_code3(serialized_line: usize, function: &FunctionBytecode, tokens: &Vec<IRTok>, idx: &mut usize) -> Result<(usize, Op, Op), IRError> {
     let dest = match next_result(serialized_line, tokens, idx)? {
     IRTok::Var(ident) => lookup_variable_dest_id(serialized_line, function, ident)?,
-    _ => return error(serialized_line, String::from("invalid dest.")),
+    _ => return error(serialized_line, codes::INVALID_IR, String::from("invalid dest.")),
     };
 
     if !matches!(next_result(serialized_line, tokens, idx)?, IRTok::Comma) {
-        return error(serialized_line, String::from("invalid instruction. expected comma between dest and src1."));
+        return error(serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected comma between dest and src1."));
     }
     
     let src1 = match next_result(serialized_line, tokens, idx)? {
     IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, ident)?,
     IRTok::Num(num) => Op::Num(*num),
-    _ => return error(serialized_line, String::from("invalid src1.")),
+    _ => return error(serialized_line, codes::INVALID_IR, String::from("invalid src1.")),
     };
 
     if !matches!(next_result(serialized_line, tokens, idx)?, IRTok::Comma) {
-        return error(serialized_line, String::from("invalid instruction. expected comma between src1 and src2."));
+        return error(serialized_line, codes::INVALID_IR, String::from("invalid instruction. expected comma between src1 and src2."));
     }
 
     let src2 = match next_result(serialized_line, tokens, idx)? {
     IRTok::Var(ident) => lookup_integer_variable_id(serialized_line, function, ident)?,
     IRTok::Num(num) => Op::Num(*num),
-    _ => return error(serialized_line, String::from("invalid src2.")),
+    _ => return error(serialized_line, codes::INVALID_IR, String::from("invalid src2.")),
     };
 
     return Ok((dest, src1, src2));
@@ -1115,7 +1861,7 @@ fn peek_result<'a>(serialized_line: usize, tokens: &'a Vec<IRTok>, index: usize)
     if index < tokens.len() {
         return Ok(&tokens[index]);
     } else {
-        return error(serialized_line, String::from("unexpected end."));
+        return error(serialized_line, codes::INVALID_IR, String::from("unexpected end."));
     }
 }
 
@@ -1133,7 +1879,7 @@ fn next_result<'a>(serialized_line: usize, tokens: &'a Vec<IRTok>, index: &mut u
         *index += 1;
         return Ok(&tokens[ret])
     } else {
-        return error(serialized_line, String::from("unexpected end."))
+        return error(serialized_line, codes::INVALID_IR, String::from("unexpected end."))
     }
 }
 
@@ -1155,6 +1901,7 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         "%func" => Some(Func),
         "%endfunc" => Some(EndFunc),
         "%int" => Some(Int),
+        "%long" => Some(Long),
         "%int[]" => Some(IntArray),
         "%call" => Some(Call),
         "%ret" => Some(Return),
@@ -1166,6 +1913,8 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         "%mult" => Some(Mult),
         "%div" => Some(Div),
         "%mod" => Some(Mod),
+        "%widen" => Some(Widen),
+        "%narrow" => Some(Narrow),
         "%lt" => Some(LessThan),
         "%le" => Some(LessEqual),
         "%neq" => Some(NotEqual),
@@ -1179,30 +1928,41 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         }
     }
 
+    // the line of a '; line N' comment.
+    fn source_line(comment: &str) -> Option<usize> {
+        comment.strip_prefix(';')?.trim().strip_prefix("line ")?.trim().parse().ok()
+    }
+
+    // a '; line N' or '; file NAME' comment.
+    fn mark(comment: &str) -> Option<IRTok> {
+        if let Some(line) = source_line(comment) {
+            return Some(IRTok::SourceLine(line));
+        }
+        let name = comment.strip_prefix(';')?.trim_start().strip_prefix("file ")?.trim();
+        Some(IRTok::SourceFile(String::from(name)))
+    }
+
+    // a number too big for a long is kept for parse_ir() to report.
     fn num_literal(s: &str) -> Option<IRTok> {
-        match s.parse::<i32>() {
+        match s.parse::<i64>() {
         Ok(val) => Some(IRTok::Num(val)),
-        Err(_) => None,
+        Err(_) => Some(IRTok::InvalidNumber(String::from(s))),
         }
     }
 
     // skip left whitespace.
-    for (i, c) in code.chars().enumerate() {
-        if c.is_whitespace() && c != '\n' {
-            continue;
-        }
-        code = &code[i..];
-        break;
-    }
+    code = code.trim_start_matches(|c: char| c.is_whitespace() && c != '\n');
  
     let mut state = StateMachine::Initial;
 
-    for (i, c) in code.chars().enumerate() {
+    // i is where c starts in bytes, and after is where the character after it does.
+    for (i, c) in code.char_indices() {
+        let after = i + c.len_utf8();
         state = match state {
 
         StateMachine::Initial => {
             if c == '\n' {
-                return (Some(IRTok::EndInstr), &code[i + 1..]);
+                return (Some(IRTok::EndInstr), &code[after..]);
             }
             if c.is_whitespace() {
                 continue;
@@ -1210,12 +1970,12 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
             match c {
             '%' => StateMachine::Lit,
             ':' => StateMachine::Label,
-            ',' => return (Some(IRTok::Comma), &code[i + 1..]),
-            '[' => return (Some(IRTok::LBrace), &code[i + 1..]),
-            '(' => return (Some(IRTok::LParen), &code[i + 1..]),
-            ')' => return (Some(IRTok::RParen), &code[i + 1..]),
-            '+' => return (Some(IRTok::Plus), &code[i + 1..]),
-            ']' => return (Some(IRTok::RBrace), &code[i + 1..]),
+            ',' => return (Some(IRTok::Comma), &code[after..]),
+            '[' => return (Some(IRTok::LBrace), &code[after..]),
+            '(' => return (Some(IRTok::LParen), &code[after..]),
+            ')' => return (Some(IRTok::RParen), &code[after..]),
+            '+' => return (Some(IRTok::Plus), &code[after..]),
+            ']' => return (Some(IRTok::RBrace), &code[after..]),
             '0'..='9' => StateMachine::Num,
             ';' => StateMachine::Comments,
             _ => StateMachine::Ident,
@@ -1229,7 +1989,7 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
             }
             if c.is_whitespace() {
                 let tok = opcode(&code[..i]);
-                return (tok, &code[i+1..]);
+                return (tok, &code[after..]);
             }
 
             StateMachine::Lit
@@ -1242,7 +2002,7 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
             }
             if c.is_whitespace() {
                 let tok = &code[..i];
-                return (Some(IRTok::Label(String::from(tok))), &code[i+1..]);
+                return (Some(IRTok::Label(String::from(tok))), &code[after..]);
             }
 
             StateMachine::Label
@@ -1250,7 +2010,13 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
 
         StateMachine::Comments => {
             if c == '\n' {
-                return (Some(IRTok::EndInstr), &code[i + 1..]);
+                // comments can have any character, so the end of the comment is found in bytes.
+                let comment = code.split('\n').next().unwrap_or("");
+                // the newline is left to end the line after a mark.
+                if let Some(mark) = mark(comment) {
+                    return (Some(mark), &code[comment.len()..]);
+                }
+                return (Some(IRTok::EndInstr), &code[comment.len() + 1..]);
             } else {
                 StateMachine::Comments
             }
@@ -1264,14 +2030,14 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
 
             if c.is_whitespace() {
                 let tok = IRTok::Var(String::from(&code[..i]));
-                return (Some(tok), &code[i+1..]);
+                return (Some(tok), &code[after..]);
             }
 
             StateMachine::Ident
         }
 
         StateMachine::Num => {
-            if c >= '0' && c <= '9' {
+            if c.is_ascii_digit() {
                 StateMachine::Num
             } else {
                 let tok = num_literal(&code[..i]);
@@ -1294,11 +2060,14 @@ fn lex_ir_token(mut code: &str) -> (Option<IRTok>, &str) {
         return (Some(tok), "");
     }
 
+    StateMachine::Label => (Some(IRTok::Label(String::from(code))), ""),
 
-    _ => {
-        println!("{:?} {}", state, code);
-        todo!()
-    }
+    StateMachine::Num => (num_literal(code), ""),
+
+    StateMachine::Comments => (mark(code), ""),
+
+    // nothing but whitespace was left.
+    StateMachine::Initial => (None, ""),
 
     }
 }
@@ -1311,6 +2080,9 @@ mod ir_tests {
     fn ir_token() {
         assert!(matches!(lex_ir_token("  %int"), (Some(IRTok::Int), _)));
         assert!(matches!(lex_ir_token(" %int[]"), (Some(IRTok::IntArray), _)));
+        assert!(matches!(lex_ir_token("%long"), (Some(IRTok::Long), _)));
+        assert!(matches!(lex_ir_token("%widen"), (Some(IRTok::Widen), _)));
+        assert!(matches!(lex_ir_token("%narrow"), (Some(IRTok::Narrow), _)));
         assert!(matches!(lex_ir_token("%call"), (Some(IRTok::Call), _)));
         assert!(matches!(lex_ir_token("%ret"), (Some(IRTok::Return), _)));
         assert!(matches!(lex_ir_token("%out"), (Some(IRTok::Out), _)));
@@ -1389,6 +2161,360 @@ mod ir_tests {
             assert!(matches!(toks[8], IRTok::EndInstr));
         }
     }
+
+    #[test]
+    fn malformed_ir() {
+        // the output of the program, or the message of its error.
+        let run = |code: &str| {
+            let mut out = vec![];
+            let result = execute_ir(code, &Limits::default(), None, None, io::empty(), &mut out).map_err(|e| (e.code, e.message));
+            result.map(|_| String::from_utf8(out).unwrap())
+        };
+        // files that end in the middle of an instruction, or have nothing in them.
+        for code in ["%func main\n%out 1", "%func main\n%jmp :end", "%func main\n%out x", "%func main\n:end", "  \n  "] {
+            assert!(run(code).is_err(), "{code}");
+        }
+        // names are not ascii.
+        assert!(run("%func main\n%int é\n%out é\n%endfunc") == Ok(String::from("0\n")));
+        assert!(run("%func main\n%out é1 ü\n%endfunc\n").is_err());
+        let error = (codes::INVALID_IR, String::from("IR line 2. number 99999999999999999999 is out of range. numbers in IR are at most 9223372036854775807."));
+        assert!(run("%func main\n%out 99999999999999999999\n%endfunc\n") == Err(error));
+        // numbers beyond the ints are longs.
+        assert!(run("%func main\n%out 99999999999\n%endfunc\n") == Ok(String::from("99999999999\n")));
+        assert!(run("%func main\n%long a\n%mov a, 99999999999\n%out a\n%endfunc\n") == Ok(String::from("99999999999\n")));
+        assert!(run("%func main\n%int a\n%mov a, 99999999999\n%endfunc\n").is_err_and(|(code, _)| code == codes::IR_TYPE_MISMATCH));
+        assert!(run("%func main\n%int a\n%add a, a, 99999999999\n%endfunc\n").is_err_and(|(code, _)| code == codes::IR_TYPE_MISMATCH));
+        // the last line does not need a newline.
+        assert!(run("%func main\n%out 1\n%endfunc") == Ok(String::from("1\n")));
+    }
+
+    #[test]
+    fn catalog_ir_examples() {
+        use crate::catalog::{Example, CATALOG};
+        for entry in CATALOG {
+            if !matches!(entry.example, Example::Ir) {
+                continue;
+            }
+            let fixed = format!("{}\n", entry.fixed);
+            assert!(parse_ir(&lex_ir(&fixed), &mut 0).is_ok(), "{}", entry.code);
+
+            // the failing examples of runtime errors are valid IR.
+            let failing = format!("{}\n", entry.failing);
+            match parse_ir(&lex_ir(&failing), &mut 0) {
+            Ok(_) => assert!(entry.code.starts_with("E04"), "{}", entry.code),
+            Err(e) => assert!(e.code == entry.code, "{}: got {}", entry.code, e),
+            }
+        }
+    }
+
+    // frames are used again by later calls, and every call still starts with its variables and arrays at 0.
+    #[test]
+    fn frames_start_empty() {
+        let code = "%func count(%int n)
+%int[] seen, 2
+%int total
+%int t
+%mov t, [seen + 1]
+%mov [seen + 1], 99
+%int more
+%neq more, n, 0
+%branch_ifn more, :base
+%int m
+%sub m, n, 1
+%call total, count(m)
+:base
+%add total, total, t
+%add total, total, n
+%ret total
+%endfunc
+
+%func main
+%int[] a, 3
+%int x
+%mov [a + 1], 7
+%call x, count(4)
+%call x, count(x)
+%endfunc
+";
+        let (result, locals) = execute_keeping_locals(code, io::empty(), io::sink()).unwrap();
+        assert!(result == 0);
+        assert!(locals == vec![(String::from("a"), Local::Array(vec![0, 7, 0])), (String::from("x"), Local::Int(55))]);
+    }
+
+    // each limit stops the program with an error of its own, and a program within them runs as before.
+    #[test]
+    fn limits() {
+        let code = "%func fill(%int n)
+%int[] a, 10
+%int r
+%mov [a + 0], n
+%mov r, [a + 0]
+%ret r
+%endfunc
+
+%func main
+%int[] b, 5
+%int i
+%int more
+:loop
+%lt more, i, LAST
+%branch_ifn more, :end
+%call more, fill(i)
+%out i
+%add i, i, 1
+%jmp :loop
+:end
+%ret i
+%endfunc
+";
+        let run = |last: &str, limits: Limits| execute_ir(&code.replace("LAST", last), &limits, None, None, io::empty(), io::sink());
+        let code_of = |last: &str, limits: Limits| run(last, limits).unwrap_err().code;
+        // 3 instructions before the loop, 13 a round, and 3 to leave it.
+        let instructions = Limits { instructions: Some(3 + 13 * 3 + 3), ..Limits::default() };
+        assert!(run("3", instructions) == Ok(3));
+        assert!(code_of("4", instructions) == codes::INSTRUCTION_LIMIT_EXCEEDED);
+
+        // main's array and one call of fill at a time.
+        let memory = Limits { array_memory: Some(60), ..Limits::default() };
+        assert!(run("3", memory) == Ok(3));
+        assert!(code_of("3", Limits { array_memory: Some(59), ..Limits::default() }) == codes::MEMORY_LIMIT_EXCEEDED);
+        assert!(code_of("3", Limits { array_memory: Some(19), ..Limits::default() }) == codes::MEMORY_LIMIT_EXCEEDED);
+
+        // 2 bytes for each of '0\n' to '9\n', and 3 for '10\n' and '11\n'.
+        let output = Limits { output: Some(26), ..Limits::default() };
+        assert!(run("12", output) == Ok(12));
+        let error = run("13", output).unwrap_err();
+        assert!(error.code == codes::OUTPUT_LIMIT_EXCEEDED && error.message == "Runtime Error: output limit of 26 bytes exceeded.");
+    }
+
+    // '%input' reads a number a line, skipping lines that are not numbers, and fails at the end of the input.
+    #[test]
+    fn input_and_output() {
+        let code = "%func main
+%int a
+%int b
+%input a
+%out a
+%input b
+%add a, a, b
+%out a
+%ret a
+%endfunc
+";
+        let run = |input: &str, output: &mut Vec<u8>| execute_ir(code, &Limits::default(), None, None, input.as_bytes(), output);
+        let mut output = vec![];
+        assert!(run("4\n  x\n -5 \n", &mut output) == Ok(-1));
+        assert!(output == b"4\n-1\n");
+
+        let mut output = vec![];
+        let error = run("4", &mut output).unwrap_err();
+        assert!(error.code == codes::END_OF_INPUT && error.trace == vec!["in main, IR line 6"]);
+        assert!(output == b"4\n");
+    }
+
+    // every arithmetic opcode at the edges of the ints, under every overflow policy.
+    #[test]
+    fn overflow() {
+        // the IR has no negative numbers, so they are subtracted from 0.
+        let set = |name: &str, num: i32| match num {
+            0.. => format!("%mov {name}, {num}\n"),
+            _ => format!("%sub {name}, 0, {}\n%sub {name}, {name}, 1\n", -(num + 1)),
+        };
+        let run = |opcode: &str, num1: i32, num2: i32, overflow: Overflow| {
+            let code = format!("%func main\n%int a\n%int b\n{}{}{opcode} a, a, b\n%ret a\n%endfunc\n", set("a", num1), set("b", num2));
+            let limits = Limits { overflow, ..Limits::default() };
+            execute_ir(&code, &limits, None, None, io::empty(), io::sink()).map_err(|e| e.code)
+        };
+        let (max, min) = (i32::MAX, i32::MIN);
+        // opcode, operands, then the result when it wraps and when it saturates. None does not overflow.
+        let cases = [
+            ("%add", max, 1, min, max),
+            ("%add", min, -1, max, min),
+            ("%add", max, min, -1, -1),
+            ("%add", max, 0, max, max),
+            ("%sub", min, 1, max, min),
+            ("%sub", max, -1, min, max),
+            ("%sub", 0, min, min, max),
+            ("%sub", -1, min, max, max),
+            ("%mult", max, 2, -2, max),
+            ("%mult", min, -1, min, max),
+            ("%mult", min, 2, 0, min),
+            ("%mult", 65536, 65536, 0, max),
+            ("%mult", -65536, 65536, 0, min),
+            ("%mult", max, -1, -max, -max),
+            ("%div", min, -1, min, max),
+            ("%div", min, 1, min, min),
+            ("%div", max, -1, -max, -max),
+            ("%mod", min, -1, 0, 0),
+            ("%mod", max, min, max, max),
+            ("%mod", min, max, -1, -1),
+        ];
+        for (opcode, num1, num2, wrapped, saturated) in cases {
+            let exact = (num1 as i64, num2 as i64);
+            let exact = match opcode {
+                "%add" => exact.0 + exact.1,
+                "%sub" => exact.0 - exact.1,
+                "%mult" => exact.0 * exact.1,
+                "%div" => exact.0 / exact.1,
+                _ => exact.0 % exact.1,
+            };
+            let case = format!("{opcode} {num1}, {num2}");
+            assert!(run(opcode, num1, num2, Overflow::Wrap) == Ok(wrapped), "{case}");
+            assert!(run(opcode, num1, num2, Overflow::Saturate) == Ok(saturated), "{case}");
+            match i32::try_from(exact) {
+            Ok(exact) => assert!(run(opcode, num1, num2, Overflow::Trap) == Ok(exact) && exact == wrapped, "{case}"),
+            Err(_) => assert!(run(opcode, num1, num2, Overflow::Trap) == Err(codes::INTEGER_OVERFLOW), "{case}"),
+            }
+        }
+        // dividing by zero is an error of its own, whatever the policy.
+        for overflow in [Overflow::Wrap, Overflow::Trap, Overflow::Saturate] {
+            assert!(run("%div", min, 0, overflow) == Err(codes::DIVIDE_BY_ZERO) && run("%mod", max, 0, overflow) == Err(codes::DIVIDE_BY_ZERO));
+        }
+        let code = "%func main\n%int a\n%mult a, 2147483647, 3\n%ret a\n%endfunc\n";
+        let limits = Limits { overflow: Overflow::Trap, ..Limits::default() };
+        let error = execute_ir(code, &limits, None, None, io::empty(), io::sink()).unwrap_err();
+        assert!(error.message == "Runtime Error: integer overflow in 2147483647 * 3." && error.trace == vec!["in main, IR line 3"]);
+    }
+
+    #[test]
+    fn longs() {
+        let run = |code: &str, overflow: Overflow| {
+            let limits = Limits { overflow, ..Limits::default() };
+            let mut out = vec![];
+            let result = execute_ir(code, &limits, None, None, io::empty(), &mut out).map_err(|e| e.code);
+            result.map(|_| String::from_utf8(out).unwrap())
+        };
+        // 20! needs a long, 21! overflows one.
+        let factorial = "%func fact(%long n) %long
+%int small
+%long r
+%lt small, n, 2
+%branch_ifn small, :more
+%ret 1
+:more
+%sub r, n, 1
+%call r, fact(r)
+%mult r, r, n
+%ret r
+%endfunc
+
+%func main
+%long f
+%widen f, N
+%call f, fact(f)
+%out f
+%ret 0
+%endfunc
+";
+        assert!(run(&factorial.replace("N", "20"), Overflow::Trap) == Ok(String::from("2432902008176640000\n")));
+        assert!(run(&factorial.replace("N", "21"), Overflow::Trap) == Err(codes::INTEGER_OVERFLOW));
+        assert!(run(&factorial.replace("N", "21"), Overflow::Saturate) == Ok(format!("{}\n", i64::MAX)));
+        assert!(run(&factorial.replace("N", "21"), Overflow::Wrap) == Ok(format!("{}\n", 2432902008176640000i64.wrapping_mul(21))));
+
+        // narrowing overflows like arithmetic does.
+        let narrow = "%func main\n%long l\n%int i\n%mult l, 2147483647, 2\n%narrow i, l\n%out i\n%endfunc\n";
+        assert!(run(narrow, Overflow::Wrap) == Ok(String::from("-2\n")));
+        assert!(run(narrow, Overflow::Saturate) == Ok(format!("{}\n", i32::MAX)));
+        assert!(run(narrow, Overflow::Trap) == Err(codes::INTEGER_OVERFLOW));
+        assert!(run(&narrow.replace("2147483647", "3"), Overflow::Trap) == Ok(String::from("6\n")));
+
+        // ints and longs only mix through %widen and %narrow.
+        let mixed = [
+            "%func main\n%long l\n%int i\n%mov i, l\n%endfunc\n",
+            "%func main\n%long l\n%int i\n%add l, l, i\n%endfunc\n",
+            "%func main\n%long l\n%int i\n%lt i, i, l\n%endfunc\n",
+            "%func main\n%long l\n%widen l, l\n%endfunc\n",
+            "%func main\n%long l\n%int[] a, 2\n%mov [a + l], 1\n%endfunc\n",
+            "%func main %long\n%ret 0\n%endfunc\n",
+            "%func f(%long n)\n%ret 0\n%endfunc\n%func main\n%int i\n%call i, f(i)\n%endfunc\n",
+            "%func f %long\n%ret 0\n%endfunc\n%func main\n%int i\n%call i, f()\n%endfunc\n",
+        ];
+        for code in mixed {
+            assert!(run(code, Overflow::Wrap) == Err(codes::IR_TYPE_MISMATCH), "{code}");
+        }
+    }
+
+    // the lines an ExecTrace logs while code runs.
+    fn traced(code: &str, trace: &ExecTrace) -> Vec<String> {
+        let calls = load(code).unwrap();
+        let limits = Limits::default();
+        let mut out = vec![];
+        let mut vm = Vm::new(io::empty(), io::sink(), &calls, &limits).unwrap();
+        vm.tracer = Some(Tracer { trace, logged: 0, out: Box::new(&mut out) });
+        assert!(vm.run_bytecode().is_ok());
+        drop(vm);
+        String::from_utf8(out).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn exec_trace() {
+        let code = "%func twice(%int n)
+%int r
+%mult r, n, 2
+%ret r
+%endfunc
+
+%func main
+%int[] a, 2
+%int x
+%mov x, 5
+%call x, twice(x)
+%mov [a + 1], x
+%lt x, x, 3
+%branch_ifn x, :end
+:end
+%ret x
+%endfunc
+";
+        let lines = traced(code, &ExecTrace::default());
+        assert!(lines.len() == 12);
+        assert!(lines[0] == "main #0, IR line 8: %int[] a -> a = [0, 0]");
+        assert!(lines[3] == "main #3, IR line 11: %call x, twice(x=5) -> calls twice");
+        assert!(lines[5] == "twice #1, IR line 3: %mult r, n=5, 2 -> r = 10");
+        assert!(lines[6] == "twice #2, IR line 4: %ret r=10 -> back in main, x = 10");
+        assert!(lines[7] == "main #4, IR line 12: %mov [a + 1], x=10 -> [a + 1] = 10");
+        assert!(lines[9] == "main #6, IR line 14: %branch_ifn x=0, :end -> jumps");
+        assert!(lines[11] == "main #8, IR line 16: %ret x=0 -> main returns 0");
+
+        let trace = ExecTrace { functions: vec![String::from("twice")], max_steps: Some(2) };
+        let lines = traced(code, &trace);
+        assert!(lines.len() == 3 && lines[0].starts_with("twice #0,") && lines[1].starts_with("twice #1,"));
+        assert!(lines[2] == "... the trace stops after 2 instructions, the program goes on.");
+    }
+
+    #[test]
+    fn call_depth() {
+        let code = "%func down(%int n)
+%int more
+%int r
+%neq more, n, 0
+%branch_ifn more, :bottom
+%sub n, n, 1
+%call r, down(n)
+%add r, r, 1
+:bottom
+%ret r
+%endfunc
+
+%func main
+%int r
+%call r, down(DEPTH)
+%ret r
+%endfunc
+";
+        // deeper than the stack of the interpreter itself would have gone when calls recursed in Rust.
+        let deep = code.replace("DEPTH", "9998");
+        assert!(execute(&deep, io::empty(), io::sink()) == Ok(9998));
+        let limits = Limits { call_depth: 100, ..Limits::default() };
+        assert!(execute_ir(&code.replace("DEPTH", "98"), &limits, None, None, io::empty(), io::sink()) == Ok(98));
+
+        let error = execute_ir(&code.replace("DEPTH", "99"), &limits, None, None, io::empty(), io::sink()).unwrap_err();
+        assert!(error.code == codes::CALL_DEPTH_EXCEEDED);
+        assert!(error.message == "Runtime Error: maximum call depth 100 exceeded. Call chain: main -> down x100");
+        // the stack trace skips from the innermost calls to main.
+        assert!(error.trace.len() == TRACE_CALLS + 2 && error.trace[TRACE_CALLS] == "... 79 more calls");
+        assert!(error.trace[0].starts_with("in down, IR line ") && error.trace[TRACE_CALLS + 1].starts_with("in main, IR line "));
+    }
 }
 
 
@@ -1400,6 +2526,7 @@ enum IRTok {
 
     // declarations.
     Int,
+    Long,
     IntArray,
 
     // function calling routines.
@@ -1417,6 +2544,8 @@ enum IRTok {
     Mult,
     Div,
     Mod,
+    Widen,
+    Narrow,
 
     // comparison operators.
     LessThan,
@@ -1440,50 +2569,58 @@ enum IRTok {
     Plus,
 
     EndInstr,
+    // a '; line N' comment: the instructions after it were compiled from line N of the source.
+    SourceLine(usize),
+    // a '; file NAME' comment: the functions after it were compiled from the file NAME.
+    SourceFile(String),
 
     InvalidInstruction(String),
+    InvalidNumber(String),
 
-    Num(i32),
+    Num(i64),
     Var(String),
 }
 
+// variables are numbered when the IR is loaded: the integers of a function get the slots 0, 1, 2...
+// of its frame in the order they are declared, starting with the parameters, and the arrays get slots of their own.
+// so an operand is an index into the frame, and running an instruction never looks a name up.
+// numbers are widened to 64 bits like the variables, an int instruction only ever gets the numbers of 32.
 #[derive(Debug, Clone)]
 enum Op {
-    Num(i32),
-    Var(i32),
+    Num(i64),
+    Var(usize),
 }
 
 #[derive(Debug)]
 enum VariableType {
-    IntVar(i32),
-    ArrayVar(i32, i32),
+    IntVar(usize),
+    ArrayVar(usize),
 }
 
 #[derive(Debug)]
 enum MemWrite {
-    IntVar(i32),
-    ArrayWrite(i32, Op),
+    IntVar(usize),
+    ArrayWrite(usize, Op),
 }
 
 #[derive(Debug)]
 enum MemRead {
-    IntVar(i32),
-    Number(i32),
-    ArrayRead(i32, Op),
+    IntVar(usize),
+    Number(i64),
+    ArrayRead(usize, Op),
 }
 
-// TODO: array bounds check.
-fn read_memory(variables: &HashMap<i32, i32>, arrays: &HashMap<i32, Vec<i32>>, read: &MemRead) -> Result<i32, IRError> {
+fn read_memory(frame: &Frame, read: &MemRead) -> Result<i64, IRError> {
     match read {
-    MemRead::IntVar(id) => Ok(*variables.get(&id).unwrap()),
+    MemRead::IntVar(slot) => Ok(frame.ints[*slot]),
     MemRead::Number(number) => Ok(*number),
-    MemRead::ArrayRead(id, index) => {
-        let array = arrays.get(&id).unwrap();
-        let variable = read_integer_value(&variables, &index);
+    MemRead::ArrayRead(slot, index) => {
+        let array = &frame.arrays[*slot];
+        let variable = read_integer_value(&frame.ints, index);
         if variable >= 0 && (variable as usize) < array.len() {
-            Ok(array[variable as usize])
+            Ok(array[variable as usize] as i64)
         } else {
-            error(MAX_LINE, format!("Runtime Error: Array out of bounds. Index {}. Array Length {}.", variable, array.len()))
+            error(MAX_LINE, codes::ARRAY_OUT_OF_BOUNDS, format!("Runtime Error: Array out of bounds. Index {}. Array Length {}.", variable, array.len()))
         }
     }
     }
@@ -1497,35 +2634,74 @@ enum Bytecode {
     End,
     Label(usize),
 
-    // declarations.
-    Int(i32),
-    IntArray(i32, i32),
+    // declarations, with the slot of the variable.
+    Int(usize),
+    Long(usize),
+    IntArray(usize),
 
     // input/output routines.
     Out(Op),
-    In(i32),
+    In(usize),
 
-    // mathematical operators.
+    // mathematical operators, computing in the width of their operands.
     Mov(MemWrite, MemRead),
-    Add(i32, Op, Op),
-    Sub(i32, Op, Op),
-    Mult(i32, Op, Op),
-    Div(i32, Op, Op),
-    Mod(i32, Op, Op),
+    Add(Width, usize, Op, Op),
+    Sub(Width, usize, Op, Op),
+    Mult(Width, usize, Op, Op),
+    Div(Width, usize, Op, Op),
+    // a remainder is never bigger than its operands, so it needs no width.
+    Mod(usize, Op, Op),
+    // an int into a long, and a long into an int.
+    Widen(usize, Op),
+    Narrow(usize, Op),
 
     // calling functions.
-    Call(i32, usize, Vec<Op>),
+    Call(usize, usize, Vec<Op>),
 
     // comparison operators.
-    LessThan(i32, Op, Op),
-    LessEqual(i32, Op, Op),
-    NotEqual(i32, Op, Op),
-    Equal(i32, Op, Op),
-    GreaterEqual(i32, Op, Op),
-    GreaterThan(i32, Op, Op),
+    LessThan(usize, Op, Op),
+    LessEqual(usize, Op, Op),
+    NotEqual(usize, Op, Op),
+    Equal(usize, Op, Op),
+    GreaterEqual(usize, Op, Op),
+    GreaterThan(usize, Op, Op),
     Return(Op),
     Jmp(usize),
     BranchIf(Op, usize),
     BranchIfn(Op, usize),
 }
 
+impl Bytecode {
+    // the IR opcode the instruction was loaded from.
+    fn opcode(&self) -> &'static str {
+        match self {
+        Bytecode::End => "%endfunc",
+        Bytecode::Label(_) => "label",
+        Bytecode::Int(_) => "%int",
+        Bytecode::Long(_) => "%long",
+        Bytecode::IntArray(_) => "%int[]",
+        Bytecode::Out(_) => "%out",
+        Bytecode::In(_) => "%input",
+        Bytecode::Mov(..) => "%mov",
+        Bytecode::Add(..) => "%add",
+        Bytecode::Sub(..) => "%sub",
+        Bytecode::Mult(..) => "%mult",
+        Bytecode::Div(..) => "%div",
+        Bytecode::Mod(..) => "%mod",
+        Bytecode::Widen(..) => "%widen",
+        Bytecode::Narrow(..) => "%narrow",
+        Bytecode::Call(..) => "%call",
+        Bytecode::LessThan(..) => "%lt",
+        Bytecode::LessEqual(..) => "%le",
+        Bytecode::NotEqual(..) => "%neq",
+        Bytecode::Equal(..) => "%eq",
+        Bytecode::GreaterEqual(..) => "%ge",
+        Bytecode::GreaterThan(..) => "%gt",
+        Bytecode::Return(_) => "%ret",
+        Bytecode::Jmp(_) => "%jmp",
+        Bytecode::BranchIf(..) => "%branch_if",
+        Bytecode::BranchIfn(..) => "%branch_ifn",
+        }
+    }
+}
+
@@ -0,0 +1,307 @@
+// a small JSON value, enough for printing machine readable diagnostics
+// and for the messages of the language server in lsp.rs.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    // the language server protocol only sends whole numbers.
+    Num(i64),
+    Str(String),
+    Array(Vec<Value>),
+    // keeps the order the keys were added in.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object(entries: Vec<(&str, Value)>) -> Value {
+        Value::Object(entries.into_iter().map(|(k, v)| (String::from(k), v)).collect())
+    }
+
+    pub fn str(s: &str) -> Value {
+        Value::Str(String::from(s))
+    }
+
+    // the value of a key of an object, or Null.
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v).unwrap_or(&Value::Null),
+            _ => &Value::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_num(&self) -> Option<i64> {
+        match self {
+            Value::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+// how deep arrays and objects may be nested, as the parser recurses into them.
+const MAX_DEPTH: usize = 128;
+
+// reads a JSON text. returns None if it is not valid JSON, or is nested deeper than MAX_DEPTH.
+pub fn parse(text: &str) -> Option<Value> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.space();
+    if parser.pos < parser.chars.len() {
+        return None;
+    }
+    Some(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    // the arrays and objects the parser is inside of.
+    depth: usize,
+}
+
+impl Parser {
+    fn space(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.space();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn word(&mut self, word: &str, value: Value) -> Option<Value> {
+        for c in word.chars() {
+            if self.chars.get(self.pos) != Some(&c) {
+                return None;
+            }
+            self.pos += 1;
+        }
+        Some(value)
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.space();
+        match self.chars.get(self.pos)? {
+            'n' => self.word("null", Value::Null),
+            't' => self.word("true", Value::Bool(true)),
+            'f' => self.word("false", Value::Bool(false)),
+            '"' => self.string().map(Value::Str),
+            '[' | '{' => {
+                if self.depth == MAX_DEPTH {
+                    return None;
+                }
+                self.depth += 1;
+                let value = if self.chars[self.pos] == '[' { self.array() } else { self.object() };
+                self.depth -= 1;
+                value
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut values = vec![];
+        if self.eat(']') {
+            return Some(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            if self.eat(']') {
+                return Some(Value::Array(values));
+            }
+            if !self.eat(',') {
+                return None;
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut entries = vec![];
+        if self.eat('}') {
+            return Some(Value::Object(entries));
+        }
+        loop {
+            self.space();
+            let key = self.string()?;
+            if !self.eat(':') {
+                return None;
+            }
+            entries.push((key, self.value()?));
+            if self.eat('}') {
+                return Some(Value::Object(entries));
+            }
+            if !self.eat(',') {
+                return None;
+            }
+        }
+    }
+
+    // a fraction or exponent is cut off, as whole numbers are all we need.
+    fn number(&mut self) -> Option<Value> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.parse::<i64>() {
+            Ok(n) => Some(Value::Num(n)),
+            Err(_) => text.parse::<f64>().ok().map(|n| Value::Num(n as i64)),
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return None;
+        }
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = *self.chars.get(self.pos)?;
+            self.pos += 1;
+            match c {
+                '"' => return Some(s),
+                '\\' => {
+                    let escaped = *self.chars.get(self.pos)?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => {
+                            let mut unit = self.hex()?;
+                            // characters outside the basic plane come as two escapes.
+                            if (0xd800..0xdc00).contains(&unit) && self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u']) {
+                                self.pos += 2;
+                                let low = self.hex()?;
+                                unit = 0x10000 + ((unit - 0xd800) << 10) + (low.checked_sub(0xdc00)? & 0x3ff);
+                            }
+                            s.push(char::from_u32(unit).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        c => s.push(c),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let digits: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Num(n) => write!(f, "{n}"),
+            Value::Str(s) => write_str(f, s),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_str(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use crate::json::{parse, Value};
+
+    #[test]
+    fn serialize() {
+        let value = Value::object(vec![
+            ("code", Value::str("E0101")),
+            ("span", Value::Null),
+            ("line", Value::Num(3)),
+            ("text", Value::str("a\"b\\c\n")),
+        ]);
+        assert!(value.to_string() == r#"{"code":"E0101","span":null,"line":3,"text":"a\"b\\c\n"}"#);
+    }
+
+    #[test]
+    fn parse_values() {
+        let text = r#"{"id": 1, "params": {"ok": true, "list": [null, -2, "a\"\u00e9\ud83d\ude00"]}, "x": 1.5}"#;
+        let value = parse(text).unwrap();
+        assert!(value.get("id").as_num() == Some(1));
+        assert!(value.get("params").get("ok").as_bool() == Some(true));
+        let list = value.get("params").get("list").as_array().unwrap();
+        assert!(list == [Value::Null, Value::Num(-2), Value::str("a\"\u{e9}\u{1f600}")]);
+        assert!(value.get("x").as_num() == Some(1) && value.get("missing") == &Value::Null);
+        assert!(parse(&value.to_string()).unwrap().get("params") == value.get("params"));
+
+        assert!(parse("{\"a\": }").is_none() && parse("[1, 2").is_none() && parse("1 2").is_none());
+        // nesting is limited, not the stack.
+        assert!(parse(&format!("{}{}", "[".repeat(128), "]".repeat(128))).is_some());
+        assert!(parse(&"[".repeat(100_000)).is_none());
+    }
+}
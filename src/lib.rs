@@ -0,0 +1,2182 @@
+// The Rust Programming Language: A Crash Course and Building Our First Lexer
+// CS152 Compiler Design using the Rust Programming Language.
+// A Handwritten Compiler Using Rust.
+// Creating a Lexer By Hand.
+//
+// the compiler as a library. the commandline tool in main.rs is built on top of it,
+// and anything else that wants to compile or run programs, such as a test runner, can use it too:
+//
+//   let compiled = rustcompiler::compile_to_ir(&source)?;
+//   let exit_code = rustcompiler::interpreter::execute(&compiled.ir, std::io::stdin().lock(), std::io::stdout())?;
+//
+// none of these print anything but the output of the program itself, and what log.rs lets through on stderr.
+
+// for our symbol table
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[macro_use]
+pub mod log;
+pub mod diagnostics;
+pub mod interpreter;
+pub mod analysis;
+pub mod warnings;
+mod suggest;
+pub mod catalog;
+pub mod json;
+pub mod repl;
+pub mod format;
+mod symbols;
+pub mod lsp;
+pub mod imports;
+pub mod debugger;
+pub mod profile;
+
+use diagnostics::{codes, Diagnostic, Span};
+use log::Level;
+use warnings::Warning;
+
+// parses tokens into IR.
+pub fn parse(tokens: &[Token]) -> Result<String, Diagnostic> {
+  parse_with_spans(tokens, &[])
+}
+
+// parses like parse(), taking the spans from lex_with_spans so that a parse error
+// points at the token the parser failed on.
+pub fn parse_with_spans(tokens: &[Token], spans: &[Span]) -> Result<String, Diagnostic> {
+  parse_in(tokens, spans, &mut Context::default())
+}
+
+// parses like parse_with_spans(), also returning the trace of the parser: the functions
+// it descended into and the tokens it looked at.
+// the parser generates IR as it goes, so this trace is the closest thing there is to a syntax tree.
+pub fn parse_traced(tokens: &[Token], spans: &[Span]) -> (Result<String, Diagnostic>, String) {
+  let mut ctx = Context { trace: Some(String::new()), ..Context::default() };
+  let result = parse_in(tokens, spans, &mut ctx);
+  (result, ctx.trace.unwrap_or_default())
+}
+
+// checks only the syntax of a program, for tools such as the formatter that work on programs which do not compile:
+// a break or continue outside of a loop is not an error here.
+pub fn parse_syntax(tokens: &[Token], spans: &[Span]) -> Result<(), Box<Diagnostic>> {
+  parse_in(tokens, spans, &mut Context { syntax_only: true, ..Context::default() })?;
+  Ok(())
+}
+
+// parses like parse_with_spans(), marking the IR of every function and statement with the line of source it came from:
+//   ; line 6
+// the marks are IR comments, so everything that reads IR can skip them. the interpreter uses them for the lines
+// of the stack trace of a runtime error.
+pub fn parse_with_source_map(tokens: &[Token], spans: &[Span], source: &str) -> Result<String, Diagnostic> {
+  parse_importing(tokens, spans, source, &HashMap::new())
+}
+
+// parses like parse_with_source_map(), calling the functions of imported files with the signatures they have there.
+pub(crate) fn parse_importing(tokens: &[Token], spans: &[Span], source: &str, imported: &HashMap<String, Signature>) -> Result<String, Diagnostic> {
+  let mut lines = Vec::with_capacity(spans.len());
+  let (mut line, mut counted) = (1, 0);
+  for span in spans {
+    line += source[counted..span.start].matches('\n').count();
+    counted = span.start;
+    lines.push(line);
+  }
+  parse_in(tokens, spans, &mut Context { lines, signatures: imported.clone(), ..Context::default() })
+}
+
+fn parse_in(tokens: &[Token], spans: &[Span], ctx: &mut Context) -> Result<String, Diagnostic> {
+  let mut index: usize = 0;
+  ctx.signatures.extend(signatures(tokens));
+  parse_program(tokens, &mut index, ctx).map_err(|error| {
+    // the parser fails on the token it just read, or at the end of the file.
+    let failed_at = index.saturating_sub(1).min(spans.len().saturating_sub(1));
+    match spans.get(failed_at) {
+      Some(span) => error.with_span(*span),
+      None => error,
+    }
+  })
+}
+
+// the semantic checks and the control flow analysis of generated IR.
+// when the semantic checks fail, their error is the only one returned, as the analysis needs valid IR.
+// errors and warnings name the file they were found in when the program has imports, and their line when the IR has the marks of the lines.
+pub fn check(generated_code: &str) -> analysis::Analysis {
+  // the IR line the checks failed on, None for an error about the whole program.
+  let mut checking = None;
+  if let Err(mut error) = semantics_check(generated_code.to_string(), &mut checking).and_then(|_| type_check(generated_code, &mut checking)) {
+    if let Some(n) = checking {
+      let (file, line) = marks_at(generated_code, n);
+      error.file = file;
+      error.line = line;
+    }
+    return analysis::Analysis { warnings: vec![], errors: vec![error] };
+  }
+  analysis::analyze(generated_code)
+}
+
+// the file and the source line IR line n came from, from the last '; file' and '; line' marks before it.
+// see imports.rs and parse_with_source_map().
+fn marks_at(generated_code: &str, n: usize) -> (Option<String>, Option<usize>) {
+  let (mut file, mut line) = (None, None);
+  for mark in generated_code.lines().take(n) {
+    if let Some(name) = mark.strip_prefix("; file ") {
+      (file, line) = (Some(name.to_string()), None);
+    } else if let Some(number) = mark.strip_prefix("; line ") {
+      line = number.trim().parse().ok();
+    }
+  }
+  (file, line)
+}
+
+#[derive(Debug)]
+pub struct Compiled {
+  pub ir: String,
+  pub warnings: Vec<Warning>,
+}
+
+// lexes, parses and checks a program.
+// returns the generated IR, ready for interpreter::execute, or every error found.
+// the files it imports are found relative to the current directory, imports::compile() takes the path of the program.
+pub fn compile_to_ir(source: &str) -> Result<Compiled, Vec<Diagnostic>> {
+  imports::compile(None, source)
+}
+
+struct Expression {
+  code: String,
+  name: String,
+  // None for a number, or an expression of only numbers, which takes the type of where it is used.
+  data_type: Option<DataType>,
+}
+
+// the state of compiling one program: the counters that number temporaries and labels,
+// and the labels of the while loops we are inside of.
+// every compilation starts from a fresh context, so the same program always compiles to the same IR,
+// and programs can be compiled on several threads at once.
+#[derive(Default)]
+struct Context {
+  var_num: i64,
+  whileloopbegin_num: i64,
+  whileloopend_num: i64,
+  iftrue_num: i64,
+  endif_num: i64,
+  // begin and end label of every enclosing while loop, innermost last.
+  while_label_stack: Vec<(String, String)>,
+  // collects the parser trace when set, instead of logging it.
+  trace: Option<String>,
+  // set by parse_syntax(), which skips the checks that are not about syntax.
+  syntax_only: bool,
+  // the source line of every token, when the IR is marked with them. see parse_with_source_map().
+  lines: Vec<usize>,
+  // the types of the variables of the function being parsed, which is called function.
+  variables: HashMap<String, DataType>,
+  function: String,
+  // the functions of the program and of the files it imports, see signatures().
+  signatures: HashMap<String, Signature>,
+}
+
+// a line of the parser trace.
+macro_rules! trace {
+  ($ctx:expr, $($arg:tt)*) => {
+    $ctx.trace(format_args!($($arg)*))
+  };
+}
+
+impl Context {
+  fn trace(&mut self, line: std::fmt::Arguments) {
+    match &mut self.trace {
+      Some(trace) => {
+        let _ = trace.write_fmt(line);
+      }
+      None => log!(Level::Trace, "{}", line),
+    }
+  }
+
+  // the mark of the source line of a token, or nothing when the IR is not marked.
+  fn mark(&self, index: usize) -> String {
+    match self.lines.get(index) {
+      Some(line) => format!("; line {line}\n"),
+      None => String::new(),
+    }
+  }
+
+  fn create_temp(&mut self) -> String {
+    self.var_num += 1;
+    format!("_temp{}", self.var_num)
+  }
+
+  fn create_whileloopbegin_label(&mut self) -> String {
+    self.whileloopbegin_num += 1;
+    format!("loopbegin{}", self.whileloopbegin_num)
+  }
+
+  fn create_whileloopend_label(&mut self) -> String {
+    self.whileloopend_num += 1;
+    format!("endloop_{}", self.whileloopend_num)
+  }
+
+  fn create_iftrue_label(&mut self) -> String {
+    self.iftrue_num += 1;
+    format!("iftrue{}", self.iftrue_num)
+  }
+
+  fn create_endif_label(&mut self) -> String {
+    self.endif_num += 1;
+    format!("endif{}", self.endif_num)
+  }
+
+  // the type of a variable in an expression. an array on its own is used like an int, which the semantic checks catch.
+  fn type_of(&self, name: &str) -> Option<DataType> {
+    match self.variables.get(name) {
+      Some(DataType::Long) => Some(DataType::Long),
+      _ => Some(DataType::Int),
+    }
+  }
+}
+
+// the parameter types and the return type of a function.
+#[derive(Debug, Clone)]
+pub(crate) struct Signature {
+  params: Vec<DataType>,
+  returns: DataType,
+}
+
+// the signatures of the functions defined in tokens, so that calls know them before the parser gets to the definition:
+// func fact(long n, int k) long {
+pub(crate) fn signatures(tokens: &[Token]) -> HashMap<String, Signature> {
+  let mut signatures = HashMap::new();
+  for (i, pair) in tokens.windows(3).enumerate() {
+    if let [Token::Func, Token::Ident(name), Token::LeftParen] = pair {
+      let rest = &tokens[i + 3..];
+      let end = rest.iter().position(|token| matches!(token, Token::RightParen)).unwrap_or(rest.len());
+      let params = rest[..end].iter().filter_map(|token| match token {
+        Token::Int => Some(DataType::Int),
+        Token::Long => Some(DataType::Long),
+        _ => None,
+      }).collect();
+      let returns = if matches!(rest.get(end + 1), Some(Token::Long)) { DataType::Long } else { DataType::Int };
+      signatures.insert(name.clone(), Signature { params, returns });
+    }
+  }
+  signatures
+}
+
+// the IR declaration of a temporary holding a value of a type. an expression of only numbers is computed in an int,
+// unless coerce() makes it a long.
+fn declaration(data_type: Option<DataType>) -> &'static str {
+  match data_type {
+    Some(DataType::Long) => "%long",
+    _ => "%int",
+  }
+}
+
+// gives a number, or an expression of only numbers, the type of where it is used.
+// a number fits either type as it is. an expression of numbers declares nothing but its temporaries,
+// which become longs where a long is expected, so that 2000000000 * 4 does not overflow an int on the way.
+fn coerce(expr: Expression, to: Option<DataType>) -> Expression {
+  if expr.data_type.is_some() || to.is_none() {
+    return expr;
+  }
+  if to == Some(DataType::Long) {
+    let code = expr.code.lines().map(|line| match line.strip_prefix("%int ") {
+      Some(temp) => format!("%long {temp}\n"),
+      None => format!("{line}\n"),
+    });
+    return Expression { code: code.collect(), data_type: to, ..expr };
+  }
+  Expression { data_type: to, ..expr }
+}
+
+// the two sides of an operation in the type it computes in: long when either side is a long, otherwise int,
+// and no type for numbers on both sides. an int and a long are left mixed for type_check() to report.
+fn common_type(left: Expression, right: Expression) -> (Expression, Expression, Option<DataType>) {
+  let data_type = match (left.data_type, right.data_type) {
+    (Some(DataType::Long), _) | (_, Some(DataType::Long)) => Some(DataType::Long),
+    (None, None) => None,
+    _ => Some(DataType::Int),
+  };
+  (coerce(left, data_type), coerce(right, data_type), data_type)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DataType {
+  Array,
+  Int,
+  Long,
+  Function,
+}
+
+impl std::fmt::Display for DataType {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+      match self {
+        DataType::Array => write!(f, "Int[]"),
+        DataType::Int => write!(f, "Int"),
+        DataType::Long => write!(f, "Long"),
+        DataType::Function => write!(f, "Function"),
+      }
+  }
+}
+
+// checking is set to the IR line being checked, and to None for the checks of the whole program after them.
+fn semantics_check(generated_code: String, checking: &mut Option<usize>) -> Result<(), Diagnostic> {
+  // each symbol name will map to a datatype
+  // data types will be array, int, or function
+
+  let mut symbol_table: HashMap<String,DataType> = HashMap::new();
+  // we only add from %func, %int, and %int instructions
+
+  //let mut cleaned_code = generated_code.replace(",", "");
+  //cleaned_code = cleaned_code.replace("[", "");
+  //cleaned_code = cleaned_code.replace("]", "");
+
+  let mut main_function_seen = false;
+  let mut scope_name = "";
+  let mut line2;
+
+  for (n, gline) in generated_code.lines().enumerate() {
+    //println!("gline: {}", gline);
+    *checking = Some(n);
+    // source line marks.
+    if gline.starts_with(';') {
+      continue;
+    }
+    let line = gline.replace(",", " ");
+    //stuff that'll add to symbol table
+    if line.starts_with("%func"){
+      line2 = line.replace(")", " )");
+      line2 = line2.replace("(", " ");
+      //println!("line {}",line);
+      let func_name = line2.split_whitespace().nth(1).unwrap();
+      //println!("func_name: {}", func_name);
+      if func_name == "main"{
+        main_function_seen = true;
+      }
+      scope_name = func_name;
+      //println!("scope_name: {}", scope_name);
+      let key_name = func_name.to_string()+"|main";
+      if(symbol_table.contains_key(&key_name)){
+        return Err(Diagnostic::new(codes::DUPLICATE_FUNCTION, format!("Function {func_name} already defined.")));
+      }
+      symbol_table.insert(key_name, DataType::Function);
+      // add function parameters to symbol table
+      let params = line2.split_whitespace().skip(2);
+      let mut param_type = DataType::Int;
+      for param in params{
+        if param == ")"{
+          break;
+        }
+        // the type of the next parameter, or the return type of a function without parameters.
+        if param.starts_with('%') {
+          param_type = if param == "%long" { DataType::Long } else { DataType::Int };
+          continue;
+        }
+        let key_name = param.to_string()+"|"+scope_name;
+        //println!("param {key_name}");
+        if(symbol_table.contains_key(&key_name)){
+          return Err(Diagnostic::new(codes::DUPLICATE_PARAMETER, format!("Duplicate parameter {param} declared in {scope_name}.")));
+        }
+        symbol_table.insert(key_name, param_type);
+      }
+      continue;
+    }
+    if line.starts_with("%int[]"){
+      let var_name = line.split_whitespace().nth(1).unwrap();
+      let array_size = line.split_whitespace().nth(2).unwrap();
+      //println!("array_name: {}", var_name);
+      if(array_size.parse::<i32>().map_or(true, |size| size <= 0)){
+        return Err(Diagnostic::new(codes::INVALID_ARRAY_SIZE, format!("Array size of {var_name} must be greater than 0, and at most {}.", i32::MAX)));
+      }
+      let key_name = var_name.to_string()+"|"+scope_name;
+      //println!("{} is array", key_name);
+      if(symbol_table.contains_key(&key_name)){
+        return Err(Diagnostic::new(codes::DUPLICATE_VARIABLE, format!("Variable {key_name} already declared.")));
+      }
+      symbol_table.insert(key_name, DataType::Array);
+      continue;
+    }
+    if line.starts_with("%int ") || line.starts_with("%long "){
+      let var_name = line.split_whitespace().nth(1).unwrap();
+      //println!("var_name: {}", var_name);
+      let key_name = var_name.to_string()+"|"+scope_name;
+      if(symbol_table.contains_key(&key_name)){
+        return Err(Diagnostic::new(codes::DUPLICATE_VARIABLE, format!("Variable {var_name} already declared.")));
+      }
+      let data_type = if line.starts_with("%long") { DataType::Long } else { DataType::Int };
+      symbol_table.insert(var_name.to_string()+"|"+scope_name, data_type);
+      continue;
+    }
+    // semantics check the line
+    let mut clean_line = line.replace("[", "");
+    clean_line = clean_line.replace("]", "");
+    clean_line = clean_line.replace("(", "( ");
+    clean_line = clean_line.replace(")", "");
+    let mut seen_array_type = false;
+    let mut seen_tokens: Vec<&str> = vec![];
+    for param in clean_line.split_whitespace(){
+      if param.starts_with("%") || param.starts_with(":"){
+        continue;
+      }
+        // not a number, not a +, and not in symbol table.. undeclared or undefined
+        //println!("param: {}", param);
+        // functions are in the symbol table under main, whichever function calls them.
+        let key_name = if param.ends_with("(") { param.replace("(","")+"|main" } else { param.to_string()+"|"+scope_name };
+        //println!("key_name: {}", key_name);
+        if !symbol_table.contains_key(&(key_name)) && !param.parse::<i64>().is_ok() && !param.starts_with("+"){
+          if(param.ends_with("(")){
+            let func_name = param.replace("(", "");
+            let functions = symbol_table.iter().filter(|(_, t)| matches!(t, DataType::Function)).map(|(k, _)| k.trim_end_matches("|main"));
+            let suggestion = suggest::closest(&func_name, functions);
+            return Err(Diagnostic::new(codes::UNDEFINED_FUNCTION, format!("Undefined function used: {}{}", func_name, suggest::did_you_mean(suggestion))));
+          }else{
+            let scope_suffix = format!("|{scope_name}");
+            let variables = symbol_table.iter().filter(|(k, t)| !matches!(t, DataType::Function) && k.ends_with(&scope_suffix)).map(|(k, _)| &k[..k.len() - scope_suffix.len()]);
+            let suggestion = suggest::closest(param, variables).or_else(|| suggest::keyword(param));
+            return Err(Diagnostic::new(codes::UNDECLARED_VARIABLE, format!("Undeclared variable used: {}{}", param, suggest::did_you_mean(suggestion))));
+          }
+        }
+        // param is a +, check if last token is an array
+        if param == "+" {
+          let last_token = *seen_tokens.last().unwrap();
+          let data_type = symbol_table.get(&(last_token.to_string() + "|" + scope_name)).unwrap();
+          if(!matches!(data_type, &DataType::Array)){
+            //println!("Error: Attempt to use {data_type} {last_token} like array.");
+            return Err(Diagnostic::new(codes::NOT_AN_ARRAY, format!("Type mismatch. '{last_token}' is not an array.")));
+          }else{
+            seen_array_type = false;
+          }
+        }
+        // if seenarraytype is true and we passed +, then we are trying to use an array like a number
+        if(seen_array_type){
+          let last_token = *seen_tokens.last().unwrap();
+          //println!("Error: Attempt to use array {last_token} like number.");
+          return Err(Diagnostic::new(codes::ARRAY_AS_INTEGER, format!("Type mismatch. Used '{last_token}' as an integer.")));
+        }
+        // set seenArrayType flag
+        if symbol_table.contains_key(&(param.to_string() + "|" + scope_name)){
+          let data_type = symbol_table.get(&(param.to_string() + "|" + scope_name)).unwrap();
+          if(matches!(data_type, &DataType::Array)){
+            seen_array_type = true;
+            //println!("set array flag {}", seen_array_type);
+          }
+        }
+        seen_tokens.push(param);
+    }
+  }
+  *checking = None;
+  if(main_function_seen == false){
+    return Err(Diagnostic::new(codes::MISSING_MAIN, String::from("Main function not defined.")));
+  }
+  return Ok(());
+}
+
+// the type of a value in IR: None for a number, which fits an int and a long alike.
+type IRType = Option<DataType>;
+
+// checks that generated IR only mixes ints and longs through %widen and %narrow:
+// the operands of an instruction, a return value and its function, and the arguments of a call and its parameters.
+// runs after semantics_check(), so every name it looks up is declared. checking is set like semantics_check() sets it.
+fn type_check(generated_code: &str, checking: &mut Option<usize>) -> Result<(), Diagnostic> {
+  *checking = None;
+  let words = |line: &str| -> Vec<String> {
+    line.split(|c: char| c.is_whitespace() || ",()[]+".contains(c)).filter(|word| !word.is_empty()).map(String::from).collect()
+  };
+  let ir_type = |word: &str| if word == "%long" { DataType::Long } else { DataType::Int };
+  // every signature first, as a call can come before the function it calls.
+  // %func fact(%long n, %int k) %long
+  let mut signatures: HashMap<String, Signature> = HashMap::new();
+  for line in generated_code.lines().filter(|line| line.starts_with("%func")) {
+    let words = words(line);
+    let mut signature = Signature { params: vec![], returns: DataType::Int };
+    let mut i = 2;
+    while i < words.len() {
+      if i + 1 < words.len() {
+        signature.params.push(ir_type(&words[i]));
+        i += 2;
+      } else {
+        signature.returns = ir_type(&words[i]);
+        i += 1;
+      }
+    }
+    signatures.insert(words[1].clone(), signature);
+  }
+  if signatures.get("main").is_some_and(|main| main.returns == DataType::Long) {
+    return Err(Diagnostic::new(codes::TYPE_MISMATCH, "Type mismatch. main returns the exit code of the program, which is an int, not a long."));
+  }
+
+  let mut types: HashMap<String, DataType> = HashMap::new();
+  let mut function = String::new();
+  let mut at = String::new();
+  for (n, line) in generated_code.lines().enumerate() {
+    *checking = Some(n);
+    if let Some(line) = line.strip_prefix("; line ") {
+      at = format!(" on line {}", line.trim());
+      continue;
+    }
+    let words = words(line);
+    let Some(opcode) = words.first() else { continue };
+    let type_of = |word: &String| -> IRType {
+      match word.parse::<i64>() {
+        Ok(number) if i32::try_from(number).is_ok() => None,
+        Ok(_) => Some(DataType::Long),
+        Err(_) => types.get(word).copied(),
+      }
+    };
+    // the name of a value in a message: temporaries hold the value of an expression.
+    let describe = |word: &String| {
+      // the only numbers with a type are the ones too big for an int.
+      if word.parse::<i64>().is_ok() {
+        return format!("the long {word}");
+      }
+      let long = type_of(word) == Some(DataType::Long);
+      match (word.starts_with("_temp"), long) {
+        (true, true) => String::from("a long value"),
+        (true, false) => String::from("an int value"),
+        (false, true) => format!("the long '{word}'"),
+        (false, false) => format!("the int '{word}'"),
+      }
+    };
+    let mismatch = |a: &String, b: &String| {
+      let message = format!("Type mismatch between {} and {}{at}, convert one of them with int() or long().", describe(a), describe(b));
+      Err(Diagnostic::new(codes::TYPE_MISMATCH, message))
+    };
+    let same = |a: &String, b: &String| match (type_of(a), type_of(b)) {
+      (Some(a), Some(b)) => a == b,
+      _ => true,
+    };
+    match opcode.as_str() {
+    "%func" => {
+      types.clear();
+      function = words[1].clone();
+      for pair in words[2..].chunks(2).filter(|pair| pair.len() == 2) {
+        types.insert(pair[1].clone(), ir_type(&pair[0]));
+      }
+    }
+    "%int" | "%long" => {
+      types.insert(words[1].clone(), ir_type(opcode));
+    }
+    "%int[]" => {
+      types.insert(words[1].clone(), DataType::Array);
+    }
+    "%mov" if line.contains('[') => {
+      // %mov dest, [array + index] or %mov [array + index], src: the array is the word after the bracket.
+      let array = words[if line.starts_with("%mov [") { 1 } else { 2 }].clone();
+      if let Some(word) = words[1..].iter().find(|word| **word != array && type_of(word) == Some(DataType::Long)) {
+        let message = format!("Type mismatch. Arrays hold ints, not {}{at}, convert it with int().", describe(word));
+        return Err(Diagnostic::new(codes::TYPE_MISMATCH, message));
+      }
+    }
+    "%mov" | "%add" | "%sub" | "%mult" | "%div" | "%mod" => {
+      for pair in words[1..].windows(2) {
+        if !same(&pair[0], &pair[1]) {
+          return mismatch(&pair[0], &pair[1]);
+        }
+      }
+    }
+    "%lt" | "%le" | "%gt" | "%ge" | "%eq" | "%neq" if !same(&words[2], &words[3]) => {
+      return mismatch(&words[2], &words[3]);
+    }
+    "%widen" | "%narrow" => {
+      let to = if opcode == "%widen" { DataType::Long } else { DataType::Int };
+      let from = if opcode == "%widen" { DataType::Int } else { DataType::Long };
+      if type_of(&words[1]) != Some(to) || type_of(&words[2]) == Some(to) {
+        let message = format!("Type mismatch. '{opcode}' makes {} from {}{at}.", article(to), article(from));
+        return Err(Diagnostic::new(codes::TYPE_MISMATCH, message));
+      }
+    }
+    "%ret" => {
+      let returns = signatures.get(&function).map_or(DataType::Int, |signature| signature.returns);
+      if type_of(&words[1]).is_some_and(|data_type| data_type != returns) {
+        let message = format!("Type mismatch. {function} returns {}, not {}{at}, convert it with {}().", article(returns), describe(&words[1]), type_name(returns));
+        return Err(Diagnostic::new(codes::TYPE_MISMATCH, message));
+      }
+    }
+    "%call" => {
+      let Some(signature) = signatures.get(&words[2]) else { continue };
+      if type_of(&words[1]).is_some_and(|data_type| data_type != signature.returns) {
+        let message = format!("Type mismatch. {} returns {}, which {} cannot hold{at}.", words[2], article(signature.returns), describe(&words[1]));
+        return Err(Diagnostic::new(codes::TYPE_MISMATCH, message));
+      }
+      for (param, (arg, data_type)) in words[3..].iter().zip(&signature.params).enumerate() {
+        if type_of(arg).is_some_and(|arg_type| arg_type != *data_type) {
+          let message = format!("Type mismatch. Passed {} as parameter {} of {}, which is {}{at}, convert it with {}().", describe(arg), param + 1, words[2], article(*data_type), type_name(*data_type));
+          return Err(Diagnostic::new(codes::TYPE_MISMATCH, message));
+        }
+      }
+    }
+    _ => {}
+    }
+  }
+  Ok(())
+}
+
+// an int or a long, as a type checking message says it.
+fn article(data_type: DataType) -> &'static str {
+  if data_type == DataType::Long { "a long" } else { "an int" }
+}
+
+fn type_name(data_type: DataType) -> &'static str {
+  if data_type == DataType::Long { "long" } else { "int" }
+}
+// Creating an Enum within Rust.
+// Documentation: https://doc.rust-lang.org/book/ch06-01-defining-an-enum.html
+// Enums are a way of saying a value is one of a possible set of values.
+// Unlike C, Rust enums can have values associated with that particular enum value.
+// for example, a Num has a 'i32' value associated with it, 
+// but Plus, Subtract, Multiply, etc. have no values associated with it.
+#[derive(Debug, Clone)]
+pub enum Token {
+  NotToken,
+  //math
+  Plus,
+  Subtract,
+  Multiply,
+  Divide,
+  Modulus,
+  Assign,
+
+  Num(i64),
+  Ident(String),
+  // a string in double quotes, only used for the path of an import.
+  Str(String),
+  
+  //keywords
+  If,
+  While,
+  Read, 
+  Func,
+  Return,
+  Int,
+  Long,
+  Print,
+  Else,
+  Break,
+  Continue,
+  Import,
+
+  LeftParen,
+  RightParen,
+  LeftCurly,
+  RightCurly,
+  LeftBracket,
+  RightBracket,
+  Comma,
+  Semicolon,
+  
+  //boolean
+  Less,
+  LessEqual,
+  Greater,
+  GreaterEqual,
+  Equality,
+  NotEqual
+}
+
+// In Rust, you can model the function behavior using the type system.
+// https://doc.rust-lang.org/std/result/
+// Result < Vec<Token>, Diagnostic>
+// means that this function can either return:
+// - A list of tokens as a Vec<Token>
+// - Or an error message represented as a string
+// If there is an error, it will return an error
+// If successful, it will return Vec<Token>
+// A Result is an enum like this:
+// enum Result {
+//     Ok(the_result),
+//     Err(the_error),
+// }
+
+
+// This is a lexer that parses numbers/identifiers and math operations
+pub fn lex(code: &str) -> Result<Vec<Token>, Diagnostic> {
+  let (tokens, _) = lex_with_spans(code)?;
+  Ok(tokens)
+}
+
+// lexes like lex(), but also returns where in the source every token was found.
+// spans[i] belongs to tokens[i].
+pub fn lex_with_spans(source: &str) -> Result<(Vec<Token>, Vec<Span>), Diagnostic> {
+  let mut code = source;
+  let mut tokens: Vec<Token> = vec![];
+  let mut spans: Vec<Span> = vec![];
+  let mut start = 0;
+  loop {
+    // every pass through the loop lexes at most one token,
+    // so a token pushed by the last pass ends where the rest of the code begins.
+    let offset = source.len() - code.len();
+    if tokens.len() > spans.len() {
+      spans.push(Span { start, end: offset });
+    }
+    start = offset;
+    if code.len() == 0 {
+      break;
+    }
+
+    let (success, token, rest) = lex_number(code);
+    if success && matches!(token, Token::NotToken) {
+      let number = &code[..code.len() - rest.len()];
+      let span = Span { start, end: start + number.len() };
+      return Err(Diagnostic::new(codes::NUMBER_TOO_BIG, format!("The number {number} is too big, a long is at most {}.", i64::MAX)).with_span(span));
+    }
+    if success {
+      code = rest; 
+      tokens.push(token);
+      continue;
+    } 
+ 
+    let (success, rest) = lex_space(code);
+    if success {
+      code = rest;
+      continue;
+    }
+
+    if code.starts_with("+") {
+      code = &code[1..];
+      tokens.push(Token::Plus);
+      continue;
+    }
+
+    if code.starts_with("-") {
+      code = &code[1..];
+      tokens.push(Token::Subtract);
+      continue;
+    }
+
+    if code.starts_with("*") {
+      code = &code[1..];
+      tokens.push(Token::Multiply);
+      continue;
+    }
+
+    if code.starts_with("/") {
+      code = &code[1..];
+      tokens.push(Token::Divide);
+      continue;
+    }
+
+    if code.starts_with("%") {
+      code = &code[1..];
+      tokens.push(Token::Modulus);
+      continue;
+    }
+
+    if code.starts_with("==") {
+      code = &code[2..];
+      tokens.push(Token::Equality);
+      continue;
+    }
+
+    if code.starts_with("=") {
+      code = &code[1..];
+      tokens.push(Token::Assign);
+      continue;
+    }
+
+    if code.starts_with("("){
+      code = &code[1..];
+      tokens.push(Token::LeftParen);
+      continue;
+    }
+    
+    if code.starts_with(")"){
+      code = &code[1..];
+      tokens.push(Token::RightParen);
+      continue;
+    }
+
+    // Check for comment
+    if code.starts_with('#') {
+      code = skip_comment(code);
+      continue;
+  }
+
+    if code.starts_with("{") {
+      code = &code[1..];
+      tokens.push(Token::LeftCurly);
+      continue;
+    }
+
+    if code.starts_with("}") {
+      code = &code[1..];
+      tokens.push(Token::RightCurly);
+      continue;
+    }
+
+    if code.starts_with("[") {
+      code = &code[1..];
+      tokens.push(Token::LeftBracket);
+      continue;
+    }
+
+    if code.starts_with("]") {
+      code = &code[1..];
+      tokens.push(Token::RightBracket);
+      continue;
+    }
+
+    if code.starts_with(",") {
+      code = &code[1..];
+      tokens.push(Token::Comma);
+      continue;
+    }
+
+    if code.starts_with(";") {
+      code = &code[1..];
+      tokens.push(Token::Semicolon);
+      continue;
+    } 
+      
+    if code.starts_with("<=") {
+      code = &code[2..];
+      tokens.push(Token::LessEqual);
+      continue;
+    }
+
+    if code.starts_with("<") {
+      code = &code[1..];
+      tokens.push(Token::Less);
+      continue;
+    }
+
+    if code.starts_with(">=") {
+      code = &code[2..];
+      tokens.push(Token::GreaterEqual);
+      continue;
+    }
+
+    if code.starts_with(">") {
+      code = &code[1..];
+      tokens.push(Token::Greater);
+      continue;
+    }
+
+    if code.starts_with("!=") {
+      code = &code[2..];
+      tokens.push(Token::NotEqual);
+      continue;
+    }
+
+    if code.starts_with('"') {
+      // a string ends on the line it starts on.
+      let Some(end) = code[1..].find(['"', '\n']).filter(|end| code[1 + end..].starts_with('"')) else {
+        let length = code.find('\n').unwrap_or(code.len());
+        let span = Span { start, end: start + length };
+        return Err(Diagnostic::new(codes::UNTERMINATED_STRING, "missing '\"' at the end of the string").with_span(span));
+      };
+      tokens.push(Token::Str(String::from(&code[1..1 + end])));
+      code = &code[end + 2..];
+      continue;
+    }
+
+    let (success, token, rest) = lex_identifier(code);
+    if success {
+      code = rest;
+      tokens.push(token);
+      continue;
+    }
+
+    let symbol = unrecognized_symbol(code);
+    let span = Span { start, end: start + symbol.len() };
+    return Err(Diagnostic::new(codes::UNRECOGNIZED_SYMBOL, format!("Unidentified symbol {symbol}")).with_span(span));
+
+  }
+
+  return Ok((tokens, spans));
+}
+
+fn lex_space(code: &str) -> (bool, &str) {
+  for letter in code.chars() {
+    if letter.is_whitespace() {
+      return (true, &code[1..]);
+    } else {
+      return (false, code);
+    }
+  }
+  return (false, code);
+}
+
+// lex numbers. a number too big for a long is lexed as a NotToken, for lex_with_spans() to report.
+fn lex_number(code: &str) -> (bool, Token, &str) {
+  enum StateMachine {
+    Start,
+    Number,
+  }
+
+  let mut success = false;
+  let mut state = StateMachine::Start;
+  let mut index = 0;
+  for letter in code.chars() {
+    match state {
+    StateMachine::Start => {
+      if letter >= '0' && letter <= '9' {
+        state = StateMachine::Number;
+        success = true;
+        index += 1;
+      }  else {
+        return (false, Token::NotToken, "");
+      }
+    }
+
+    StateMachine::Number => {
+      if letter >= '0' && letter <= '9' {
+        state = StateMachine::Number;
+        success = true;
+        index += 1;
+      } else if (letter >= 'A' || letter <= 'Z') && (letter >= 'a' && letter <= 'z'){
+        log!(Level::Trace, "Invalid token: {}\n", letter as i32);
+        return (false, Token::NotToken, "");
+      } else {
+        let token = code[..index].parse::<i64>().map_or(Token::NotToken, Token::Num);
+        return (true, token, &code[index..]);
+      }
+    }
+
+    }
+  }
+
+  if success == true {
+    let token = code.parse::<i64>().map_or(Token::NotToken, Token::Num);
+    return (true, token, "");
+  } else {
+    return (false, Token::NotToken, "");
+  }
+}
+
+// lex identifiers.
+fn lex_identifier(code: &str) -> (bool, Token, &str) {
+  enum StateMachine {
+    Start,
+    Ident,
+  }
+
+  let mut success = false;
+  let mut state = StateMachine::Start;
+  let mut index = 0;
+  for letter in code.chars() {
+    match state {
+    StateMachine::Start => {
+      if (letter >= 'a' && letter <= 'z') || (letter >= 'A' && letter <= 'Z'){
+        state = StateMachine::Ident;
+        success = true;
+        index += 1;
+      } else {
+        return (false, Token::NotToken, "");
+      }
+    }
+
+    StateMachine::Ident => {
+      if (letter >= 'A' && letter <= 'Z') || (letter >= 'a' && letter <= 'z') || (letter >= '0' && letter <= '9') || letter == '_' {
+        state = StateMachine::Ident;
+        success = true;
+        index += 1;
+      } else {
+        let token = &code[..index];
+        return (true, create_identifier(token), &code[index..]);
+      }
+    }
+
+    }
+  }
+
+  if success == true {
+    return (true, create_identifier(code), "");
+  } else {
+    return (false, Token::NotToken, "");
+  }
+}
+
+// Function to skip comments in the code
+// takes a reference to a string (code) as input, returns a slice of the string.
+// If the input code contains a newline character ('\n'),  returns a slice starting from the character immediately after the newline.
+//If no newline character is found, it returns an empty string.
+fn skip_comment(code: &str) -> &str {
+  if let Some(pos) = code.find('\n') {
+      &code[pos + 1..]
+  } else {
+      ""
+  }
+}
+
+
+fn unrecognized_symbol(code: &str) -> &str {
+  enum StateMachine {
+    Start,
+    Symbol,
+  }
+
+  let mut state_machine = StateMachine::Start;
+  let mut index = 0;
+  for letter in code.chars() {
+    match state_machine {
+    StateMachine::Start => {
+      state_machine = StateMachine::Symbol;
+      index += 1;
+    } 
+    
+    StateMachine::Symbol => {
+      if letter.is_whitespace() {
+        return &code[..index];
+      } else {
+        index += 1;
+      }
+    }
+
+    }
+  }
+  return &code[..index];
+} 
+
+fn create_identifier(code: &str) -> Token {
+  match code {
+  "func" => Token::Func,
+  "return" => Token::Return,
+  "int" => Token::Int,
+  "long" => Token::Long,
+
+  // todo: implement all keywords...
+  // ... all keywords...
+
+  "read" => Token::Read,
+  "while" => Token::While,
+  "if" => Token::If,
+
+  // print, else, break, continue keywords
+  "print" => Token::Print,
+  "else" => Token::Else,
+  "break" => Token::Break,
+  "continue" => Token::Continue,
+  "import" => Token::Import,
+  _ => Token::Ident(String::from(code)),
+  }
+}
+
+
+
+// the <'a> is the "lifetimes" type annotations in Rust.
+//
+fn peek<'a>(tokens: &'a [Token], index: usize) -> Option<&'a Token> {
+    if index < tokens.len() {
+        return Some(&tokens[index])
+    } else {
+        return None
+    }
+}
+
+fn peek_result<'a>(tokens: &'a [Token], index: usize) -> Result<&'a Token, Diagnostic> {
+    if index < tokens.len() {
+        return Ok(&tokens[index])
+    } else {
+        return Err(Diagnostic::new(codes::UNEXPECTED_END, "expected a token, but got nothing"))
+    }
+}
+
+fn next<'a>(tokens: &'a [Token], index: &mut usize) -> Option<&'a Token> {
+    if *index < tokens.len() {
+        let ret = *index;
+        *index += 1;
+        return Some(&tokens[ret])
+    } else {
+        return None
+    }
+}
+
+fn next_result<'a>(tokens: &'a [Token], index: &mut usize) -> Result<&'a Token, Diagnostic> {
+    if *index < tokens.len() {
+        let ret = *index;
+        *index += 1;
+        return Ok(&tokens[ret])
+    } else {
+        return Err(Diagnostic::new(codes::UNEXPECTED_END, "expected a token, but got nothing"))
+    }
+}
+
+// parse programs with multiple functions.
+// imports are left to imports.rs, the parser only checks that they are well formed.
+// loop over everything, outputting generated code.
+fn parse_program(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<String, Diagnostic> {
+  let mut generated_code = String::from("");
+  loop {
+      if matches!(peek(tokens, *index), Some(Token::Import)) {
+        parse_import(tokens, index, ctx)?;
+        continue;
+      }
+      match parse_function(tokens, index, ctx)? {
+      None => {
+          break;
+      }
+      Some(func_code) => {
+        generated_code += &func_code;
+      }
+      }
+  }
+  return Ok(generated_code);
+}
+
+// parse an import such as:
+// import "lib/gcd.tt";
+fn parse_import(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<(), Diagnostic> {
+  trace!(ctx, "parse_import\n");
+  *index += 1;
+  if !matches!(next_result(tokens, index)?, Token::Str(_)) {
+    return Err(Diagnostic::new(codes::EXPECTED_IMPORT_PATH, "expected the path of a file in double quotes after import"));
+  }
+  if !matches!(next_result(tokens, index)?, Token::Semicolon) {
+    return Err(Diagnostic::new(codes::MISSING_SEMICOLON, "expect ';' after import"));
+  }
+  Ok(())
+}
+
+// parse function such as:
+// func main(int a, int b) {
+//    # ... statements here...
+//    # ...
+// }
+// a function returning a long says so after its parameters:
+// func fact(long n) long {
+// a loop is done to handle statements.
+fn parse_function(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Option<String>, Diagnostic> {
+  trace!(ctx, "parse_function\n");
+  // Check if the next token is 'func'
+  match next(tokens, index) {
+      None => { // If there are no more tokens, return None
+          return Ok(None);
+      }
+      Some(token) => {
+          if let Token::Ident(ident) = token {
+              return Err(Diagnostic::new(codes::EXPECTED_FUNC, format!("functions must begin with func{}", suggest::did_you_mean(suggest::keyword(ident)))));
+          }
+          if !matches!(token, Token::Func) { // If the token is not 'func', return an error
+              return Err(Diagnostic::new(codes::EXPECTED_FUNC, "functions must begin with func"));
+          }
+      }
+  }
+  // Check if the next token is an identifier
+  let func_ident = match next_result(tokens, index)? {
+      Token::Ident(func_ident) => func_ident,
+      _ => { 
+        return Err(Diagnostic::new(codes::EXPECTED_FUNCTION_NAME, "functions must have a function identifier")); 
+      }
+  };
+  // Check if the next token is '('
+  if !matches!( next_result(tokens, index)?, Token::LeftParen) {
+      return Err(Diagnostic::new(codes::MISSING_PAREN, "expected '('"));
+  }
+  
+  // the mark of a function is the line of its name.
+  let mut code = format!("{}%func {}", ctx.mark(*index - 2), func_ident);
+  let mut params: Vec<(DataType, String)> = vec![];
+  ctx.variables.clear();
+  ctx.function = func_ident.clone();
+
+  // Loop to parse function parameters
+  loop {
+      match next_result(tokens, index)? {
+          Token::RightParen => { break; }
+          token @ (Token::Int | Token::Long) => {
+              let data_type = if matches!(token, Token::Long) { DataType::Long } else { DataType::Int };
+              match next_result(tokens, index)? {
+                  Token::Ident(param) => {
+                    ctx.variables.insert(param.clone(), data_type);
+                    params.push((data_type, param.clone()));  
+                    match peek_result(tokens, *index)? {
+                          Token::Comma => { *index += 1; }
+                          Token::RightParen => {}
+                          _ => { 
+                            return Err(Diagnostic::new(codes::INVALID_PARAMETERS, "expected ',' or ')'")); 
+                          }
+                      }
+                  }
+                  _ => { 
+                    return Err(Diagnostic::new(codes::INVALID_PARAMETERS, "expected ident function parameter")); 
+                  }
+              }
+          }
+          Token::Ident(ident) => {
+            return Err(Diagnostic::new(codes::INVALID_PARAMETERS, format!("expected 'int' or 'long' keyword or ')' token{}", suggest::did_you_mean(suggest::keyword(ident)))));
+          }
+          _ => { 
+            return Err(Diagnostic::new(codes::INVALID_PARAMETERS, "expected 'int' or 'long' keyword or ')' token")); 
+          }
+      }
+  }
+  // if there are params, add them to the function signature
+  trace!(ctx, "params: {:?}\n", params);
+  if(params.len() > 0){
+    code += &format!("(");
+    for (data_type, param) in params.iter() {
+      code += &format!("{} {}", declaration(Some(*data_type)), param);
+      code += &format!(", ");
+    }
+    code += &format!(")");
+    code = code.replace(", )", ")");
+  }
+  // the return type, int unless it says long.
+  match peek(tokens, *index) {
+    Some(Token::Long) => {
+      *index += 1;
+      code += " %long";
+    }
+    Some(Token::Int) => {
+      *index += 1;
+    }
+    _ => {}
+  }
+  code += &format!("\n");
+
+  // Check if the next token is '{'
+  if !matches!(next_result(tokens, index)?, Token::LeftCurly) {
+      return Err(Diagnostic::new(codes::MISSING_CURLY, "expected '{'"));
+  }
+  // Loop to parse statements inside the function body
+  loop {
+      match parse_statement(tokens, index, ctx)? {
+          None => { break; }
+          Some(statement) => {
+            code += &statement;
+          }
+      }
+  }
+  code += "%endfunc\n\n";
+  // Check if the next token is '}'
+  if !matches!(next_result(tokens, index)?, Token::RightCurly) {
+      return Err(Diagnostic::new(codes::MISSING_CURLY, "expected '}'"));
+  }
+  return Ok(Some(code)); // Return Ok if parsing is successful
+}
+
+// parsing a statement such as:
+// int a;
+// a = a + b;
+// a = a % b;
+// print(a)
+// read(a)
+// returns epsilon if '}'
+fn parse_statement(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Option<String>, Diagnostic> {
+  let mark = ctx.mark(*index);
+  let code = parse_unmarked_statement(tokens, index, ctx)?;
+  Ok(code.map(|code| mark + &code))
+}
+
+fn parse_unmarked_statement(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Option<String>, Diagnostic> {
+  trace!(ctx, "parse_statement {:?}\n", peek(tokens, *index));
+  match peek(tokens, *index) {
+      None => { // If there are no more tokens, return None
+          trace!(ctx, "parse statement exit (none)\n");
+          return Ok(None);
+      }
+      Some(token) => {
+        let codenode: Option<String>;
+          match token {
+              // If the token is '}', return None
+              Token::RightCurly => {
+                codenode = None;
+                return Ok(codenode); 
+              } 
+              // If the token is 'int' or 'long', parse variable declaration
+              Token::Int | Token::Long => { 
+                let long = matches!(token, Token::Long);
+                *index += 1;
+                match next_result(tokens, index)? {
+                  
+                  // need to return intermediate code for arrays here
+                  Token::LeftBracket => {
+                    if long {
+                      return Err(Diagnostic::new(codes::INVALID_STATEMENT, "arrays hold ints, declare them with 'int [size] name'"));
+                    }
+                    let array_param = parse_array_form(tokens, index, ctx)?;
+                    match peek(tokens, *index) {
+                      Some(Token::Ident(ident)) => {
+                        *index += 1;
+                        // let m_expr = parse_term(tokens, index)?; // Parse the next term
+                        // let t = create_temp();
+                        // let instr = format!("%int {}\n{opcode} {}, {}, {}\n", t, t, expr.name, m_expr.name);
+                        // expr.code += &m_expr.code;
+                        // expr.code += &instr;
+                        // expr.name = t;
+
+                        ctx.variables.insert(ident.clone(), DataType::Array);
+                        let statement = format!("%int[] {}, {}\n", ident, array_param.name);
+                        codenode = Some(statement);
+                      }
+                      _ => {
+                        return Err(Diagnostic::new(codes::EXPECTED_IDENTIFIER, "expected identifier"));
+                      }
+                    }
+                  }
+
+                  Token::Ident(ident) => {
+                    let data_type = if long { DataType::Long } else { DataType::Int };
+                    ctx.variables.insert(ident.clone(), data_type);
+                    let statement = format!("{} {}\n", declaration(Some(data_type)), ident);
+                    codenode = Some(statement);
+                    //println!("bsdgdfsdf");
+                  }
+
+                  _ => {
+                      return Err(Diagnostic::new(codes::EXPECTED_IDENTIFIER, "expected identifier"));
+                  }
+
+                }
+                // <NOT REQUIRED/PART OF PROMPT> Check if there's an assignment after variable declaration
+                //if matches!(peek_result(tokens, *index)?, Token::Assign) {
+                 //   *index += 1;
+                    //println!("parse expression after variable declaration\n");
+                //} 
+                
+              }
+              
+              // If the token is an identifier
+              Token::Ident(ident) => {
+                *index += 1; // Move to the next token index
+                // Check the next token
+                match peek(tokens, *index) {
+                    Some(Token::Assign) => {
+                        // If the next token is '=', parse an assignment
+                        *index += 1;
+                        trace!(ctx, "parse expression after identifier\n");
+                        let expr = parse_expression(tokens, index, ctx)?;
+                        let expr = coerce(expr, ctx.type_of(ident));
+                        let code = format!("{}%mov {}, {}\n", expr.code, ident, expr.name);
+                        codenode = Some(code);
+                        // codenode = None;
+                        
+                    }
+                    Some(Token::Less) | Some(Token::LessEqual) | Some(Token::Greater) | Some(Token::GreaterEqual) | Some(Token::Equality) | Some(Token::NotEqual) => {
+                        // If the next token is a boolean operator, parse a boolean expression
+                        //println!("parse boolean expression after variable declaration\n");
+                        // parse_boolean_expression(tokens, index)?;
+                        let bool_expr = parse_boolean_expression(tokens, index, ctx)?;
+                        let code = format!("{}%mov {}\n", bool_expr.code, bool_expr.name);
+                        codenode = Some(code);
+                    }
+                    Some(Token::LeftBracket) =>{
+                        let array_param =parse_array_form(tokens, index, ctx)?;
+                        //println!("current tok4: {:?}", tokens[*index]);
+                        match peek(tokens, *index){
+                          Some(Token::Assign) => {
+                            // If the next token is '=', parse an assignment
+                            *index += 1;
+                            //println!("parse expression after identifier");
+                            // parse_expression(tokens, index)?;
+                            let expr = parse_expression(tokens, index, ctx)?;
+                            let expr = coerce(expr, Some(DataType::Int));
+                            let code = format!("{}%mov [{} + {}], {}\n", expr.code, ident, array_param.name, expr.name);
+                            codenode = Some(code);
+                          }
+                          Some(Token::Less) | Some(Token::LessEqual) | Some(Token::Greater) | Some(Token::GreaterEqual) | Some(Token::Equality) | Some(Token::NotEqual) => {
+                            // If the next token is a boolean operator, parse a boolean expression
+                            //println!("parse boolean expression after variable declaration\n");
+                            // parse_boolean_expression(tokens, index)?;
+                            let bool_expr = parse_boolean_expression(tokens, index, ctx)?;
+                            let code = format!("{}%mov {}\n", bool_expr.code, bool_expr.name);
+                            codenode = Some(code);
+                          }
+                          _ => {
+                            codenode = None;
+                            return Err(Diagnostic::new(codes::UNEXPECTED_AFTER_IDENTIFIER, "unexpected token after identifier []"));
+                        }
+                        }
+
+                    }
+                    _ => {
+                        codenode = None;
+                        // 'whle i < 10' or 'pritn(a)' end up here, so check for a misspelled keyword.
+                        return Err(Diagnostic::new(codes::UNEXPECTED_AFTER_IDENTIFIER, format!("unexpected token after identifier{}", suggest::did_you_mean(suggest::keyword(ident)))));
+                    }
+                }
+              }
+              // If the token is 'return', parse the expression
+              Token::Return => { 
+                *index += 1; 
+                let expr = parse_expression(tokens, index, ctx)?;
+                let returns = ctx.signatures.get(&ctx.function).map(|signature| signature.returns);
+                let expr = coerce(expr, returns);
+                let code = format!("{}%ret {}\n", expr.code, expr.name);
+                codenode = Some(code);
+              }
+              // If the token is 'print' 
+              Token::Print => { 
+                  *index += 1; // Move to the next token index
+                  if !matches!(next_result(tokens, index)?, Token::LeftParen) { // If the next token is not '(', return an error
+                      return Err(Diagnostic::new(codes::MISSING_PAREN, "expect '(' closing statement"));
+                  }
+                  let mut expr = parse_term(tokens, index, ctx)?;
+                  trace!(ctx, "parse print expression: {}\n", expr.name);
+                  let array_num;
+                  if(matches!(peek_result(tokens, *index)?, Token::LeftBracket)){
+                    array_num = parse_array_form(tokens, index, ctx)?;
+                    let t = ctx.create_temp();
+                    expr.code = format!("{}%int {}\n%mov {}, [{} + {}]\n", expr.code,t,t, expr.name, array_num.name);
+                    expr.name = t;
+                  }
+                  let code = format!("{}%out {}\n", expr.code, expr.name);
+                  if !matches!(next_result(tokens, index)?, Token::RightParen) { // If the next token is not ')', return an error
+                      return Err(Diagnostic::new(codes::MISSING_PAREN, "expect ')' closing statement"));
+                  }
+                  codenode = Some(code)
+              }
+              // If the token is 'read'
+              Token::Read => { 
+                  *index += 1; // Move to the next token index
+                  if !matches!(next_result(tokens, index)?, Token::LeftParen) { // If the next token is not '(', return an error
+                      return Err(Diagnostic::new(codes::MISSING_PAREN, "expect '(' closing statement"));
+                  }
+                  let expr = parse_expression(tokens, index, ctx)?;
+                  let code = format!("{}%input {}\n", expr.code, expr.name);
+                  if !matches!(next_result(tokens, index)?, Token::RightParen) { // If the next token is not ')', return an error
+                      return Err(Diagnostic::new(codes::MISSING_PAREN, "expect ')' closing statement"));
+                  }
+                  codenode = Some(code);
+              }
+
+              Token::While => { 
+                //println!("While");
+                let start = *index;
+                *index += 1; // Move to the next token index
+                let while_label = ctx.create_whileloopbegin_label();
+                let endwhile_label = ctx.create_whileloopend_label();
+                ctx.while_label_stack.push((while_label.clone(), endwhile_label.clone()));
+                let mut statementCode: String = String::from("");
+                let condition = parse_boolean_expression(tokens, index, ctx)?; // Parse boolean expression
+                let mut code = format!(":{}\n", while_label);
+                code += &format!("{}%branch_ifn {}, :{}\n",condition.code, condition.name, endwhile_label);
+                //println!("parsed");
+                if !matches!(next_result(tokens, index)?, Token::LeftCurly) { // If the next token is not '{', return an error
+                    return Err(Diagnostic::new(codes::MISSING_CURLY, "expect '{' for while loop"));
+                }
+                //println!("after left curly: {:?}", tokens[*index]);
+                while !matches!(peek_result(tokens, *index)?, Token::RightCurly){ // while not right bracket
+                  //println!("not right bracket: {:?}", tokens[*index]);
+                  // parse_statement(tokens, index)?;
+                  match parse_statement(tokens, index, ctx)? {
+                    None => { /* do nothing lol */ }
+                    Some(statement) => {
+                      statementCode += &statement;
+                    }
+                  }
+                }
+                *index += 1; // matched a }
+                code += &statementCode;
+                // jumping back belongs to the line of the while, not to the last statement of the loop.
+                code += &ctx.mark(start);
+                code += &format!("%jmp :{}\n",while_label);
+                code += &format!(":{}\n", endwhile_label);
+                
+                // return Ok(Some(String::from("TODO:While"))); // skip ; check
+                ctx.while_label_stack.pop();
+                return Ok(Some(code)); // skip ; check
+              }
+            
+              Token::If => {
+                //println!("if");
+                let start = *index;
+                *index += 1; // Move to the next token index
+                // create labels
+                let iftrue_label = ctx.create_iftrue_label();
+                let endif_label = ctx.create_endif_label();
+                //let else_label = create_else_label();
+                trace!(ctx, "before if parse boolean expression: {:?}\n", peek(tokens, *index));
+                let conditional = parse_boolean_expression(tokens, index, ctx)?; // Parse boolean expression
+                trace!(ctx, "after if parse boolean expression: {:?}\n", peek(tokens, *index));
+                let mut code = format!("{}%branch_if {}, :{}\n",conditional.code, conditional.name, iftrue_label);
+                let mut statementCode: String = String::from("");
+                if !matches!(next_result(tokens, index)?, Token::LeftCurly) { // If the next token is not '{', return an error
+                  return Err(Diagnostic::new(codes::MISSING_CURLY, "expect '{' closing statement"));
+                }
+                while !matches!(peek_result(tokens, *index)?, Token::RightCurly){ // while not right bracket
+                  match parse_statement(tokens, index, ctx)? {
+                    None => { /* do nothing lol */ }
+                    Some(statement) => {
+                      statementCode += &statement;
+                    }
+                  }
+                }
+                *index += 1;
+                // handle else
+                if matches!(peek_result(tokens, *index)?, Token::Else) {
+                  *index += 1; // Move to the next token index
+                  if !matches!(next_result(tokens, index)?, Token::LeftCurly) { // If the next token is not '{', return an error
+                    return Err(Diagnostic::new(codes::MISSING_CURLY, "expect '{' opening statement"));
+                  }
+                  while !matches!(peek_result(tokens, *index)?, Token::RightCurly){ // while not right bracket
+                    match parse_statement(tokens, index, ctx)? {
+                      None => { /* do nothing lol */ }
+                      Some(statement) => {
+                        code += &statement;
+                      }
+                    }
+                  }
+
+                  *index += 1;
+                }
+                code += &ctx.mark(start);
+                code += &format!("%jmp :{}\n", endif_label);
+                code += &format!(":{}\n", iftrue_label);
+                code += &statementCode;
+                code += &format!(":{}\n", endif_label);
+                return Ok(Some(code)); // skip ; check
+              }
+
+              Token::Continue => { 
+                *index += 1; // Move to the next token index
+                let code = match ctx.while_label_stack.last() {
+                  Some((while_label, _)) => format!("%jmp :{}\n", while_label),
+                  None if ctx.syntax_only => String::new(),
+                  None => return Err(Diagnostic::new(codes::CONTINUE_OUTSIDE_LOOP, "continue statement outside of loop")),
+                };
+                codenode = Some(code);
+              }
+
+              Token::Break => { 
+                *index += 1; // Move to the next token index
+                let code = match ctx.while_label_stack.last() {
+                  Some((_, endwhile_label)) => format!("%jmp :{}\n", endwhile_label),
+                  None if ctx.syntax_only => String::new(),
+                  None => return Err(Diagnostic::new(codes::BREAK_OUTSIDE_LOOP, "break statement outside of loop")),
+                };
+                codenode = Some(code);
+              }
+
+              // If the token is invalid, return an error
+              
+              _ => {
+                codenode = None;
+                trace!(ctx, "Token at invalid statement: {:?}\n", peek(tokens, *index));
+                return Err(Diagnostic::new(codes::INVALID_STATEMENT, "invalid statement.")); } 
+          }
+          //println!("before ; : {:?}", tokens[*index]);
+          if !matches!(next_result(tokens, index)?, Token::Semicolon) { // If the next token is not ';', return an error
+              trace!(ctx, "not ; : {:?}\n", peek(tokens, *index));
+              return Err(Diagnostic::new(codes::MISSING_SEMICOLON, "expect ';' closing statement after statement"));
+          }
+          return Ok(codenode); // Return Ok if parsing is successful
+      }
+  }
+}
+
+// parsing a simple expression such as:
+// "a" (alone)
+// "a + b"
+// "a * b"
+// "a - b"
+// NOTE: this cannot parse "complex" expressions such as "a + b * c".
+// I leave "a + b * c" as an exercise for the student.
+fn parse_expression(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Expression, Diagnostic> {
+  let mut expr = parse_multiply_expression(tokens, index, ctx)?; // this gets the identifier or num
+  trace!(ctx, "parse_expression {}\n", expr.name);
+  //parse_term(tokens, index)?; // Parse the first term
+  if(matches!(peek_result(tokens, *index)?, Token::LeftBracket)){
+    let arraynum = parse_array_form(tokens, index, ctx)?;
+    let t = ctx.create_temp();
+    expr.code = format!("{}%int {}\n%mov {}, [{} + {}]\n", expr.code,t,t, expr.name, arraynum.name);
+    expr.name = format!("{}", t);
+    expr.data_type = Some(DataType::Int);
+  }
+  loop {
+    let opcode = match peek_result(tokens, *index)?{
+      Token::Plus => "%add",
+      Token::Subtract => "%sub",
+      // Token::Multiply => "%mult",
+      // Token::Divide => "%div",
+      // Token::Modulus => "%mod",
+      _ => break,
+    };
+    *index += 1; //go to next token
+    let right_expr = parse_multiply_expression(tokens, index, ctx)?; // Parse the next higher precedence expression
+    let (left_expr, right_expr, data_type) = common_type(expr, right_expr);
+    let temp_var = ctx.create_temp(); // Create a temporary variable for the result
+    let code = format!("{} {}\n{} {}, {}, {}\n", declaration(data_type), temp_var, opcode, temp_var, left_expr.name, right_expr.name);
+    expr = Expression { code: left_expr.code + &right_expr.code + &code, name: temp_var, data_type }; // Combine the code from both expressions
+  }
+  Ok(expr) // Return the final expression
+}
+
+// Function to parse and evaluate expressions involving multiplication, division, and modulus
+fn parse_multiply_expression(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Expression, Diagnostic> {
+  // Start by parsing a term (either a number or an expression inside parentheses)
+  let mut expr = parse_term(tokens, index, ctx)?;
+  // Loop to handle multiplication, division, and modulus operations
+  loop {
+      let opcode = match peek_result(tokens, *index)? {
+          Token::Multiply => "%mult",
+          Token::Divide => "%div",
+          Token::Modulus => "%mod",
+          _ => break, // If it's not a multiplication, division, or modulus token, break the loop
+      };
+      *index += 1; // Move to the next token
+      let right_expr = parse_term(tokens, index, ctx)?; // Parse the next term
+      let (left_expr, right_expr, data_type) = common_type(expr, right_expr);
+      let temp_var = ctx.create_temp(); // Create a temporary variable for the result
+      let code = format!("{} {}\n{} {}, {}, {}\n", declaration(data_type), temp_var, opcode, temp_var, left_expr.name, right_expr.name);
+      expr = Expression { code: left_expr.code + &right_expr.code + &code, name: temp_var, data_type }; // Combine the code from both expressions
+  }
+  Ok(expr) // Return the final expression
+}
+
+
+fn parse_array_form(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Expression, Diagnostic> {
+  //println!("current tok: {:?}", tokens[*index]);
+  //println!("run parse expression after [");
+  if(matches!(peek_result(tokens, *index)?,Token::LeftBracket)){
+    *index += 1;
+  }
+  let number = parse_term(tokens, index, ctx)?; 
+  //println!("current tok2: {:?}", tokens[*index]);
+  if !matches!(next_result(tokens, index)?, Token::RightBracket) {
+    return Err(Diagnostic::new(codes::MISSING_BRACKET, "expected ']'"));
+  }
+  //println!("after ]: {:?}", tokens[*index]);
+  //*index += 1;
+
+  return Ok(number)
+}
+
+fn parse_boolean_expression(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Expression, Diagnostic> {
+  trace!(ctx, "parse_boolean_expression\n");
+  trace!(ctx, "term: {:?}\n", peek(tokens, *index));
+  let expr = parse_term(tokens, index, ctx)?; // Parse the left side of the expression
+  trace!(ctx, "after term: {:?}\n", peek(tokens, *index));
+  //println!("parsed : {:?}", tokens[*index]);
+
+  if(matches!(peek_result(tokens, *index)?,Token::LeftBracket)){
+    parse_array_form(tokens, index, ctx)?;
+  }
+  let opcode = match peek_result(tokens, *index)?{
+    Token::Less => "%lt",
+    Token::LessEqual => "%le",
+    Token::Greater => "%gt",
+    Token::GreaterEqual => "%ge",
+    Token::Equality => "%eq",
+    Token::NotEqual => "%neq",
+    _ => "%mov"
+  };
+
+  match peek_result(tokens, *index)? {
+      Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual | Token::Equality | Token::NotEqual => {
+          *index += 1; // Move to the next token
+          trace!(ctx, "boolean operator : {:?}\n", peek(tokens, *index));
+          // parse_term(tokens, index)?; // Parse the right side of the expression
+          trace!(ctx, "term: {:?}\n", peek(tokens, *index));
+          let m_expr = parse_term(tokens, index, ctx)?; // Parse the next term
+          trace!(ctx, "after term: {:?}\n", peek(tokens, *index));
+          // ints and longs are compared as they are, the result is an int either way.
+          let (left_expr, m_expr, _) = common_type(expr, m_expr);
+          let t = ctx.create_temp();
+          let code = format!("%int {t}\n{opcode} {t}, {}, {}\n", left_expr.name, m_expr.name);
+          Ok(Expression { code: left_expr.code + &m_expr.code + &code, name: t, data_type: Some(DataType::Int) })
+      }
+      _ => {
+        trace!(ctx, "not boolean operator : {:?}\n", peek(tokens, *index));
+        return Err(Diagnostic::new(codes::EXPECTED_COMPARISON, "expected boolean operator"))
+      },
+  }
+}
+
+
+// a term is either a Number or an Identifier.
+// fn parse_term(tokens: &[Token], index: &mut usize) -> Result<Expression, Diagnostic> {
+//   match next_result(tokens, index)? {
+//     Token::Ident(name) => {
+//         // Check if the first character of the identifier is a digit
+//         if name.chars().next().unwrap().is_digit(10) {
+//             return Err(format!("Variable names cannot start with a digit: {}", name));
+//         }
+//         // Successfully parsed an identifier
+//         let expr = Expression {
+//           code : String::from(""),
+//           name : name.clone(),
+//         };
+//         return Ok(expr);
+//     }
+//     Token::Num(num) => {
+//         let expr = Expression {
+//           code : String::from(""),
+//           name : format!("{}", num),
+//         };
+//         return Ok(expr);
+//     }
+//     Token::LeftParen => {
+//       // Parse the expression inside the parentheses
+//       parse_expression(tokens, index)?;
+//       // Check if the next token is a right parenthesis
+//       if let Token::RightParen = next_result(tokens, index)? {
+//         let expr = Expression {
+//           code : String::from(""),
+//           name : format!("{}", "("),
+//         };
+//           return Ok(expr);
+//       } else {
+//           return Err(Diagnostic::new(codes::MISSING_PAREN, "Expecting ')' after '('"));
+//       }
+//     }
+//     _ => {
+//         return Err(Diagnostic::new(codes::INVALID_EXPRESSION, "invalid expression"));
+//     }
+//   }
+// }
+
+// fn parse_term(tokens: &[Token], index: &mut usize) -> Result<Expression, Diagnostic> {
+//   match next_result(tokens, index)? {
+//       Token::Ident(name) => {
+//           let mut expr = Expression {
+//               code: String::new(),
+//               name: name.clone(),
+//           };
+//           if(matches!(peek_result(tokens, *index)?, Token::LeftParen)){
+//             println!("function call {}", name);
+//             *index += 1;
+//             let mut params: Vec<String> = vec![];
+//             loop{
+//               match peek_result(tokens, *index)? {
+//                 Token::RightParen => {
+//                   *index += 1;
+//                   break;
+//                 }
+//                 _ => {
+//                   let expr_param = parse_expression(tokens, index)?;
+//                   params.push(expr_param.name);
+//                   expr.code += &expr_param.code;
+//                   if(matches!(peek_result(tokens, *index)?, Token::Comma)){
+//                     *index += 1;
+//                   } else {
+//                      // pass the right paren
+//                     *index += 1;
+//                     break;
+//                   }
+//                 }
+//               }
+//             }
+//             // done with params
+//             // what we will write the return value to
+//             let t = create_temp();
+//             expr.code += &format!("%int {}\n", t);
+//             expr.code += &format!("%call {}, {}({})\n", t, name, params.join(","));
+//             expr.name = t;
+          
+//           }
+//           Ok(expr)
+//       },
+//       Token::Num(num) => {
+//           let expr = Expression {
+//               code: String::new(),
+//               name: format!("{}", num),
+//           };
+//           Ok(expr)
+//       },
+//       Token::LeftParen => {
+//           let expr = parse_expression(tokens, index)?;
+//           if !matches!(next_result(tokens, index)?, Token::RightParen) {
+//               Err(Diagnostic::new(codes::MISSING_PAREN, "Expecting ')' after '('"))
+//           } else {
+//               Ok(expr)
+//           }
+//       },
+//       _ => Err(Diagnostic::new(codes::INVALID_EXPRESSION, "invalid expression")),
+//   }
+// }
+
+// a conversion is a term too: long(a) widens an int to a long, int(a) narrows a long to an int.
+fn parse_term(tokens: &[Token], index: &mut usize, ctx: &mut Context) -> Result<Expression, Diagnostic> {
+  match next_result(tokens, index)? {
+      Token::Num(num) => {
+          // a number beyond the ints is a long, the others fit either type.
+          let expr = Expression {
+              code: String::from(""),
+              name: format!("{}", num),
+              data_type: if i32::try_from(*num).is_ok() { None } else { Some(DataType::Long) },
+          };
+          Ok(expr)
+      }
+      token @ (Token::Int | Token::Long) if matches!(peek(tokens, *index), Some(Token::LeftParen)) => {
+          let to = if matches!(token, Token::Long) { DataType::Long } else { DataType::Int };
+          *index += 1;
+          let expr = parse_expression(tokens, index, ctx)?;
+          if !matches!(next_result(tokens, index)?, Token::RightParen) {
+              return Err(Diagnostic::new(codes::MISSING_PAREN, "Expecting ')' after '('"));
+          }
+          match (expr.data_type, to) {
+              (Some(DataType::Int), DataType::Long) | (Some(DataType::Long), DataType::Int) => {
+                  let t = ctx.create_temp();
+                  let opcode = if to == DataType::Long { "%widen" } else { "%narrow" };
+                  let code = format!("{}{} {t}\n{opcode} {t}, {}\n", expr.code, declaration(Some(to)), expr.name);
+                  Ok(Expression { code, name: t, data_type: Some(to) })
+              }
+              _ => Ok(coerce(expr, Some(to))),
+          }
+      }
+      Token::Ident(name) => {
+          let mut expr = Expression {
+              code: String::new(),
+              name: name.clone(),
+              data_type: ctx.type_of(name),
+          };
+          // Check for function call or array indexing
+          match peek_result(tokens, *index)? {
+              Token::LeftParen => {
+                  *index += 1;
+                  let signature = ctx.signatures.get(name).cloned();
+                  let mut params: Vec<String> = vec![];
+                  loop {
+                      match peek_result(tokens, *index)? {
+                          Token::RightParen => {
+                              *index += 1;
+                              break;
+                          }
+                          _ => {
+                              let expr_param = parse_expression(tokens, index, ctx)?;
+                              let param_type = signature.as_ref().and_then(|signature| signature.params.get(params.len()).copied());
+                              let expr_param = coerce(expr_param, param_type);
+                              params.push(expr_param.name);
+                              expr.code += &expr_param.code;
+                              if matches!(peek_result(tokens, *index)?, Token::Comma) {
+                                  *index += 1;
+                              } else {
+                                  *index += 1;
+                                  break;
+                              }
+                          }
+                      }
+                  }
+                  let returns = signature.map_or(DataType::Int, |signature| signature.returns);
+                  let temp_var = ctx.create_temp();
+                  expr.code += &format!("{} {}\n", declaration(Some(returns)), temp_var);
+                  expr.code += &format!("%call {}, {}({})\n", temp_var, name, params.join(","));
+                  expr.name = temp_var;
+                  expr.data_type = Some(returns);
+              }
+              Token::LeftBracket => {
+                  *index += 1;
+                  let index_expr = parse_expression(tokens, index, ctx)?;
+                  if !matches!(next_result(tokens, index)?, Token::RightBracket) {
+                      return Err(Diagnostic::new(codes::MISSING_BRACKET, "Expecting ']' after '['"));
+                  }
+                  let index_expr = coerce(index_expr, Some(DataType::Int));
+                  let temp_var = ctx.create_temp();
+                  expr.code += &index_expr.code;
+                  expr.code += &format!("%int {}\n", temp_var);
+                  expr.code += &format!("%mov {}, [{} + {}]\n", temp_var, name, index_expr.name);
+                  expr.name = temp_var;
+                  expr.data_type = Some(DataType::Int);
+              }
+              _ => {}
+          }
+          Ok(expr)
+      }
+      Token::LeftParen => {
+          let expr = parse_expression(tokens, index, ctx)?;
+          if !matches!(next_result(tokens, index)?, Token::RightParen) {
+              Err(Diagnostic::new(codes::MISSING_PAREN, "Expecting ')' after '('"))
+          } else {
+              Ok(expr)
+          }
+      }
+      _ => Err(Diagnostic::new(codes::INVALID_EXPRESSION, "invalid expression")),
+  }
+}
+
+
+// writing tests!
+// testing shows robustness in software, and is good for spotting regressions
+// to run a test, type "cargo test" in the terminal.
+// Rust will then run all the functions annotated with the "#[test]" keyword.
+#[cfg(test)]
+mod tests {
+    use crate::Token;
+    use crate::lex;
+    use crate::lex_with_spans;
+    use crate::diagnostics::Span;
+
+    #[test]
+    fn lexer_test() {
+
+        let toks = lex("1 <2").unwrap();
+        assert!(toks.len() == 3);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::Less));
+        assert!(matches!(toks[2], Token::Num(2)));
+
+        let toks = lex("1 > 2").unwrap();
+        assert!(toks.len() == 3);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::Greater));
+        assert!(matches!(toks[2], Token::Num(2)));
+
+        let toks = lex("1 <= 2").unwrap();
+        assert!(toks.len() == 3);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::LessEqual));
+        assert!(matches!(toks[2], Token::Num(2)));
+
+        let toks = lex("1>=2").unwrap();
+        assert!(toks.len() == 3);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::GreaterEqual));
+        assert!(matches!(toks[2], Token::Num(2)));
+
+        let toks = lex("1==2").unwrap();
+        assert!(toks.len() == 3);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::Equality));
+        assert!(matches!(toks[2], Token::Num(2)));
+
+        let toks = lex("1!=2").unwrap();
+        assert!(toks.len() == 3);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::NotEqual));
+        assert!(matches!(toks[2], Token::Num(2)));
+
+        // test that lexer works on correct cases
+        let toks = lex("1 + 2 + 3").unwrap();
+        assert!(toks.len() == 5);
+        assert!(matches!(toks[0], Token::Num(1)));
+        assert!(matches!(toks[1], Token::Plus));
+        assert!(matches!(toks[2], Token::Num(2)));
+        assert!(matches!(toks[3], Token::Plus));
+        assert!(matches!(toks[4], Token::Num(3)));
+
+
+        let toks = lex("3 + 215 +-").unwrap();
+        assert!(toks.len() == 5);
+        assert!(matches!(toks[0], Token::Num(3)));
+        assert!(matches!(toks[1], Token::Plus));
+        assert!(matches!(toks[2], Token::Num(215)));
+        assert!(matches!(toks[3], Token::Plus));
+        assert!(matches!(toks[4], Token::Subtract));
+
+        // test that the lexer catches invalid tokens
+        assert!(matches!(lex("^^^"), Err(_)));
+
+        //test that lexer identifies left paren and right paren
+        let toks = lex("( ( ( ( ) ) ) )").unwrap();
+        assert!(toks.len() == 8);
+        assert!(matches!(toks[0], Token::LeftParen));
+        assert!(matches!(toks[1], Token::LeftParen));
+        assert!(matches!(toks[2], Token::LeftParen));
+        assert!(matches!(toks[3], Token::LeftParen));
+        assert!(matches!(toks[4], Token::RightParen));
+        assert!(matches!(toks[5], Token::RightParen));
+        assert!(matches!(toks[6], Token::RightParen));
+        assert!(matches!(toks[7], Token::RightParen));
+
+
+        // test for print
+        let toks = lex("print ").unwrap();
+        assert!(toks.len() == 1);
+        assert!(matches!(toks[0], Token::Print));
+
+         // test for else
+         let toks = lex("else ").unwrap();
+         assert!(toks.len() == 1);
+         assert!(matches!(toks[0], Token::Else));
+
+          // test for break
+        let toks = lex("break ").unwrap();
+        assert!(toks.len() == 1);
+        assert!(matches!(toks[0], Token::Break));
+
+         // test for continue
+        let toks = lex("continue ").unwrap();
+        assert!(toks.len() == 1);
+        assert!(matches!(toks[0], Token::Continue));
+
+         //test for comments
+        let toks = lex("#Hello \n 1").unwrap();
+        assert!(toks.len() == 1);
+        assert!(matches!(toks[0], Token::Num(1)));
+    }
+
+    #[test]
+    fn lexer_spans() {
+        let (toks, spans) = lex_with_spans("int abc; # c\n  x <= 12").unwrap();
+        assert!(toks.len() == spans.len());
+        let text: Vec<(usize, usize)> = spans.iter().map(|span| (span.start, span.end)).collect();
+        assert!(text == vec![(0, 3), (4, 7), (7, 8), (15, 16), (17, 19), (20, 22)]);
+
+        let error = lex_with_spans("int a;\n a $b").unwrap_err();
+        assert!(error.span == Some(Span { start: 10, end: 12 }));
+
+        let (toks, spans) = lex_with_spans("import \"lib.tt\";").unwrap();
+        assert!(format!("{:?}", toks) == r#"[Import, Str("lib.tt"), Semicolon]"#);
+        assert!(spans[1] == Span { start: 7, end: 15 });
+        let error = lex_with_spans("import \"lib.tt;\nfunc").unwrap_err();
+        assert!(error.span == Some(Span { start: 7, end: 15 }));
+    }
+
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use crate::{lex, parse_statement, Context, Token};
+
+    #[test]
+    fn test_assignment() {
+        // Test valid assignments
+        let tokens = lex("a = 1 + 2;").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+
+        // Test assignment with a boolean expression
+        let tokens = lex("b = a > 5;").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+
+        // Test assignment with a parenthesized expression
+        let tokens = lex("c = (a * 3);").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+
+        // Test assignment with multiple operators
+        let tokens = lex("d = (a + b) / (c - 1);").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+    }
+
+    #[test]
+    fn test_error_handling() {
+        // Test missing semicolon
+        let tokens = lex("e = a + b").unwrap();
+        assert!(matches!(parse_statement(&tokens, &mut 0, &mut Context::default()), Err(_)));
+
+        // Test invalid expression
+        let tokens = lex("f = a * ;").unwrap();
+        assert!(matches!(parse_statement(&tokens, &mut 0, &mut Context::default()), Err(_)));
+
+        // Test assignment with an invalid identifier
+        let tokens = lex("3 = a + b;").unwrap();
+        assert!(matches!(parse_statement(&tokens, &mut 0, &mut Context::default()), Err(_)));
+    }
+
+    #[test]
+    fn test_control_flow() {
+        // Test if statement
+        let tokens = lex("if a > 5 { b = 10; } else { b = 5; }").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+
+        // Test while loop
+        let tokens = lex("while a < 10 { a = a + 1; }").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+
+        // Test for loop
+        let tokens = lex("for i = 0; i < 10; i = i + 1 { println(i); }").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+    }
+
+    #[test]
+    fn test_function_definition() {
+        // Test function definition
+        let tokens = lex("fn add(x, y) { return x + y; }").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+
+        // Test function call
+        let tokens = lex("result = add(3, 5);").unwrap();
+        parse_statement(&tokens, &mut 0, &mut Context::default()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod api_tests {
+    use std::io;
+
+    use crate::diagnostics::{codes, Span};
+    use crate::{compile_to_ir, interpreter, lex, parse};
+
+    #[test]
+    fn compile_and_execute() {
+        let compiled = compile_to_ir("func main() {\n  int a;\n  int b;\n  a = 6;\n  return a * 7;\n}").unwrap();
+        assert!(compiled.warnings.len() == 1);
+        assert!(interpreter::execute(&compiled.ir, io::empty(), io::sink()) == Ok(42));
+    }
+
+    #[test]
+    fn errors_of_each_stage() {
+        let errors = compile_to_ir("func main() {\n  int a $;\n}").unwrap_err();
+        assert!(errors[0].code == codes::UNRECOGNIZED_SYMBOL);
+
+        let errors = compile_to_ir("func main() {\n  int a\n}").unwrap_err();
+        assert!(errors[0].code == codes::MISSING_SEMICOLON);
+        assert!(errors[0].span.is_some());
+
+        let errors = compile_to_ir("func main() {\n  a = 1;\n}").unwrap_err();
+        assert!(errors[0].code == codes::UNDECLARED_VARIABLE);
+
+        let ir = parse(&lex("func main() {\n  int a;\n  a = 0;\n  a = 1 / a;\n}").unwrap()).unwrap();
+        assert!(interpreter::execute(&ir, io::empty(), io::sink()).unwrap_err().code == codes::DIVIDE_BY_ZERO);
+    }
+
+    #[test]
+    fn functions_call_functions() {
+        let compiled = compile_to_ir("func double(int x) {\n  return x * 2;\n}\nfunc quad(int x) {\n  return double(double(x));\n}\nfunc main() {\n  return quad(3);\n}").unwrap();
+        assert!(interpreter::execute(&compiled.ir, io::empty(), io::sink()) == Ok(12));
+    }
+
+    #[test]
+    fn runtime_errors_have_a_stack_trace() {
+        let source = "func divide(int a, int b) {\n  return a / b;\n}\n\nfunc main() {\n  int q;\n  q = divide(1, 0);\n}";
+        let compiled = compile_to_ir(source).unwrap();
+        assert!(compiled.ir.starts_with("; line 1\n%func divide(%int a, %int b)\n; line 2\n%int _temp1\n%div _temp1, a, b\n"));
+        let error = interpreter::execute(&compiled.ir, io::empty(), io::sink()).unwrap_err();
+        assert!(error.code == codes::DIVIDE_BY_ZERO && error.line == Some(2));
+        assert!(error.trace == vec!["in divide, line 2 (IR line 5)", "in main, line 7 (IR line 15)"]);
+        assert!(error.to_string().ends_with("zero.\n  in divide, line 2 (IR line 5)\n  in main, line 7 (IR line 15)"));
+
+        // IR without marks only has the lines of the IR.
+        let ir = parse(&lex(source).unwrap()).unwrap();
+        assert!(interpreter::execute(&ir, io::empty(), io::sink()).unwrap_err().trace == vec!["in divide, IR line 3", "in main, IR line 10"]);
+    }
+
+    #[test]
+    fn compiling_is_deterministic() {
+        let source = "func main() {\n  int i;\n  i = 0;\n  while i < 3 {\n    if i == 1 {\n      break;\n    }\n    i = i + 1;\n  }\n  print(i);\n}";
+        let first = compile_to_ir(source).unwrap().ir;
+        assert!(compile_to_ir(source).unwrap().ir == first);
+
+        // a compilation that fails inside a loop leaves nothing behind for the next one.
+        assert!(compile_to_ir("func main() {\n  while 1 < 2 {\n    break\n  }\n}").is_err());
+        assert!(compile_to_ir("func main() {\n  break;\n}").unwrap_err()[0].code == codes::BREAK_OUTSIDE_LOOP);
+
+        let threads: Vec<_> = (0..4).map(|_| std::thread::spawn(move || compile_to_ir(source).unwrap().ir)).collect();
+        for thread in threads {
+            assert!(thread.join().unwrap() == first);
+        }
+    }
+
+    #[test]
+    fn longs() {
+        let source = "func fact(long n) long {\n  if n < 2 {\n    return 1;\n  }\n  return n * fact(n - 1);\n}\n\nfunc main() {\n  long f;\n  f = fact(20);\n  print(f);\n  return int(f / fact(18));\n}";
+        let compiled = compile_to_ir(source).unwrap();
+        assert!(compiled.ir.starts_with("; line 1\n%func fact(%long n) %long\n"));
+        assert!(compiled.ir.contains("%long _temp2\n%sub _temp2, n, 1\n%long _temp3\n%call _temp3, fact(_temp2)\n%long _temp4\n%mult _temp4, n, _temp3\n"));
+        assert!(compiled.ir.contains("%int _temp8\n%narrow _temp8, _temp7\n%ret _temp8\n"));
+        let mut output = vec![];
+        assert!(interpreter::execute(&compiled.ir, io::empty(), &mut output) == Ok(380));
+        assert!(output == b"2432902008176640000\n");
+
+        // numbers fit either type, an expression of numbers is computed in longs where a long is expected.
+        let ir = compile_to_ir("func main() {\n  long a;\n  a = 0 - 5;\n  a = a * 3;\n  return int(a);\n}").unwrap().ir;
+        assert!(ir.contains("%long _temp1\n%sub _temp1, 0, 5\n%mov a, _temp1\n; line 4\n%long _temp2\n%mult _temp2, a, 3\n"));
+        assert!(interpreter::execute(&ir, io::empty(), io::sink()) == Ok(-15));
+        let compiled = compile_to_ir("func main() {\n  long x;\n  x = 2000000000 * 4;\n  print(x);\n  x = long(1) + 2000000000 * 2;\n  print(x);\n}").unwrap();
+        let mut output = vec![];
+        assert!(interpreter::execute(&compiled.ir, io::empty(), &mut output) == Ok(0));
+        assert!(output == b"8000000000\n4000000001\n");
+
+        // numbers beyond the ints are longs, and numbers beyond the longs are an error of the lexer.
+        let compiled = compile_to_ir("func main() {\n  long x;\n  x = 5000000000;\n  print(x);\n  x = 9223372036854775807;\n  print(x);\n}").unwrap();
+        let mut output = vec![];
+        assert!(interpreter::execute(&compiled.ir, io::empty(), &mut output) == Ok(0));
+        assert!(output == b"5000000000\n9223372036854775807\n");
+        let errors = compile_to_ir("func main() {\n  int x;\n  x = 5000000000;\n}").unwrap_err();
+        assert!(errors[0].message == "Type mismatch between the int 'x' and the long 5000000000 on line 3, convert one of them with int() or long().");
+        let errors = compile_to_ir("func main() {\n  long x;\n  x = 9223372036854775808;\n}").unwrap_err();
+        assert!(errors[0].code == codes::NUMBER_TOO_BIG && errors[0].span == Some(Span { start: 30, end: 49 }));
+        assert!(compile_to_ir("func main() {\n  int [3000000000] a;\n}").unwrap_err()[0].code == codes::INVALID_ARRAY_SIZE);
+
+        // ints and longs do not mix without a conversion.
+        let mixed = [
+            ("func main() {\n  long a;\n  int b;\n  b = a;\n}", "Type mismatch between the int 'b' and the long 'a' on line 4, convert one of them with int() or long()."),
+            ("func main() {\n  long a;\n  int b;\n  a = a + b;\n}", "Type mismatch between the long 'a' and the int 'b' on line 4, convert one of them with int() or long()."),
+            ("func main() {\n  long a;\n  int b;\n  if a < b {\n  }\n}", "Type mismatch between the long 'a' and the int 'b' on line 4, convert one of them with int() or long()."),
+            ("func f(long a) {\n  return a;\n}\nfunc main() {\n}", "Type mismatch. f returns an int, not the long 'a' on line 2, convert it with int()."),
+            ("func f(long a) {\n  return 0;\n}\nfunc main() {\n  int b;\n  b = f(b);\n}", "Type mismatch. Passed the int 'b' as parameter 1 of f, which is a long on line 6, convert it with long()."),
+            ("func main() long {\n  return 0;\n}", "Type mismatch. main returns the exit code of the program, which is an int, not a long."),
+        ];
+        for (source, message) in mixed {
+            let errors = compile_to_ir(source).unwrap_err();
+            assert!(errors[0].code == codes::TYPE_MISMATCH && errors[0].message == message, "{}", errors[0].message);
+        }
+        assert!(compile_to_ir("func main() {\n  long [4] a;\n}").unwrap_err()[0].code == codes::INVALID_STATEMENT);
+    }
+}
@@ -0,0 +1,49 @@
+// what the compiler prints besides the output of the program itself.
+// all of it goes to stderr, so stdout is left to the program. how much is printed is set with the level:
+//   Off     nothing, as for '--diagnostics=json'
+//   Error   the context of an error, such as the listing of invalid IR (the default)
+//   Info    what every stage did: the tokens, the generated IR, the exit code ('-v')
+//   Trace   the parser descending through the program as well ('-vv' or '--trace')
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Off,
+    Error,
+    Info,
+    Trace,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Error as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+// eprint!() at a level, as in log!(Level::Info, "Run successful. Exit code {}\n", n).
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::log::enabled($level) {
+            eprint!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod log_tests {
+    use crate::log::*;
+
+    #[test]
+    fn levels() {
+        assert!(Level::Off < Level::Error && Level::Info < Level::Trace);
+        set_level(Level::Info);
+        assert!(enabled(Level::Error) && enabled(Level::Info) && !enabled(Level::Trace));
+        set_level(Level::Error);
+    }
+}
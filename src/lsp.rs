@@ -0,0 +1,518 @@
+// the language server: 'rustcompiler-lsp', see src/bin/rustcompiler-lsp.rs.
+// it talks the language server protocol over stdin and stdout, so that editors can show for .tt files:
+//   - the errors and warnings of the compiler, when a file is opened or saved, also those in the files it imports
+//   - where a variable or function is declared, and everywhere it is used
+//   - the type of a name when hovering over it, and how many parameters a function takes
+//   - the functions of a file, for the outline of the editor
+//
+// every message is a JSON object after a 'Content-Length' header. the server keeps the text of every open file,
+// and works everything out from that text again for every request, as programs are small.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use crate::diagnostics::{codes, line_col, line_span, Diagnostic, Span};
+use crate::json::{self, Value};
+use crate::symbols::Symbols;
+use crate::warnings::{Warning, WarningKind};
+use crate::{imports, lex_with_spans};
+
+// error codes of the protocol.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// severities of a diagnostic, and the kind of symbol of a function.
+const ERROR: i64 = 1;
+const WARNING: i64 = 2;
+const FUNCTION: i64 = 12;
+
+// the longest message read, so that a bad Content-Length does not allocate the memory it asks for.
+const MAX_MESSAGE: usize = 64 << 20;
+
+#[derive(Default)]
+struct Server {
+    // the text of every open file, by URI.
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+// serves one client until it sends 'exit' or closes the input.
+// returns the exit code of the process: 0 if the client asked the server to shut down first.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> i32 {
+    let mut server = Server::default();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) | Err(_) => return 1,
+        };
+        let message = match json::parse(&message) {
+            Some(message) => message,
+            None => {
+                let _ = write_message(&mut output, &error_response(Value::Null, PARSE_ERROR, "The message is not valid JSON."));
+                continue;
+            }
+        };
+        let method = message.get("method").as_str().unwrap_or("");
+        if method == "exit" {
+            return if server.shutdown { 0 } else { 1 };
+        }
+        for reply in server.handle(method, &message) {
+            if write_message(&mut output, &reply).is_err() {
+                return 1;
+            }
+        }
+    }
+}
+
+// reads the body of the next message. None at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "a message without Content-Length"));
+    };
+    if length > MAX_MESSAGE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "a message longer than 64 MiB"));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn response(id: Value, result: Value) -> Value {
+    Value::object(vec![("jsonrpc", Value::str("2.0")), ("id", id), ("result", result)])
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    let error = Value::object(vec![("code", Value::Num(code)), ("message", Value::str(message))]);
+    Value::object(vec![("jsonrpc", Value::str("2.0")), ("id", id), ("error", error)])
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    Value::object(vec![("jsonrpc", Value::str("2.0")), ("method", Value::str(method)), ("params", params)])
+}
+
+impl Server {
+    // the messages to send back for one message from the client.
+    fn handle(&mut self, method: &str, message: &Value) -> Vec<Value> {
+        let params = message.get("params");
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("").to_string();
+        let id = message.get("id").clone();
+
+        let result = match method {
+            "initialize" => capabilities(),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/didOpen" => {
+                let text = params.get("textDocument").get("text").as_str().unwrap_or("");
+                self.documents.insert(uri.clone(), text.to_string());
+                return vec![self.diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                // the whole text is sent on every change, see capabilities().
+                let changes = params.get("contentChanges").as_array().unwrap_or(&[]);
+                if let Some(text) = changes.last().and_then(|change| change.get("text").as_str()) {
+                    self.documents.insert(uri, text.to_string());
+                }
+                return vec![];
+            }
+            "textDocument/didSave" => {
+                if let Some(text) = params.get("text").as_str() {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                return vec![self.diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                let params = Value::object(vec![("uri", Value::str(&uri)), ("diagnostics", Value::Array(vec![]))]);
+                return vec![notification("textDocument/publishDiagnostics", params)];
+            }
+            "textDocument/definition" => self.definition(&uri, params.get("position")),
+            "textDocument/references" => {
+                let declaration = params.get("context").get("includeDeclaration").as_bool().unwrap_or(true);
+                self.references(&uri, params.get("position"), declaration)
+            }
+            "textDocument/hover" => self.hover(&uri, params.get("position")),
+            "textDocument/documentSymbol" => self.document_symbols(&uri),
+            // other notifications, such as 'initialized', need no answer.
+            _ if id == Value::Null => return vec![],
+            _ => return vec![error_response(id, METHOD_NOT_FOUND, &format!("'{method}' is not supported."))],
+        };
+        if id == Value::Null {
+            return vec![];
+        }
+        vec![response(id, result)]
+    }
+
+    // the text of a file and the symbols in it.
+    fn document(&self, uri: &str) -> Option<(&str, Symbols)> {
+        let text = self.documents.get(uri)?;
+        let (tokens, spans) = lex_with_spans(text).ok()?;
+        Some((text, Symbols::new(&tokens, &spans)))
+    }
+
+    fn diagnostics(&self, uri: &str) -> Value {
+        let text = self.documents.get(uri).map(String::as_str).unwrap_or("");
+        let params = Value::object(vec![("uri", Value::str(uri)), ("diagnostics", Value::Array(diagnostics(uri, text)))]);
+        notification("textDocument/publishDiagnostics", params)
+    }
+
+    fn definition(&self, uri: &str, position: &Value) -> Value {
+        let Some((text, symbols)) = self.document(uri) else {
+            return Value::Null;
+        };
+        match symbols.at(offset(text, position)) {
+            Some(symbol) => location(uri, text, symbol.definition),
+            None => Value::Null,
+        }
+    }
+
+    fn references(&self, uri: &str, position: &Value, declaration: bool) -> Value {
+        let Some((text, symbols)) = self.document(uri) else {
+            return Value::Null;
+        };
+        let Some(symbol) = symbols.at(offset(text, position)) else {
+            return Value::Null;
+        };
+        let mut spans = symbol.references.clone();
+        if declaration {
+            spans.insert(0, symbol.definition);
+        }
+        Value::Array(spans.into_iter().map(|span| location(uri, text, span)).collect())
+    }
+
+    fn hover(&self, uri: &str, position: &Value) -> Value {
+        let Some((text, symbols)) = self.document(uri) else {
+            return Value::Null;
+        };
+        let offset = offset(text, position);
+        match symbols.at(offset) {
+            Some(symbol) => {
+                let contents = Value::object(vec![("kind", Value::str("plaintext")), ("value", Value::Str(symbol.describe()))]);
+                Value::object(vec![("contents", contents)])
+            }
+            None => Value::Null,
+        }
+    }
+
+    fn document_symbols(&self, uri: &str) -> Value {
+        let Some((text, symbols)) = self.document(uri) else {
+            return Value::Array(vec![]);
+        };
+        let functions = symbols.functions().map(|function| {
+            Value::object(vec![
+                ("name", Value::str(&function.name)),
+                ("detail", Value::Str(function.describe())),
+                ("kind", Value::Num(FUNCTION)),
+                ("range", range(text, function.extent)),
+                ("selectionRange", range(text, function.definition)),
+            ])
+        });
+        Value::Array(functions.collect())
+    }
+}
+
+fn capabilities() -> Value {
+    let sync = Value::object(vec![
+        ("openClose", Value::Bool(true)),
+        // the whole text on every change.
+        ("change", Value::Num(1)),
+        ("save", Value::object(vec![("includeText", Value::Bool(true))])),
+    ]);
+    let capabilities = Value::object(vec![
+        ("textDocumentSync", sync),
+        ("definitionProvider", Value::Bool(true)),
+        ("referencesProvider", Value::Bool(true)),
+        ("hoverProvider", Value::Bool(true)),
+        ("documentSymbolProvider", Value::Bool(true)),
+    ]);
+    let info = Value::object(vec![("name", Value::str("rustcompiler-lsp")), ("version", Value::str(env!("CARGO_PKG_VERSION")))]);
+    Value::object(vec![("capabilities", capabilities), ("serverInfo", info)])
+}
+
+// the errors of a program, or its warnings if it has no errors, as LSP diagnostics.
+// an error in an imported file is put at the start of this file, and names the other file.
+// the warnings of an imported file are left for when that file is open.
+fn diagnostics(uri: &str, text: &str) -> Vec<Value> {
+    let path = path(uri);
+    let symbols = lex_with_spans(text).map(|(tokens, spans)| Symbols::new(&tokens, &spans)).unwrap_or_default();
+    let diagnostic = |code: &str, severity: i64, message: &str, span: Option<Span>| {
+        Value::object(vec![
+            ("range", range(text, span.unwrap_or(Span { start: 0, end: 0 }))),
+            ("severity", Value::Num(severity)),
+            ("code", Value::str(code)),
+            ("source", Value::str("rustcompiler")),
+            ("message", Value::str(message)),
+        ])
+    };
+    let this_file = path.as_ref().map(|path| path.display().to_string());
+    match imports::compile(path.as_deref(), text) {
+        Ok(compiled) => compiled
+            .warnings
+            .iter()
+            .filter(|warning| warning.file.is_none() || warning.file == this_file)
+            .map(|warning| diagnostic(warning.kind.code(), WARNING, &warning.message, warning_span(warning, text, &symbols)))
+            .collect(),
+        Err(errors) => errors
+            .iter()
+            .map(|error| match &error.file {
+                Some(file) if Some(file) != this_file.as_ref() => diagnostic(error.code, ERROR, &format!("in {file}: {}", error.message), None),
+                _ => diagnostic(error.code, ERROR, &error.message, error_span(error, text, &symbols)),
+            })
+            .collect(),
+    }
+}
+
+// the path of a 'file://' URI, which imports are relative to.
+fn path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // characters such as spaces are escaped as '%20'.
+    let mut bytes = vec![];
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail.get(..2).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(escaped) if byte == b'%' => {
+                bytes.push(escaped);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+// the semantic checks run on the IR, which only has the lines of the source, so their errors are placed with the symbols:
+// on the first undeclared name the message mentions, the second declaration of a name, or a name the message mentions
+// on the line of the error. otherwise an error covers its line.
+// a function of an imported file is undeclared as far as the symbols know, hence the name has to be in the message.
+fn error_span(error: &Diagnostic, text: &str, symbols: &Symbols) -> Option<Span> {
+    if error.span.is_some() {
+        return error.span;
+    }
+    let mentioned = |name: &str| error.message.split(|c: char| !c.is_alphanumeric() && c != '_').any(|word| word == name);
+    let found = match error.code {
+        codes::UNDECLARED_VARIABLE | codes::UNDEFINED_FUNCTION => symbols.undeclared().find(|span| mentioned(&text[span.start..span.end])),
+        codes::DUPLICATE_FUNCTION | codes::DUPLICATE_VARIABLE | codes::DUPLICATE_PARAMETER => symbols.duplicates.first().copied(),
+        _ => None,
+    };
+    let on_line = |span: &Span| error.line.is_none_or(|line| line_col(text, span.start).0 == line);
+    let spans = symbols.symbols.iter().filter(|symbol| mentioned(&symbol.name));
+    let mentioned_on_line = || spans.flat_map(|symbol| std::iter::once(symbol.definition).chain(symbol.references.iter().copied())).find(on_line);
+    found.or_else(mentioned_on_line).or_else(|| line_span(text, error.line?))
+}
+
+// a warning is about a name on its line: an uninitialized read is where it is used, the others are where it is declared.
+// an unreachable statement is not a name, and covers its line.
+fn warning_span(warning: &Warning, text: &str, symbols: &Symbols) -> Option<Span> {
+    let on_line = |span: &Span| warning.line.is_none_or(|line| line_col(text, span.start).0 == line);
+    let found = symbols.symbols.iter().filter(|symbol| symbol.name == warning.symbol).find_map(|symbol| match warning.kind {
+        WarningKind::UninitializedRead => symbol.references.iter().copied().find(on_line),
+        WarningKind::UnreachableCode => None,
+        _ => Some(symbol.definition).filter(on_line),
+    });
+    found.or_else(|| line_span(text, warning.line?))
+}
+
+fn location(uri: &str, text: &str, span: Span) -> Value {
+    Value::object(vec![("uri", Value::str(uri)), ("range", range(text, span))])
+}
+
+fn range(text: &str, span: Span) -> Value {
+    Value::object(vec![("start", position(text, span.start)), ("end", position(text, span.end))])
+}
+
+// positions in the protocol are a line, and a character counted in UTF-16 code units, both from 0.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    Value::object(vec![("line", Value::Num(before.matches('\n').count() as i64)), ("character", Value::Num(character as i64))])
+}
+
+// the byte offset of a position.
+fn offset(text: &str, position: &Value) -> usize {
+    let line = position.get("line").as_num().unwrap_or(0).max(0) as usize;
+    let character = position.get("character").as_num().unwrap_or(0).max(0) as usize;
+    let line_start: usize = text.split_inclusive('\n').take(line).map(str::len).sum();
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod lsp_tests {
+    use crate::json::{self, Value};
+    use crate::lsp::*;
+
+    const SOURCE: &str = "func add(int a, int b) {\n    return a + b;\n}\n\nfunc main() {\n    int x;\n    x = add(1, 2);\n    print(x);\n}\n";
+
+    fn message(value: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", value.len(), value)
+    }
+
+    // runs a session and returns what the server sent back.
+    fn session(messages: &[String]) -> (i32, Vec<Value>) {
+        let input = messages.concat();
+        let mut output = vec![];
+        let code = serve(input.as_bytes(), &mut output);
+        let mut replies = vec![];
+        let mut output = output.as_slice();
+        while let Ok(Some(reply)) = read_message(&mut output) {
+            replies.push(json::parse(&reply).unwrap());
+        }
+        (code, replies)
+    }
+
+    fn request(id: i64, method: &str, params: &str) -> String {
+        message(&format!(r#"{{"jsonrpc":"2.0","id":{id},"method":"{method}","params":{params}}}"#))
+    }
+
+    fn at(line: i64, character: i64) -> String {
+        format!(r#"{{"textDocument":{{"uri":"file:///a.tt"}},"position":{{"line":{line},"character":{character}}},"context":{{"includeDeclaration":true}}}}"#)
+    }
+
+    #[test]
+    fn requests() {
+        let open = format!(r#"{{"textDocument":{{"uri":"file:///a.tt","text":{}}}}}"#, Value::str(SOURCE));
+        let (code, replies) = session(&[
+            request(1, "initialize", "{}"),
+            message(r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#),
+            message(&format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{open}}}"#)),
+            // the 'add' in main, the 'x' in print(x), and the 'a' in add.
+            request(2, "textDocument/definition", &at(6, 9)),
+            request(3, "textDocument/references", &at(7, 10)),
+            request(4, "textDocument/hover", &at(1, 11)),
+            request(5, "textDocument/hover", &at(6, 9)),
+            request(6, "textDocument/documentSymbol", r#"{"textDocument":{"uri":"file:///a.tt"}}"#),
+            request(7, "textDocument/formatting", "{}"),
+            request(8, "shutdown", "null"),
+            message(r#"{"jsonrpc":"2.0","method":"exit"}"#),
+        ]);
+        assert!(code == 0);
+        assert!(replies.len() == 9);
+        assert!(replies[0].get("result").get("capabilities").get("hoverProvider").as_bool() == Some(true));
+        // a program without errors or warnings.
+        assert!(replies[1].get("params").get("diagnostics").as_array() == Some(&[]));
+
+        let definition = replies[2].get("result").get("range").get("start");
+        assert!(definition.get("line").as_num() == Some(0) && definition.get("character").as_num() == Some(5));
+        let references = replies[3].get("result").as_array().unwrap();
+        let lines: Vec<i64> = references.iter().map(|r| r.get("range").get("start").get("line").as_num().unwrap()).collect();
+        assert!(lines == [5, 6, 7]);
+        assert!(replies[4].get("result").get("contents").get("value").as_str() == Some("a: Int, parameter of add"));
+        assert!(replies[5].get("result").get("contents").get("value").as_str() == Some("add: Function, 2 parameters"));
+
+        let symbols = replies[6].get("result").as_array().unwrap();
+        assert!(symbols.iter().map(|s| s.get("name").as_str().unwrap()).eq(["add", "main"]));
+        assert!(symbols[1].get("range").get("end").get("line").as_num() == Some(8));
+        assert!(replies[7].get("error").get("code").as_num() == Some(METHOD_NOT_FOUND));
+        assert!(replies[8].get("result") == &Value::Null && replies[8].get("id").as_num() == Some(8));
+    }
+
+    #[test]
+    fn bad_messages() {
+        // a length nobody could send ends the session instead of being allocated.
+        let (code, replies) = session(&[String::from("Content-Length: 99999999999999\r\n\r\n{}")]);
+        assert!(code == 1 && replies.is_empty());
+        // JSON nested too deep for the parser is a parse error, and the server reads on.
+        let (_, replies) = session(&[message(&"[".repeat(100_000)), request(1, "shutdown", "null")]);
+        assert!(replies[0].get("error").get("code").as_num() == Some(PARSE_ERROR) && replies[1].get("id").as_num() == Some(1));
+    }
+
+    #[test]
+    fn diagnostics_on_save() {
+        let save = |text: &str| {
+            let params = format!(r#"{{"textDocument":{{"uri":"file:///a.tt"}},"text":{}}}"#, Value::str(text));
+            message(&format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didSave","params":{params}}}"#))
+        };
+        let (code, replies) = session(&[
+            save("func main() {\n    int x;\n    y = 1;\n}\n"),
+            save("func main() {\n    int x;\n    x = 1 +;\n}\n"),
+            save("func main() {\n    int x;\n}\n"),
+            save("func main() {\n    long x;\n    x = 99999999999999999999;\n}\n"),
+            save("func main() {\n    return 0;\n    print(1);\n}\n"),
+        ]);
+        // the input ended without 'shutdown' and 'exit'.
+        assert!(code == 1);
+        let first = |reply: &Value| reply.get("params").get("diagnostics").as_array().unwrap()[0].clone();
+
+        // a semantic error has no position of its own, and is put on the undeclared name.
+        let undeclared = first(&replies[0]);
+        assert!(undeclared.get("code").as_str() == Some(codes::UNDECLARED_VARIABLE));
+        assert!(undeclared.get("range").get("start") == &Value::object(vec![("line", Value::Num(2)), ("character", Value::Num(4))]));
+        assert!(first(&replies[1]).get("severity").as_num() == Some(ERROR));
+
+        let unused = first(&replies[2]);
+        assert!(unused.get("severity").as_num() == Some(WARNING) && unused.get("code").as_str() == Some(codes::UNUSED_VARIABLE));
+        assert!(unused.get("range").get("start").get("line").as_num() == Some(1));
+
+        // a number too big for a long is an error of the lexer, on the number.
+        let too_big = first(&replies[3]);
+        assert!(too_big.get("code").as_str() == Some(codes::NUMBER_TOO_BIG));
+        assert!(too_big.get("range").get("end") == &Value::object(vec![("line", Value::Num(2)), ("character", Value::Num(28))]));
+
+        // unreachable code is no name, and covers the line it is on.
+        let unreachable = first(&replies[4]);
+        assert!(unreachable.get("code").as_str() == Some(codes::UNREACHABLE_CODE));
+        assert!(unreachable.get("range").get("start") == &Value::object(vec![("line", Value::Num(2)), ("character", Value::Num(4))]));
+    }
+
+    #[test]
+    fn imported_files() {
+        let example = format!("file://{}/examples_imports/main.tt", env!("CARGO_MANIFEST_DIR").replace(' ', "%20"));
+        let text = std::fs::read_to_string(path(&example).unwrap()).unwrap();
+        assert!(diagnostics(&example, &text).is_empty());
+        // a file that is not saved anywhere has its imports looked up in the current directory.
+        let not_found = &diagnostics("untitled:1", &text)[0];
+        assert!(not_found.get("code").as_str() == Some(codes::IMPORT_NOT_FOUND));
+        assert!(path("file:///a%20b/c.tt") == Some(PathBuf::from("/a b/c.tt")) && path("untitled:1").is_none());
+
+        // the warnings of an imported file are not put on a name of the same spelling in this one.
+        let directory = std::env::temp_dir().join(format!("rustcompiler-lsp-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("lib.tt"), "func helper() {\n    int x;\n    return 1;\n}\n").unwrap();
+        let text = "import \"lib.tt\";\nfunc main() {\n    int x;\n    x = helper();\n    print(x);\n}\n";
+        assert!(diagnostics(&format!("file://{}/main.tt", directory.display()), text).is_empty());
+    }
+
+    #[test]
+    fn positions() {
+        let text = "ab\n\u{e9}\u{1f600}x\n";
+        let at = |line, character| Value::object(vec![("line", Value::Num(line)), ("character", Value::Num(character))]);
+        // 'x' is after one UTF-16 unit for the e and two for the emoji.
+        assert!(position(text, 9) == at(1, 3) && offset(text, &at(1, 3)) == 9);
+        assert!(offset(text, &at(0, 10)) == 2 && offset(text, &at(5, 0)) == text.len());
+    }
+}
@@ -1,1615 +1,796 @@
-// The Rust Programming Language: A Crash Course and Building Our First Lexer
-// CS152 Compiler Design using the Rust Programming Language.
-// A Handwritten Compiler Using Rust.
-// Creating a Lexer By Hand.
+// the commandline tool. the compiler itself lives in lib.rs.
+//
+//   rustcompiler [options] <file>          lex, parse, check and run a program
+//   rustcompiler lex [options] <file>      print the tokens
+//   rustcompiler parse [options] <file>    print the parser trace
+//   rustcompiler check [options] <file>    only report errors and warnings
+//   rustcompiler emit-ir [options] <file>  print the generated IR
+//   rustcompiler run [options] <file>      run the program, the same as leaving out the command
+//   rustcompiler run-ir [options] <file>   run a file of IR, such as one written with 'emit-ir' or by hand
+//   rustcompiler debug [options] <file>    run the program under the debugger, see debugger.rs
+//   rustcompiler repl                      enter statements and functions interactively, see repl.rs
+//   rustcompiler fmt [--check] <file>      print the program in the layout of format.rs.
+//                                          '--check' prints nothing, and exits with 1 if the file is not formatted
+//
+// options:
+//   --emit tokens,ast,ir     what to output instead of the command's usual output.
+//                            every kind can be given its own file, as in '--emit tokens=a.tokens,ir=a.ir'
+//   -o <path>                write the output to a file instead of stdout
+//   -A, --allow <warning>    silence a warning, see warnings.rs
+//   --diagnostics=json       print every error and warning as a JSON object
+//   -v, --verbose            log what every stage did. '-vv' or '--trace' logs the parser trace as well
+//   --explain <code>         explain an error code, such as 'E0101'
+//   --overflow <policy>      what arithmetic that does not fit in an int does: wrap (the default), trap or saturate
+//   --input <path>           what the program reads with '%input', instead of stdin
+//   --max-call-depth <n>     how many calls can be running at once before the program is stopped, 10000 by default
+//   --max-instructions <n>   stop the program after it ran n instructions of the IR
+//   --max-memory <bytes>     stop the program when the arrays of the calls running take more bytes, 4 for every element
+//   --max-output <bytes>     stop the program when it prints more bytes
+//   --trace-exec[=f,g]       log every instruction that runs to stderr, or only those of the functions f and g
+//   --trace-steps <n>        stop the log of --trace-exec after n instructions
+//   --profile                count the instructions the program runs, and print where they went to stderr, see profile.rs
+//   --profile-stacks <path>  with --profile, write the counts of every chain of calls for flame graph tools
+//
+// stdout only gets the output of the program, or what was asked for with --emit.
+// errors, warnings and the log go to stderr.
+// the exit code is the return value of main when the program ran, see 'mod exit' for the others.
+// a program can import other files, see imports.rs. errors in them name the file.
+// the language server for editors is a binary of its own, 'rustcompiler-lsp', see lsp.rs.
 
 // used to get the commandline arguments from the commandline.
 use std::env;
 // used to interact with the file system
 use std::fs;
-// for our symbol table
-use std::collections::HashMap;
-
-mod interpreter;
-
-struct Expression {
-  code: String,
-  name: String,
+use std::io;
+use std::path::Path;
+use std::process::ExitCode;
+
+use rustcompiler::log;
+use rustcompiler::diagnostics::{self, codes, Diagnostic};
+use rustcompiler::json::Value;
+use rustcompiler::log::Level;
+use rustcompiler::profile::Profile;
+use rustcompiler::{catalog, interpreter, warnings, Token};
+
+// the stages of compiling a program, in order. a command runs every stage up to its own.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Stage {
+    Lex,
+    Parse,
+    Check,
+    Run,
 }
 
-static mut WHILE_LABEL_STACK: Vec<String> = vec![];
-static mut VAR_NUM: i64 = 0;
-fn create_temp() -> String {
-  unsafe {
-      VAR_NUM += 1;
-      format!("_temp{}", VAR_NUM)
-  }
+// what '--emit' can write out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Emit {
+    Tokens,
+    // the parser generates IR as it goes without building a tree, so this is the parser trace.
+    Ast,
+    Ir,
 }
 
-static mut whileloopbegin_num: i64 = 0;
-fn create_whileloopbegin_label() -> String {
-  unsafe {
-    whileloopbegin_num += 1;
-      format!("loopbegin{}", whileloopbegin_num)
-  }
-}
+impl Emit {
+    fn from_name(name: &str) -> Option<Emit> {
+        match name {
+            "tokens" => Some(Emit::Tokens),
+            "ast" => Some(Emit::Ast),
+            "ir" => Some(Emit::Ir),
+            _ => None,
+        }
+    }
 
-static mut whileloopend_num: i64 = 0;
-fn create_whileloopend_label() -> String {
-  unsafe {
-    whileloopend_num += 1;
-      format!("endloop_{}", whileloopend_num)
-  }
-}
+    fn name(&self) -> &'static str {
+        match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::Ir => "ir",
+        }
+    }
 
-static mut iftrue_num: i64 = 0;
-fn create_iftrue_label() -> String {
-  unsafe {
-    iftrue_num += 1;
-      format!("iftrue{}", iftrue_num)
-  }
+    // the stage that produces it.
+    fn stage(&self) -> Stage {
+        match self {
+            Emit::Tokens => Stage::Lex,
+            Emit::Ast | Emit::Ir => Stage::Parse,
+        }
+    }
 }
 
-static mut endif_num: i64 = 0;
-fn create_endif_label() -> String {
-  unsafe {
-    endif_num += 1;
-      format!("endif{}", endif_num)
-  }
+#[derive(Debug, PartialEq)]
+enum Mode {
+    // compile the program in the file, running every stage up to options.stage.
+    Compile,
+    // the file is IR to run.
+    RunIr,
+    // compile the program, then run it under the debugger.
+    Debug,
+    Repl,
+    // format the file, or with check, only tell whether it is formatted.
+    Fmt { check: bool },
 }
 
-static mut else_num: i64 = 0;
-fn create_else_label() -> String {
-  unsafe {
-    else_num += 1;
-      format!("else{}", else_num)
-  }
-}
-enum DataType {
-  Array,
-  Int,
-  Function,
+struct Options {
+    mode: Mode,
+    filename: String,
+    stage: Stage,
+    // what to write out, and the file for it. None is stdout.
+    emit: Vec<(Emit, Option<String>)>,
+    // where 'fmt' writes the program. None is stdout.
+    output: Option<String>,
+    allowed: warnings::Allowed,
+    json: bool,
+    log_level: Level,
+    limits: interpreter::Limits,
+    trace: Option<interpreter::ExecTrace>,
+    profile: bool,
+    // where --profile-stacks writes the collapsed stacks.
+    profile_stacks: Option<String>,
+    // the file the program reads instead of stdin.
+    input: Option<String>,
 }
 
-impl std::fmt::Display for DataType {
-  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-      match self {
-        DataType::Array => write!(f, "Int[]"),
-        DataType::Int => write!(f, "Int"),
-        DataType::Function => write!(f, "Function"),
-      }
-  }
+// exit codes. a program that ran exits with what its main function returned,
+// the other codes tell which stage failed.
+mod exit {
+    use rustcompiler::diagnostics::{codes, Diagnostic};
+
+    pub const SUCCESS: u8 = 0;
+    // 'fmt --check' found a file that is not formatted.
+    pub const UNFORMATTED: u8 = 1;
+    // bad commandline arguments.
+    pub const USAGE: u8 = 2;
+    // a file could not be read or written.
+    pub const IO: u8 = 3;
+    pub const LEX: u8 = 10;
+    pub const PARSE: u8 = 11;
+    // the semantic checks and the control flow analysis.
+    pub const SEMANTIC: u8 = 12;
+    // the generated IR did not load.
+    pub const IR: u8 = 13;
+    pub const RUNTIME: u8 = 14;
+    // an import was not found, or a file ends up importing itself.
+    pub const IMPORT: u8 = 15;
+
+    pub fn failure(error: &Diagnostic) -> u8 {
+        match error.code {
+            codes::CANNOT_READ_FILE | codes::CANNOT_WRITE_FILE => IO,
+            codes::IMPORT_NOT_FOUND | codes::IMPORT_CYCLE => IMPORT,
+            code if code.starts_with("E00") => LEX,
+            code if code.starts_with("E01") => PARSE,
+            code if code.starts_with("E02") => SEMANTIC,
+            code if code.starts_with("E03") => IR,
+            _ => RUNTIME,
+        }
+    }
+
+    // processes only exit with 0 to 255, so main's return value is cut to its lowest byte like a shell does:
+    // return -1 exits with 255, return 256 with 0.
+    pub fn program(return_value: i32) -> u8 {
+        (return_value & 0xff) as u8
+    }
 }
 
-fn semantics_check(generated_code: String) -> bool {
-  // each symbol name will map to a datatype
-  // data types will be array, int, or function
-
-  let mut symbol_table: HashMap<String,DataType> = HashMap::new();
-  // we only add from %func, %int, and %int instructions
-
-  //let mut cleaned_code = generated_code.replace(",", "");
-  //cleaned_code = cleaned_code.replace("[", "");
-  //cleaned_code = cleaned_code.replace("]", "");
-
-  let mut main_function_seen = false;
-  let mut scope_name = "";
-  let mut line2;
-
-  for gline in generated_code.lines() {
-    //println!("gline: {}", gline);
-    let line = gline.replace(",", " ");
-    //stuff that'll add to symbol table
-    if line.starts_with("%func"){
-      line2 = line.replace(")", " )");
-      line2 = line2.replace("(", " ");
-      //println!("line {}",line);
-      let func_name = line2.split_whitespace().nth(1).unwrap();
-      //println!("func_name: {}", func_name);
-      if func_name == "main"{
-        main_function_seen = true;
-      }
-      scope_name = func_name;
-      //println!("scope_name: {}", scope_name);
-      let key_name = func_name.to_string()+"|main";
-      if(symbol_table.contains_key(&key_name)){
-        println!("Error: Function {func_name} already defined.");
-        return false;
-      }
-      symbol_table.insert(key_name, DataType::Function);
-      // add function parameters to symbol table
-      let mut params = line2.split_whitespace().skip(2);
-      for param in params{
-        if param == ")"{
-          break;
+fn main() -> ExitCode {
+    // Let us get commandline arguments and store them in a Vec<String>
+    let args: Vec<String> = env::args().collect();
+    let options = match parse_args(&args[1..]) {
+        Ok(Some(options)) => options,
+        Ok(None) => return ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(exit::USAGE);
         }
-        if(param == "%int"){
-          continue;
+    };
+    log::set_level(options.log_level);
+    match options.mode {
+        Mode::Compile | Mode::Debug => ExitCode::from(compile(&options)),
+        Mode::RunIr => ExitCode::from(run_ir(&options)),
+        Mode::Repl => {
+            rustcompiler::repl::run();
+            ExitCode::SUCCESS
         }
-        let key_name = param.to_string()+"|"+scope_name;
-        //println!("param {key_name}");
-        if(symbol_table.contains_key(&key_name)){
-          println!("Error: Duplicate parameter {param} declared in {scope_name}.");
-          return false;
+        Mode::Fmt { check } => ExitCode::from(fmt(&options, check)),
+    }
+}
+
+// returns None when there is nothing left to do, as after '--explain'.
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let no_file = "Please provide an input file through the commandline arguments for the lexer.";
+    if args.is_empty() {
+        return Err(String::from(no_file));
+    }
+
+    let (stage, default_emit, mut arg_index) = match args[0].as_str() {
+        "lex" => (Some(Stage::Lex), Some(Emit::Tokens), 1),
+        "parse" => (Some(Stage::Parse), Some(Emit::Ast), 1),
+        "check" => (Some(Stage::Check), None, 1),
+        "emit-ir" => (Some(Stage::Parse), Some(Emit::Ir), 1),
+        "run" | "run-ir" | "debug" | "repl" => (Some(Stage::Run), None, 1),
+        "fmt" => (Some(Stage::Parse), None, 1),
+        _ => (None, None, 0),
+    };
+
+    let mut allowed = warnings::Allowed::default();
+    let mut json = false;
+    let mut verbosity = 0;
+    let mut filename: Option<&String> = None;
+    let mut emit: Vec<(Emit, Option<String>)> = vec![];
+    let mut output: Option<&String> = None;
+    let mut check = false;
+    let mut limits = interpreter::Limits::default();
+    let mut trace: Option<interpreter::ExecTrace> = None;
+    let mut profile = false;
+    let mut profile_stacks: Option<&String> = None;
+    let mut input: Option<&String> = None;
+    while arg_index < args.len() {
+        let arg = &args[arg_index];
+        if arg == "--explain" {
+            // print the catalog entry of an error or warning code, such as 'E0101'.
+            match args.get(arg_index + 1).and_then(|code| catalog::lookup(code)) {
+                Some(entry) => print!("{}", catalog::explain(entry)),
+                None => return Err(String::from("Unknown error code. Codes look like 'E0101' or 'W0002'.")),
+            }
+            return Ok(None);
+        } else if arg == "-A" || arg == "--allow" {
+            arg_index += 1;
+            let spec = args.get(arg_index).ok_or(format!("Expected a warning name after {arg}."))?;
+            allowed.allow(spec)?;
+        } else if let Some(format) = arg.strip_prefix("--diagnostics=") {
+            match format {
+                "json" => json = true,
+                "human" => json = false,
+                _ => return Err(format!("Unknown diagnostics format '{format}'. Expected 'human' or 'json'.")),
+            }
+        } else if arg == "-v" || arg == "--verbose" {
+            verbosity += 1;
+        } else if arg == "-vv" || arg == "--trace" {
+            verbosity += 2;
+        } else if arg == "--emit" || arg.starts_with("--emit=") {
+            let kinds = match arg.strip_prefix("--emit=") {
+                Some(kinds) => kinds,
+                None => {
+                    arg_index += 1;
+                    args.get(arg_index).ok_or("Expected tokens, ast or ir after --emit.")?
+                }
+            };
+            for kind in kinds.split(',') {
+                let (name, path) = match kind.split_once('=') {
+                    Some((name, path)) => (name, Some(path.to_string())),
+                    None => (kind, None),
+                };
+                let kind = Emit::from_name(name).ok_or(format!("Unknown --emit kind '{name}'. Expected tokens, ast or ir."))?;
+                emit.push((kind, path));
+            }
+        } else if arg == "-o" {
+            arg_index += 1;
+            output = Some(args.get(arg_index).ok_or("Expected a path after -o.")?);
+        } else if arg == "--max-call-depth" {
+            arg_index += 1;
+            limits.call_depth = positive(args, arg_index, arg)?;
+        } else if arg == "--max-instructions" {
+            arg_index += 1;
+            limits.instructions = Some(positive(args, arg_index, arg)?);
+        } else if arg == "--max-memory" {
+            arg_index += 1;
+            limits.array_memory = Some(positive(args, arg_index, arg)?);
+        } else if arg == "--max-output" {
+            arg_index += 1;
+            limits.output = Some(positive(args, arg_index, arg)?);
+        } else if arg == "--trace-exec" || arg.starts_with("--trace-exec=") {
+            let trace = trace.get_or_insert_with(Default::default);
+            if let Some(functions) = arg.strip_prefix("--trace-exec=") {
+                trace.functions.extend(functions.split(',').filter(|name| !name.is_empty()).map(String::from));
+            }
+        } else if arg == "--trace-steps" {
+            arg_index += 1;
+            // a number of steps is only of use with the trace, so it turns the trace on.
+            trace.get_or_insert_with(Default::default).max_steps = Some(positive(args, arg_index, arg)?);
+        } else if arg == "--profile" {
+            profile = true;
+        } else if arg == "--profile-stacks" {
+            arg_index += 1;
+            profile_stacks = Some(args.get(arg_index).ok_or("Expected a path after --profile-stacks.")?);
+        } else if arg == "--overflow" {
+            arg_index += 1;
+            let policy = args.get(arg_index).ok_or("Expected wrap, trap or saturate after --overflow.")?;
+            limits.overflow = interpreter::Overflow::from_name(policy).ok_or(format!("Unknown --overflow policy '{policy}'. Expected wrap, trap or saturate."))?;
+        } else if arg == "--input" {
+            arg_index += 1;
+            input = Some(args.get(arg_index).ok_or("Expected a path after --input.")?);
+        } else if arg == "--check" && args[0] == "fmt" {
+            check = true;
+        } else if filename.is_none() {
+            filename = Some(arg);
+        } else {
+            return Err(String::from("Too many commandline arguments."));
         }
-        symbol_table.insert(key_name, DataType::Int);
-      }
-      continue;
+        arg_index += 1;
     }
-    if line.starts_with("%int[]"){
-      let var_name = line.split_whitespace().nth(1).unwrap();
-      let array_size = line.split_whitespace().nth(2).unwrap();
-      //println!("array_name: {}", var_name);
-      if(array_size.parse::<i32>().unwrap() <= 0){
-        println!("Error: Array size of {var_name} must be greater than 0.");
-        return false;
-      }
-      let key_name = var_name.to_string()+"|"+scope_name;
-      //println!("{} is array", key_name);
-      if(symbol_table.contains_key(&key_name)){
-        println!("Error: Variable {key_name} already declared.");
-        return false;
-      }
-      symbol_table.insert(key_name, DataType::Array);
-      continue;
+
+    let mode = match args[0].as_str() {
+        "run-ir" => Mode::RunIr,
+        "debug" => Mode::Debug,
+        "repl" => Mode::Repl,
+        "fmt" => Mode::Fmt { check },
+        _ => Mode::Compile,
+    };
+    if mode == Mode::Repl && (filename.is_some() || !emit.is_empty() || output.is_some()) {
+        return Err(String::from("'repl' takes no file, and has nothing to emit."));
     }
-    if line.starts_with("%int"){
-      let var_name = line.split_whitespace().nth(1).unwrap();
-      //println!("var_name: {}", var_name);
-      let key_name = var_name.to_string()+"|"+scope_name;
-      if(symbol_table.contains_key(&key_name)){
-        println!("Error: Variable {var_name} already declared.");
-        return false;
-      }
-      symbol_table.insert(var_name.to_string()+"|"+scope_name, DataType::Int);
-      continue;
+    let empty = String::new();
+    let filename = if mode == Mode::Repl { &empty } else { filename.ok_or(no_file)? };
+    if emit.is_empty() {
+        emit.extend(default_emit.map(|kind| (kind, None)));
     }
-    // semantics check the line
-    let mut clean_line = line.replace("[", "");
-    clean_line = clean_line.replace("]", "");
-    clean_line = clean_line.replace("(", "( ");
-    clean_line = clean_line.replace(")", "");
-    let mut seen_array_type = false;
-    let mut seen_tokens: Vec<&str> = vec![];
-    for param in clean_line.split_whitespace(){
-      if param.starts_with("%") || param.starts_with(":"){
-        continue;
-      }
-        // not a number, not a +, and not in symbol table.. undeclared or undefined
-        //println!("param: {}", param);
-        let key_name = param.replace("(","")+"|"+scope_name;
-        //println!("key_name: {}", key_name);
-        if !symbol_table.contains_key(&(key_name)) && !param.parse::<i32>().is_ok() && !param.starts_with("+"){
-          if(param.ends_with("(")){
-            println!("Error: Undefined function used: {}", param.replace("(", ""));
-          }else{
-            println!("Error: Undeclared variable used: {}", param);
-          }
-          return false;
-        }
-        // param is a +, check if last token is an array
-        if param == "+" {
-          let last_token = *seen_tokens.last().unwrap();
-          let data_type = symbol_table.get(&(last_token.to_string() + "|" + scope_name)).unwrap();
-          if(!matches!(data_type, &DataType::Array)){
-            println!("Error: Type mismatch. '{last_token}' is not an array.");
-            //println!("Error: Attempt to use {data_type} {last_token} like array.");
-            return false;
-          }else{
-            seen_array_type = false;
-          }
+    if let Mode::Fmt { check } = mode {
+        if !emit.is_empty() {
+            return Err(String::from("'fmt' has nothing to emit, it prints the formatted program."));
         }
-        // if seenarraytype is true and we passed +, then we are trying to use an array like a number
-        if(seen_array_type){
-          let last_token = *seen_tokens.last().unwrap();
-          println!("Error: Type mismatch. Used '{last_token}' as an integer.");
-          //println!("Error: Attempt to use array {last_token} like number.");
-          return false;
+        if check && output.is_some() {
+            return Err(String::from("'fmt --check' writes nothing to -o."));
         }
-        // set seenArrayType flag
-        if symbol_table.contains_key(&(param.to_string() + "|" + scope_name)){
-          let data_type = symbol_table.get(&(param.to_string() + "|" + scope_name)).unwrap();
-          if(matches!(data_type, &DataType::Array)){
-            seen_array_type = true;
-            //println!("set array flag {}", seen_array_type);
-          }
+    } else if let Some(output) = output {
+        match emit.as_mut_slice() {
+            [(_, path)] => *path = Some(output.clone()),
+            [] => return Err(String::from("Nothing to write to -o. Pick what to write with --emit.")),
+            _ => return Err(String::from("-o takes a single --emit kind. Use --emit kind=path to write several.")),
         }
-        seen_tokens.push(param);
     }
-  }
-  if(main_function_seen == false){
-    println!("Error: Main function not defined.");
-    return false;
-  }
-  return true;
+    if mode == Mode::RunIr && !emit.is_empty() {
+        return Err(String::from("'run-ir' has nothing to emit, the IR is the input."));
+    }
+    if mode == Mode::Debug && input.is_some() {
+        return Err(String::from("'debug' runs the program on the terminal, it takes no --input."));
+    }
+    if profile_stacks.is_some() && !profile {
+        return Err(String::from("--profile-stacks writes what --profile counts. Add --profile."));
+    }
+    let stage = stage.unwrap_or(Stage::Run);
+    if let Some((kind, _)) = emit.iter().find(|(kind, _)| kind.stage() > stage) {
+        return Err(format!("'{}' cannot emit {}, it stops before that is made.", args[0], kind.name()));
+    }
+
+    Ok(Some(Options {
+        mode,
+        filename: filename.clone(),
+        stage,
+        emit,
+        output: output.cloned(),
+        allowed,
+        json,
+        // JSON diagnostics are meant for tools, so the error context is left out for them.
+        log_level: match verbosity {
+            0 if json => Level::Off,
+            0 => Level::Error,
+            1 => Level::Info,
+            _ => Level::Trace,
+        },
+        limits,
+        trace,
+        profile,
+        profile_stacks: profile_stacks.cloned(),
+        input: input.cloned(),
+    }))
 }
-fn main() {
-    // Let us get commandline arguments and store them in a Vec<String>
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 1 {
-        println!("Please provide an input file through the commandline arguments for the lexer.");
-        return;
-    }
 
-    if args.len() > 2 {
-        println!("Too many commandline arguments.");
-        return;
-    }
+// returns the exit code of the process.
+fn compile(options: &Options) -> u8 {
+    let filename = &options.filename;
+    let json = options.json;
 
     // read the entire file contents, storing them inside 'code' as a string.
-    let filename = &args[1];
     let code = match fs::read_to_string(filename) {
     Err(error) => {
-        println!("**Error. File \"{}\": {}", filename, error);
-        return;
+        let error = Diagnostic::new(codes::CANNOT_READ_FILE, format!("File \"{}\": {}", filename, error));
+        if json {
+            report_json(&error, filename, "");
+        } else {
+            eprintln!("**{}", error);
+        }
+        return exit::failure(&error);
     }
 
     Ok(code) => {
         code
-    } 
-
-    };
-
-    let tokens = match lex(&code) {
-    Err(error_message) => {
-        println!("**Error**");
-        println!("----------------------");
-        println!("{}", error_message);
-        println!("----------------------");
-        return;
     }
 
-    Ok(data) => data,
-    
     };
 
-
-    // print out the lexer tokens parsed.
-
-    println!("----------------------");
-    println!("Finished Lexing the file {}", filename);
-    println!("Expression:");
-    println!("{code}");
-    println!("Here are the Results:");
-    println!("----------------------");
-    for t in &tokens {
-      println!("{:?}", t);
-    }
-
-    let mut index: usize = 0;
-    println!();
-
-    match parse_program(&tokens, &mut index) {
-
-    Ok(generated_code) => {
-        println!("Program Parsed Successfully.");
-        println!("{}",&generated_code);
-        // let generated_code: String = parse(tokens)?;
-
-        // SEMANTICS CHECK!!!!
-        if (semantics_check(generated_code.clone())){
-          interpreter::execute_ir(&generated_code);
-        }
-    }
-
-    Err(message) => {
-        println!("**Error**");
-        println!("----------------------");
-        if tokens.len() == 0 {
-            println!("No code has been provided.");
+    // prints an error in the format picked on the commandline.
+    let report = |error: &Diagnostic| {
+        if json {
+            report_json(error, filename, &code);
         } else {
-            println!("Error: {message}");
-            println!("----------------------");
+            eprintln!("{error}");
         }
-    }
-
-    }
-    
-    // 
-
-}
-
-// Creating an Enum within Rust.
-// Documentation: https://doc.rust-lang.org/book/ch06-01-defining-an-enum.html
-// Enums are a way of saying a value is one of a possible set of values.
-// Unlike C, Rust enums can have values associated with that particular enum value.
-// for example, a Num has a 'i32' value associated with it, 
-// but Plus, Subtract, Multiply, etc. have no values associated with it.
-#[derive(Debug, Clone)]
-enum Token {
-  NotToken,
-  //math
-  Plus,
-  Subtract,
-  Multiply,
-  Divide,
-  Modulus,
-  Assign,
-
-  Num(i32),
-  Ident(String),
-  
-  //keywords
-  If,
-  While,
-  Read, 
-  Func,
-  Return,
-  Int,
-  Print,
-  Else,
-  Break,
-  Continue,
-
-  LeftParen,
-  RightParen,
-  LeftCurly,
-  RightCurly,
-  LeftBracket,
-  RightBracket,
-  Comma,
-  Semicolon,
-  
-  //boolean
-  Less,
-  LessEqual,
-  Greater,
-  GreaterEqual,
-  Equality,
-  NotEqual
-}
-
-// In Rust, you can model the function behavior using the type system.
-// https://doc.rust-lang.org/std/result/
-// Result < Vec<Token>, String>
-// means that this function can either return:
-// - A list of tokens as a Vec<Token>
-// - Or an error message represented as a string
-// If there is an error, it will return an error
-// If successful, it will return Vec<Token>
-// A Result is an enum like this:
-// enum Result {
-//     Ok(the_result),
-//     Err(the_error),
-// }
-
-
-// This is a lexer that parses numbers/identifiers and math operations
-fn lex(mut code: &str) -> Result<Vec<Token>, String> {
-  let mut tokens: Vec<Token> = vec![];
-  while code.len() > 0 {
-    let (success, token, rest) = lex_number(code);
-    if success {
-      code = rest; 
-      tokens.push(token);
-      continue;
-    } 
- 
-    let (success, rest) = lex_space(code);
-    if success {
-      code = rest;
-      continue;
-    }
-
-    if code.starts_with("+") {
-      code = &code[1..];
-      tokens.push(Token::Plus);
-      continue;
-    }
-
-    if code.starts_with("-") {
-      code = &code[1..];
-      tokens.push(Token::Subtract);
-      continue;
-    }
-
-    if code.starts_with("*") {
-      code = &code[1..];
-      tokens.push(Token::Multiply);
-      continue;
-    }
-
-    if code.starts_with("/") {
-      code = &code[1..];
-      tokens.push(Token::Divide);
-      continue;
-    }
-
-    if code.starts_with("%") {
-      code = &code[1..];
-      tokens.push(Token::Modulus);
-      continue;
-    }
-
-    if code.starts_with("==") {
-      code = &code[2..];
-      tokens.push(Token::Equality);
-      continue;
-    }
-
-    if code.starts_with("=") {
-      code = &code[1..];
-      tokens.push(Token::Assign);
-      continue;
-    }
-
-    if code.starts_with("("){
-      code = &code[1..];
-      tokens.push(Token::LeftParen);
-      continue;
-    }
-    
-    if code.starts_with(")"){
-      code = &code[1..];
-      tokens.push(Token::RightParen);
-      continue;
-    }
-
-    // Check for comment
-    if code.starts_with('#') {
-      code = skip_comment(code);
-      continue;
-  }
-
-    if code.starts_with("{") {
-      code = &code[1..];
-      tokens.push(Token::LeftCurly);
-      continue;
-    }
-
-    if code.starts_with("}") {
-      code = &code[1..];
-      tokens.push(Token::RightCurly);
-      continue;
-    }
-
-    if code.starts_with("[") {
-      code = &code[1..];
-      tokens.push(Token::LeftBracket);
-      continue;
-    }
+    };
 
-    if code.starts_with("]") {
-      code = &code[1..];
-      tokens.push(Token::RightBracket);
-      continue;
+    let (tokens, spans) = match rustcompiler::lex_with_spans(&code) {
+    Err(error_message) => {
+        if json {
+            report_json(&error_message, filename, &code);
+            return exit::failure(&error_message);
+        }
+        eprintln!("**Error**");
+        eprintln!("----------------------");
+        eprintln!("{}", error_message);
+        eprintln!("----------------------");
+        return exit::failure(&error_message);
     }
 
-    if code.starts_with(",") {
-      code = &code[1..];
-      tokens.push(Token::Comma);
-      continue;
-    }
+    Ok(data) => data,
 
-    if code.starts_with(";") {
-      code = &code[1..];
-      tokens.push(Token::Semicolon);
-      continue;
-    } 
-      
-    if code.starts_with("<=") {
-      code = &code[2..];
-      tokens.push(Token::LessEqual);
-      continue;
-    }
+    };
 
-    if code.starts_with("<") {
-      code = &code[1..];
-      tokens.push(Token::Less);
-      continue;
-    }
 
-    if code.starts_with(">=") {
-      code = &code[2..];
-      tokens.push(Token::GreaterEqual);
-      continue;
-    }
+    // print out the lexer tokens parsed.
 
-    if code.starts_with(">") {
-      code = &code[1..];
-      tokens.push(Token::Greater);
-      continue;
+    log!(Level::Info, "----------------------\n");
+    log!(Level::Info, "Finished Lexing the file {}\n", filename);
+    log!(Level::Info, "Expression:\n");
+    log!(Level::Info, "{code}\n");
+    log!(Level::Info, "Here are the Results:\n");
+    log!(Level::Info, "----------------------\n");
+    for t in &tokens {
+      log!(Level::Info, "{:?}\n", t);
     }
 
-    if code.starts_with("!=") {
-      code = &code[2..];
-      tokens.push(Token::NotEqual);
-      continue;
+    if let Err(error) = emit(options, Emit::Tokens, || token_listing(&tokens)) {
+        report(&error);
+        return exit::failure(&error);
     }
-
-    let (success, token, rest) = lex_identifier(code);
-    if success {
-      code = rest;
-      tokens.push(token);
-      continue;
+    if options.stage == Stage::Lex {
+        return exit::SUCCESS;
     }
 
-    let symbol = unrecognized_symbol(code);
-    return Err(format!("Unidentified symbol {symbol}"));
-
-  }
-
-  return Ok(tokens);
-}
+    log!(Level::Info, "\n");
 
-fn lex_space(code: &str) -> (bool, &str) {
-  for letter in code.chars() {
-    if letter.is_whitespace() {
-      return (true, &code[1..]);
+    // the trace is only collected when it is emitted, otherwise it is logged.
+    let (parsed, trace) = if options.emit.iter().any(|(kind, _)| *kind == Emit::Ast) {
+        rustcompiler::parse_traced(&tokens, &spans)
     } else {
-      return (false, code);
-    }
-  }
-  return (false, code);
-}
-
-// lex numbers.
-fn lex_number(code: &str) -> (bool, Token, &str) {
-  enum StateMachine {
-    Start,
-    Number,
-  }
-
-  let mut success = false;
-  let mut state = StateMachine::Start;
-  let mut index = 0;
-  for letter in code.chars() {
-    match state {
-    StateMachine::Start => {
-      if letter >= '0' && letter <= '9' {
-        state = StateMachine::Number;
-        success = true;
-        index += 1;
-      }  else {
-        return (false, Token::NotToken, "");
-      }
-    }
-
-    StateMachine::Number => {
-      if letter >= '0' && letter <= '9' {
-        state = StateMachine::Number;
-        success = true;
-        index += 1;
-      } else if (letter >= 'A' || letter <= 'Z') && (letter >= 'a' && letter <= 'z'){
-        print!("Invalid token: {}\n", letter as i32);
-        return (false, Token::NotToken, "");
-      } else {
-        let num = code[..index].parse::<i32>().unwrap();
-        return (true, Token::Num(num), &code[index..]);
-      }
-    }
-
-    }
-  }
-
-  if success == true {
-    let num: i32 = code.parse::<i32>().unwrap();
-    return (true, Token::Num(num), "");
-  } else {
-    return (false, Token::NotToken, "");
-  }
-}
+        (rustcompiler::parse_with_spans(&tokens, &spans), String::new())
+    };
 
-// lex identifiers.
-fn lex_identifier(code: &str) -> (bool, Token, &str) {
-  enum StateMachine {
-    Start,
-    Ident,
-  }
+    match parsed {
+
+    Ok(_) => {
+        // the files it imports are read and parsed now, and their IR comes before the IR of this file.
+        let program = match rustcompiler::imports::load(Some(Path::new(filename)), &code) {
+            Ok(program) => program,
+            Err(error) => {
+                report(&error);
+                return exit::failure(&error);
+            }
+        };
+        let generated_code = program.ir();
+        log!(Level::Info, "Program Parsed Successfully.\n");
+        log!(Level::Info, "{}\n",&generated_code);
+
+        let emitted = emit(options, Emit::Ast, || trace.clone()).and_then(|_| emit(options, Emit::Ir, || generated_code.clone()));
+        if let Err(error) = emitted {
+            report(&error);
+            return exit::failure(&error);
+        }
+        if options.stage == Stage::Parse {
+            return exit::SUCCESS;
+        }
 
-  let mut success = false;
-  let mut state = StateMachine::Start;
-  let mut index = 0;
-  for letter in code.chars() {
-    match state {
-    StateMachine::Start => {
-      if (letter >= 'a' && letter <= 'z') || (letter >= 'A' && letter <= 'Z'){
-        state = StateMachine::Ident;
-        success = true;
-        index += 1;
-      } else {
-        return (false, Token::NotToken, "");
-      }
+        // semantic checks, then control flow checks: uninitialized reads, missing returns, unused and unreachable code.
+        let analysis = program.check();
+        for warning in &analysis.warnings {
+          if options.allowed.is_allowed(warning) {
+            continue;
+          }
+          if json {
+            let (filename, code) = source_of(warning.file.as_deref(), filename, &code);
+            let span = warning.line.and_then(|line| diagnostics::line_span(&code, line));
+            eprintln!("{}", diagnostics::to_json(warning.kind.code(), "warning", &warning.message, filename, span, &code));
+          } else {
+            eprintln!("{warning}");
+          }
+        }
+        for error in &analysis.errors {
+          report(error);
+        }
+        if let Some(error) = analysis.errors.first() {
+          return exit::failure(error);
+        }
+        if options.stage == Stage::Check {
+          return exit::SUCCESS;
+        }
+        if options.mode == Mode::Debug {
+          // the lines of the file only match the marks in the IR when nothing was imported.
+          let source = if program.has_imports() { None } else { Some(code.as_str()) };
+          let stdin = io::stdin();
+          let commands = std::iter::from_fn(|| {
+            let mut line = String::new();
+            match stdin.read_line(&mut line) {
+              Ok(0) | Err(_) => None,
+              Ok(_) => Some(line),
+            }
+          });
+          return match rustcompiler::debugger::run(&generated_code, source, &options.limits, commands, &mut io::stdout()) {
+            Ok(exit_code) => exit_code.map_or(exit::SUCCESS, exit::program),
+            Err(error) => {
+              report(&error);
+              exit::failure(&error)
+            }
+          };
+        }
+        match execute(options, &generated_code) {
+          Ok(exit_code) => {
+            log!(Level::Info, "Run successful. Exit code {}\n", exit_code);
+            exit::program(exit_code)
+          }
+          Err(error) => {
+            report(&error);
+            exit::failure(&error)
+          }
+        }
     }
 
-    StateMachine::Ident => {
-      if (letter >= 'A' && letter <= 'Z') || (letter >= 'a' && letter <= 'z') || (letter >= '0' && letter <= '9') || letter == '_' {
-        state = StateMachine::Ident;
-        success = true;
-        index += 1;
-      } else {
-        let token = &code[..index];
-        return (true, create_identifier(token), &code[index..]);
-      }
+    Err(message) => {
+        // a failed parse still leaves the trace up to the error, which is what it is most useful for.
+        if let Err(error) = emit(options, Emit::Ast, || trace.clone()) {
+            report(&error);
+        }
+        if json {
+          report(&message);
+          return exit::failure(&message);
+        }
+        eprintln!("**Error**");
+        eprintln!("----------------------");
+        if tokens.is_empty() {
+            eprintln!("No code has been provided.");
+        } else {
+            eprintln!("{message}");
+            eprintln!("----------------------");
+        }
+        exit::failure(&message)
     }
 
     }
-  }
-
-  if success == true {
-    return (true, create_identifier(code), "");
-  } else {
-    return (false, Token::NotToken, "");
-  }
-}
-
-// Function to skip comments in the code
-// takes a reference to a string (code) as input, returns a slice of the string.
-// If the input code contains a newline character ('\n'),  returns a slice starting from the character immediately after the newline.
-//If no newline character is found, it returns an empty string.
-fn skip_comment(code: &str) -> &str {
-  if let Some(pos) = code.find('\n') {
-      &code[pos + 1..]
-  } else {
-      ""
-  }
 }
 
+// runs a file of IR, reporting errors just like running a compiled program does.
+fn run_ir(options: &Options) -> u8 {
+    let filename = &options.filename;
+    let report = |error: &Diagnostic, code: &str| {
+        if options.json {
+            report_json(error, filename, code);
+        } else {
+            eprintln!("{error}");
+        }
+        exit::failure(error)
+    };
 
-fn unrecognized_symbol(code: &str) -> &str {
-  enum StateMachine {
-    Start,
-    Symbol,
-  }
-
-  let mut state_machine = StateMachine::Start;
-  let mut index = 0;
-  for letter in code.chars() {
-    match state_machine {
-    StateMachine::Start => {
-      state_machine = StateMachine::Symbol;
-      index += 1;
-    } 
-    
-    StateMachine::Symbol => {
-      if letter.is_whitespace() {
-        return &code[..index];
-      } else {
-        index += 1;
-      }
-    }
-
+    let code = match fs::read_to_string(filename) {
+        Ok(code) => code,
+        Err(error) => {
+            let error = Diagnostic::new(codes::CANNOT_READ_FILE, format!("File \"{}\": {}", filename, error));
+            if options.json {
+                report_json(&error, filename, "");
+            } else {
+                eprintln!("**{}", error);
+            }
+            return exit::failure(&error);
+        }
+    };
+    match execute(options, &code) {
+        Ok(exit_code) => {
+            log!(Level::Info, "Run successful. Exit code {}\n", exit_code);
+            exit::program(exit_code)
+        }
+        // the lines of its marks are lines of the source the IR was compiled from, not of the IR.
+        Err(error) => report(&Diagnostic { line: None, ..error }, &code),
     }
-  }
-  return &code[..index];
-} 
-
-fn create_identifier(code: &str) -> Token {
-  match code {
-  "func" => Token::Func,
-  "return" => Token::Return,
-  "int" => Token::Int,
-
-  // todo: implement all keywords...
-  // ... all keywords...
-
-  "read" => Token::Read,
-  "while" => Token::While,
-  "if" => Token::If,
-
-  // print, else, break, continue keywords
-  "print" => Token::Print,
-  "else" => Token::Else,
-  "break" => Token::Break,
-  "continue" => Token::Continue,
-  _ => Token::Ident(String::from(code)),
-  }
 }
 
-
-
-// the <'a> is the "lifetimes" type annotations in Rust.
-//
-fn peek<'a>(tokens: &'a Vec<Token>, index: usize) -> Option<&'a Token> {
-    if index < tokens.len() {
-        return Some(&tokens[index])
-    } else {
-        return None
-    }
+// the number after an option such as '--max-call-depth', which has to be above 0.
+fn positive(args: &[String], index: usize, option: &str) -> Result<usize, String> {
+    let number = args.get(index).and_then(|number| number.parse::<usize>().ok()).filter(|number| *number > 0);
+    number.ok_or(format!("Expected a number above 0 after {option}."))
 }
 
-fn peek_result<'a>(tokens: &'a Vec<Token>, index: usize) -> Result<&'a Token, String> {
-    if index < tokens.len() {
-        return Ok(&tokens[index])
-    } else {
-        return Err(String::from("expected a token, but got nothing"))
+// runs IR with the limits, trace and input of the options, printing the profile after it when one was asked for.
+fn execute(options: &Options, code: &str) -> Result<i32, Diagnostic> {
+    let input: Box<dyn io::BufRead> = match &options.input {
+        Some(path) => match fs::File::open(path) {
+            Ok(file) => Box::new(io::BufReader::new(file)),
+            Err(error) => return Err(Diagnostic::new(codes::CANNOT_READ_FILE, format!("File \"{}\": {}", path, error))),
+        },
+        None => Box::new(io::stdin().lock()),
+    };
+    let mut profile = options.profile.then(Profile::default);
+    let result = interpreter::execute_ir(code, &options.limits, options.trace.as_ref(), profile.as_mut(), input, io::stdout().lock());
+    // IR that does not load never runs, and has nothing to show.
+    let Some(profile) = profile.filter(|profile| profile.ran()) else {
+        return result;
+    };
+    eprint!("{}", profile.report());
+    let return_value = result?;
+    if let Some(path) = &options.profile_stacks {
+        if let Err(error) = fs::write(path, profile.collapsed_stacks()) {
+            return Err(Diagnostic::new(codes::CANNOT_WRITE_FILE, format!("File \"{}\": {}", path, error)));
+        }
     }
+    Ok(return_value)
 }
 
-fn next<'a>(tokens: &'a Vec<Token>, index: &mut usize) -> Option<&'a Token> {
-    if *index < tokens.len() {
-        let ret = *index;
-        *index += 1;
-        return Some(&tokens[ret])
-    } else {
-        return None
-    }
-}
+// prints the program formatted, or writes it to the file given with -o.
+// with check, nothing is written and the exit code tells whether the file is formatted already.
+fn fmt(options: &Options, check: bool) -> u8 {
+    let filename = &options.filename;
+    let report = |error: &Diagnostic, code: &str| {
+        if options.json {
+            report_json(error, filename, code);
+        } else {
+            eprintln!("{error}");
+        }
+        exit::failure(error)
+    };
 
-fn next_result<'a>(tokens: &'a Vec<Token>, index: &mut usize) -> Result<&'a Token, String> {
-    if *index < tokens.len() {
-        let ret = *index;
-        *index += 1;
-        return Ok(&tokens[ret])
-    } else {
-        return Err(String::from("expected a token, but got nothing"))
+    let code = match fs::read_to_string(filename) {
+        Ok(code) => code,
+        Err(error) => {
+            let error = Diagnostic::new(codes::CANNOT_READ_FILE, format!("File \"{}\": {}", filename, error));
+            return report(&error, "");
+        }
+    };
+    let formatted = match rustcompiler::format::format(&code) {
+        Ok(formatted) => formatted,
+        Err(error) => return report(&error, &code),
+    };
+    if check {
+        if formatted == code {
+            return exit::SUCCESS;
+        }
+        eprintln!("{filename} is not formatted. 'rustcompiler fmt {filename} -o {filename}' formats it.");
+        return exit::UNFORMATTED;
+    }
+    match &options.output {
+        Some(path) => {
+            if let Err(error) = fs::write(path, formatted) {
+                let error = Diagnostic::new(codes::CANNOT_WRITE_FILE, format!("File \"{}\": {}", path, error));
+                return report(&error, &code);
+            }
+        }
+        None => print!("{formatted}"),
     }
+    exit::SUCCESS
 }
 
-// parse programs with multiple functions
-// loop over everything, outputting generated code.
-fn parse_program(tokens: &Vec<Token>, index: &mut usize) -> Result<String, String> {
-  let mut generated_code = String::from("");
-  loop {
-      match parse_function(tokens, index)? {
-      None => {
-          break;
-      }
-      Some(func_code) => {
-        generated_code += &func_code;
-      }
-      }
-  }
-  return Ok(generated_code);
-}
-
-// parse function such as:
-// func main(int a, int b) {
-//    # ... statements here...
-//    # ...
-// }
-// a loop is done to handle statements.
-fn parse_function(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<String>, String> {
-  print!("parse_function\n");
-  // Check if the next token is 'func'
-  match next(tokens, index) {
-      None => { // If there are no more tokens, return None
-          return Ok(None);
-      }
-      Some(token) => {
-          if !matches!(token, Token::Func) { // If the token is not 'func', return an error
-              return Err(String::from("functions must begin with func"));
-          }
-      }
-  }
-  // Check if the next token is an identifier
-  let func_ident = match next_result(tokens, index)? {
-      Token::Ident(func_ident) => func_ident,
-      _ => { 
-        return Err(String::from("functions must have a function identifier")); 
-      }
-  };
-  // Check if the next token is '('
-  if !matches!( next_result(tokens, index)?, Token::LeftParen) {
-      return Err(String::from("expected '('"));
-  }
-  
-  let mut code = format!("%func {}", func_ident);
-  let mut params: Vec<String> = vec![];
-
-  // Loop to parse function parameters
-  loop {
-      match next_result(tokens, index)? {
-          Token::RightParen => { break; }
-          Token::Int => {
-              match next_result(tokens, index)? {
-                  Token::Ident(param) => {
-                    params.push(param.clone());  
-                    match peek_result(tokens, *index)? {
-                          Token::Comma => { *index += 1; }
-                          Token::RightParen => {}
-                          _ => { 
-                            return Err(String::from("expected ',' or ')'")); 
-                          }
-                      }
-                  }
-                  _ => { 
-                    return Err(String::from("expected ident function parameter")); 
-                  }
-              }
-          }
-          _ => { 
-            return Err(String::from("expected 'int' keyword or ')' token")); 
-          }
-      }
-  }
-  // if there are params, add them to the function signature
-  print!("params: {:?}\n", params);
-  if(params.len() > 0){
-    code += &format!("(");
-    for param in params.iter() {
-      code += &format!("%int {}", param);
-      code += &format!(", ");
-    }
-    code += &format!(")");
-    code = code.replace(", )", ")");
-  }
-  code += &format!("\n");
-
-  // Check if the next token is '{'
-  if !matches!(next_result(tokens, index)?, Token::LeftCurly) {
-      return Err(String::from("expected '{'"));
-  }
-  // Loop to parse statements inside the function body
-  loop {
-      match parse_statement(tokens, index)? {
-          None => { break; }
-          Some(statement) => {
-            code += &statement;
-          }
-      }
-  }
-  code += "%endfunc\n\n";
-  // Check if the next token is '}'
-  if !matches!(next_result(tokens, index)?, Token::RightCurly) {
-      return Err(String::from("expected '}'"));
-  }
-  return Ok(Some(code)); // Return Ok if parsing is successful
+fn token_listing(tokens: &[Token]) -> String {
+    tokens.iter().map(|t| format!("{:?}\n", t)).collect()
 }
 
-// parsing a statement such as:
-// int a;
-// a = a + b;
-// a = a % b;
-// print(a)
-// read(a)
-// returns epsilon if '}'
-fn parse_statement(tokens: &Vec<Token>, index: &mut usize) -> Result<Option<String>, String> {
-  print!("parse_statement {:?}\n", tokens[*index]);
-  match peek(tokens, *index) {
-      None => { // If there are no more tokens, return None
-          print!("parse statement exit (none)\n");
-          return Ok(None);
-      }
-      Some(token) => {
-        let codenode: Option<String>;
-          match token {
-              // If the token is '}', return None
-              Token::RightCurly => {
-                codenode = None;
-                return Ok(codenode); 
-              } 
-              // If the token is 'int', parse variable declaration
-              Token::Int => { 
-                *index += 1;
-                match next_result(tokens, index)? {
-                  
-                  // need to return intermediate code for arrays here
-                  Token::LeftBracket => {
-                    let array_param = parse_array_form(tokens, index)?;
-                    match peek(tokens, *index) {
-                      Some(Token::Ident(ident)) => {
-                        *index += 1;
-                        // let m_expr = parse_term(tokens, index)?; // Parse the next term
-                        // let t = create_temp();
-                        // let instr = format!("%int {}\n{opcode} {}, {}, {}\n", t, t, expr.name, m_expr.name);
-                        // expr.code += &m_expr.code;
-                        // expr.code += &instr;
-                        // expr.name = t;
-
-                        let statement = format!("%int[] {}, {}\n", ident, array_param.name);
-                        codenode = Some(statement);
-                      }
-                      _ => {
-                        return Err(String::from("expected identifier"));
-                      }
-                    }
-                  }
-
-                  Token::Ident(ident) => {
-                    let statement = format!("%int {}\n", ident);
-                    codenode = Some(statement);
-                    //println!("bsdgdfsdf");
-                  }
-
-                  _ => {
-                      return Err(String::from("expected identifier"));
-                  }
-
-                }
-                // <NOT REQUIRED/PART OF PROMPT> Check if there's an assignment after variable declaration
-                //if matches!(peek_result(tokens, *index)?, Token::Assign) {
-                 //   *index += 1;
-                    //println!("parse expression after variable declaration\n");
-                //} 
-                
-              }
-              
-              // If the token is an identifier
-              Token::Ident(ident) => {
-                *index += 1; // Move to the next token index
-                // Check the next token
-                match peek(tokens, *index) {
-                    Some(Token::Assign) => {
-                        // If the next token is '=', parse an assignment
-                        *index += 1;
-                        println!("parse expression after identifier");
-                        let expr = parse_expression(tokens, index)?;
-                        let code = format!("{}%mov {}, {}\n", expr.code, ident, expr.name);
-                        codenode = Some(code);
-                        // codenode = None;
-                        
-                    }
-                    Some(Token::Less) | Some(Token::LessEqual) | Some(Token::Greater) | Some(Token::GreaterEqual) | Some(Token::Equality) | Some(Token::NotEqual) => {
-                        // If the next token is a boolean operator, parse a boolean expression
-                        //println!("parse boolean expression after variable declaration\n");
-                        // parse_boolean_expression(tokens, index)?;
-                        let bool_expr = parse_boolean_expression(tokens, index)?;
-                        let code = format!("{}%mov {}\n", bool_expr.code, bool_expr.name);
-                        codenode = Some(code);
-                    }
-                    Some(Token::LeftBracket) =>{
-                        let array_param =parse_array_form(tokens, index)?;
-                        //println!("current tok4: {:?}", tokens[*index]);
-                        match peek(tokens, *index){
-                          Some(Token::Assign) => {
-                            // If the next token is '=', parse an assignment
-                            *index += 1;
-                            //println!("parse expression after identifier");
-                            // parse_expression(tokens, index)?;
-                            let expr = parse_expression(tokens, index)?;
-                            let code = format!("{}%mov [{} + {}], {}\n", expr.code, ident, array_param.name, expr.name);
-                            codenode = Some(code);
-                          }
-                          Some(Token::Less) | Some(Token::LessEqual) | Some(Token::Greater) | Some(Token::GreaterEqual) | Some(Token::Equality) | Some(Token::NotEqual) => {
-                            // If the next token is a boolean operator, parse a boolean expression
-                            //println!("parse boolean expression after variable declaration\n");
-                            // parse_boolean_expression(tokens, index)?;
-                            let bool_expr = parse_boolean_expression(tokens, index)?;
-                            let code = format!("{}%mov {}\n", bool_expr.code, bool_expr.name);
-                            codenode = Some(code);
-                          }
-                          _ => {
-                            codenode = None;
-                            return Err(String::from("unexpected token after identifier []"));
-                        }
-                        }
-
-                    }
-                    _ => {
-                        codenode = None;
-                        return Err(String::from("unexpected token after identifier"));
-                    }
-                }
-              }
-              // If the token is 'return', parse the expression
-              Token::Return => { 
-                *index += 1; 
-                let expr = parse_expression(tokens, index)?;
-                let code = format!("{}%ret {}\n", expr.code, expr.name);
-                codenode = Some(code);
-              }
-              // If the token is 'print' 
-              Token::Print => { 
-                  *index += 1; // Move to the next token index
-                  if !matches!(next_result(tokens, index)?, Token::LeftParen) { // If the next token is not '(', return an error
-                      return Err(String::from("expect '(' closing statement"));
-                  }
-                  let mut expr = parse_term(tokens, index)?;
-                  print!("parse print expression: {}\n", expr.name);
-                  let array_num;
-                  if(matches!(peek_result(tokens, *index)?, Token::LeftBracket)){
-                    array_num = parse_array_form(tokens, index)?;
-                    let t = create_temp();
-                    expr.code = format!("{}%int {}\n%mov {}, [{} + {}]\n", expr.code,t,t, expr.name, array_num.name);
-                    expr.name = t;
-                  }
-                  let code = format!("{}%out {}\n", expr.code, expr.name);
-                  if !matches!(next_result(tokens, index)?, Token::RightParen) { // If the next token is not ')', return an error
-                      return Err(String::from("expect ')' closing statement"));
-                  }
-                  codenode = Some(code)
-              }
-              // If the token is 'read'
-              Token::Read => { 
-                  *index += 1; // Move to the next token index
-                  if !matches!(next_result(tokens, index)?, Token::LeftParen) { // If the next token is not '(', return an error
-                      return Err(String::from("expect '(' closing statement"));
-                  }
-                  let expr = parse_expression(tokens, index)?;
-                  let code = format!("{}%input {}\n", expr.code, expr.name);
-                  if !matches!(next_result(tokens, index)?, Token::RightParen) { // If the next token is not ')', return an error
-                      return Err(String::from("expect ')' closing statement"));
-                  }
-                  codenode = Some(code);
-              }
-
-              Token::While => { 
-                //println!("While");
-                *index += 1; // Move to the next token index
-                let while_label = create_whileloopbegin_label();
-                let endwhile_label = create_whileloopend_label();
-                unsafe{
-                  WHILE_LABEL_STACK.push(while_label.clone());
-                  WHILE_LABEL_STACK.push(endwhile_label.clone());
-                }
-                let mut statementCode: String = String::from("");
-                let condition = parse_boolean_expression(tokens, index)?; // Parse boolean expression
-                let mut code = format!(":{}\n", while_label);
-                code += &format!("{}%branch_ifn {}, :{}\n",condition.code, condition.name, endwhile_label);
-                //println!("parsed");
-                if !matches!(next_result(tokens, index)?, Token::LeftCurly) { // If the next token is not '{', return an error
-                    return Err(String::from("expect '{' for while loop"));
-                }
-                //println!("after left curly: {:?}", tokens[*index]);
-                while !matches!(peek_result(tokens, *index)?, Token::RightCurly){ // while not right bracket
-                  //println!("not right bracket: {:?}", tokens[*index]);
-                  // parse_statement(tokens, index)?;
-                  match parse_statement(tokens, index)? {
-                    None => { /* do nothing lol */ }
-                    Some(statement) => {
-                      statementCode += &statement;
-                    }
-                  }
-                }
-                *index += 1; // matched a }
-                code += &statementCode;
-                code += &format!("%jmp :{}\n",while_label);
-                code += &format!(":{}\n", endwhile_label);
-                
-                // return Ok(Some(String::from("TODO:While"))); // skip ; check
-                unsafe{
-                  WHILE_LABEL_STACK.pop();
-                  WHILE_LABEL_STACK.pop();
-                }
-                return Ok(Some(code)); // skip ; check
-              }
-            
-              Token::If => {
-                //println!("if");
-                *index += 1; // Move to the next token index
-                // create labels
-                let iftrue_label = create_iftrue_label();
-                let endif_label = create_endif_label();
-                //let else_label = create_else_label();
-                println!("before if parse boolean expression: {:?}", tokens[*index]);
-                let conditional = parse_boolean_expression(tokens, index)?; // Parse boolean expression
-                println!("after if parse boolean expression: {:?}", tokens[*index]);
-                let mut code = format!("{}%branch_if {}, :{}\n",conditional.code, conditional.name, iftrue_label);
-                let mut statementCode: String = String::from("");
-                if !matches!(next_result(tokens, index)?, Token::LeftCurly) { // If the next token is not '{', return an error
-                  return Err(String::from("expect '{' closing statement"));
-                }
-                while !matches!(peek_result(tokens, *index)?, Token::RightCurly){ // while not right bracket
-                  match parse_statement(tokens, index)? {
-                    None => { /* do nothing lol */ }
-                    Some(statement) => {
-                      statementCode += &statement;
-                    }
-                  }
-                }
-                *index += 1;
-                // handle else
-                if matches!(peek_result(tokens, *index)?, Token::Else) {
-                  *index += 1; // Move to the next token index
-                  if !matches!(next_result(tokens, index)?, Token::LeftCurly) { // If the next token is not '{', return an error
-                    return Err(String::from("expect '{' opening statement"));
-                  }
-                  while !matches!(peek_result(tokens, *index)?, Token::RightCurly){ // while not right bracket
-                    match parse_statement(tokens, index)? {
-                      None => { /* do nothing lol */ }
-                      Some(statement) => {
-                        code += &statement;
-                      }
-                    }
-                  }
-
-                  *index += 1;
-                }
-                code += &format!("%jmp :{}\n", endif_label);
-                code += &format!(":{}\n", iftrue_label);
-                code += &statementCode;
-                code += &format!(":{}\n", endif_label);
-                return Ok(Some(code)); // skip ; check
-              }
-
-              Token::Continue => { 
-                *index += 1; // Move to the next token index
-                let code;
-                unsafe{
-                  if(WHILE_LABEL_STACK.len() == 0){
-                    return Err(String::from("continue statement outside of loop"));
-                  }
-                   code = format!("%jmp :{}\n", WHILE_LABEL_STACK[WHILE_LABEL_STACK.len() - 2]);
-                }
-                codenode = Some(code);
-              }
-
-              Token::Break => { 
-                *index += 1; // Move to the next token index
-                let code;
-                unsafe{
-                  if(WHILE_LABEL_STACK.len() == 0){
-                    return Err(String::from("break statement outside of loop"));
-                  }
-                  code = format!("%jmp :{}\n", WHILE_LABEL_STACK[WHILE_LABEL_STACK.len() - 1]);
+// writes out what was made of the program, if it was asked for with '--emit' or is the output of the command.
+fn emit(options: &Options, kind: Emit, text: impl Fn() -> String) -> Result<(), Diagnostic> {
+    for (emitted, path) in &options.emit {
+        if *emitted != kind {
+            continue;
+        }
+        match path {
+            Some(path) => {
+                if let Err(error) = fs::write(path, text()) {
+                    return Err(Diagnostic::new(codes::CANNOT_WRITE_FILE, format!("File \"{}\": {}", path, error)));
                 }
-                codenode = Some(code);
-              }
-
-              // If the token is invalid, return an error
-              
-              _ => {
-                codenode = None;
-                println!("Token at invalid statement: {:?}", tokens[*index]);
-                return Err(String::from("invalid statement.")); } 
-          }
-          //println!("before ; : {:?}", tokens[*index]);
-          if !matches!(next_result(tokens, index)?, Token::Semicolon) { // If the next token is not ';', return an error
-              println!("not ; : {:?}", tokens[*index]);
-              return Err(String::from("expect ';' closing statement after statement"));
-          }
-          return Ok(codenode); // Return Ok if parsing is successful
-      }
-  }
-}
-
-// parsing a simple expression such as:
-// "a" (alone)
-// "a + b"
-// "a * b"
-// "a - b"
-// NOTE: this cannot parse "complex" expressions such as "a + b * c".
-// I leave "a + b * c" as an exercise for the student.
-fn parse_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-  let mut expr = parse_multiply_expression(tokens, index)?; // this gets the identifier or num
-  print!("parse_expression {}\n", expr.name);
-  //parse_term(tokens, index)?; // Parse the first term
-  if(matches!(peek_result(tokens, *index)?, Token::LeftBracket)){
-    let arraynum = parse_array_form(tokens, index)?;
-    let t = create_temp();
-    expr.code = format!("{}%int {}\n%mov {}, [{} + {}]\n", expr.code,t,t, expr.name, arraynum.name);
-    expr.name = format!("{}", t);
-  }
-  loop {
-    let opcode = match peek_result(tokens, *index)?{
-      Token::Plus => "%add",
-      Token::Subtract => "%sub",
-      // Token::Multiply => "%mult",
-      // Token::Divide => "%div",
-      // Token::Modulus => "%mod",
-      _ => break,
-    };
-    *index += 1; //go to next token
-    let mut right_expr = parse_multiply_expression(tokens, index)?; // Parse the next higher precedence expression
-    let temp_var = create_temp(); // Create a temporary variable for the result
-    let code = format!("%int {}\n{} {}, {}, {}\n", temp_var, opcode, temp_var, expr.name, right_expr.name);
-    expr.code += &right_expr.code; // Combine the code from both expressions
-    expr.code += &code;
-    expr.name = temp_var; // Update the name to the temporary variable
-  }
-  Ok(expr) // Return the final expression
-}
-
-// Function to parse and evaluate expressions involving multiplication, division, and modulus
-fn parse_multiply_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-  // Start by parsing a term (either a number or an expression inside parentheses)
-  let mut expr = parse_term(tokens, index)?;
-  // Loop to handle multiplication, division, and modulus operations
-  loop {
-      let opcode = match peek_result(tokens, *index)? {
-          Token::Multiply => "%mult",
-          Token::Divide => "%div",
-          Token::Modulus => "%mod",
-          _ => break, // If it's not a multiplication, division, or modulus token, break the loop
-      };
-      *index += 1; // Move to the next token
-      let mut right_expr = parse_term(tokens, index)?; // Parse the next term
-      let temp_var = create_temp(); // Create a temporary variable for the result
-      let code = format!("%int {}\n{} {}, {}, {}\n", temp_var, opcode, temp_var, expr.name, right_expr.name);
-      expr.code += &right_expr.code; // Combine the code from both expressions
-      expr.code += &code;
-      expr.name = temp_var; // Update the name to the temporary variable
-  }
-  Ok(expr) // Return the final expression
-}
-
-
-fn parse_array_form(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-  //println!("current tok: {:?}", tokens[*index]);
-  //println!("run parse expression after [");
-  if(matches!(peek_result(tokens, *index)?,Token::LeftBracket)){
-    *index += 1;
-  }
-  let number = parse_term(tokens, index)?; 
-  //println!("current tok2: {:?}", tokens[*index]);
-  if !matches!(next_result(tokens, index)?, Token::RightBracket) {
-    return Err(String::from("expected ']'"));
-  }
-  //println!("after ]: {:?}", tokens[*index]);
-  //*index += 1;
-
-  return Ok(number)
+            }
+            None => print!("{}", text()),
+        }
+    }
+    Ok(())
 }
 
-fn parse_boolean_expression(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-  print!("parse_boolean_expression\n");
-  println!("term: {:?}", tokens[*index]);
-  let mut expr = parse_term(tokens, index)?; // Parse the left side of the expression
-  println!("after term: {:?}", tokens[*index]);
-  let mut instr = "";
-  //println!("parsed : {:?}", tokens[*index]);
-
-  if(matches!(peek_result(tokens, *index)?,Token::LeftBracket)){
-    parse_array_form(tokens,index)?;
-  }
-  let opcode = match peek_result(tokens, *index)?{
-    Token::Less => "%lt",
-    Token::LessEqual => "%le",
-    Token::Greater => "%gt",
-    Token::GreaterEqual => "%ge",
-    Token::Equality => "%eq",
-    Token::NotEqual => "%neq",
-    _ => "%mov"
-  };
-
-  match peek_result(tokens, *index)? {
-      Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual | Token::Equality | Token::NotEqual => {
-          *index += 1; // Move to the next token
-          println!("boolean operator : {:?}", tokens[*index]);
-          // parse_term(tokens, index)?; // Parse the right side of the expression
-          println!("term: {:?}", tokens[*index]);
-          let m_expr = parse_term(tokens, index)?; // Parse the next term
-          println!("after term: {:?}", tokens[*index]);
-          let t = create_temp();
-          let code = format!("%int {t}\n{opcode} {t}, {}, {}\n", expr.name, m_expr.name);
-          expr.code += &m_expr.code;
-          expr.code += &code;
-          expr.name = t;
-      }
-      _ => {
-        println!("not boolean operator : {:?}", tokens[*index]);
-        return Err(String::from("expected boolean operator"))
-      },
-  }
-  return Ok(expr);
+// prints one error as a JSON object on stderr.
+// an error in a file the program imports points into that file. an error without a span of its own,
+// such as a runtime or a semantic error, points at the line it was found on.
+fn report_json(error: &Diagnostic, filename: &str, code: &str) {
+  let (filename, code) = source_of(error.file.as_deref(), filename, code);
+  let span = error.span.or_else(|| error.line.and_then(|line| diagnostics::line_span(&code, line)));
+  let mut json = diagnostics::to_json(error.code, "error", &error.message, filename, span, &code);
+  if let (Value::Object(entries), false) = (&mut json, error.trace.is_empty()) {
+    entries.push((String::from("trace"), Value::Array(error.trace.iter().map(|call| Value::str(call)).collect())));
+  }
+  eprintln!("{json}");
 }
 
-
-// a term is either a Number or an Identifier.
-// fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-//   match next_result(tokens, index)? {
-//     Token::Ident(name) => {
-//         // Check if the first character of the identifier is a digit
-//         if name.chars().next().unwrap().is_digit(10) {
-//             return Err(format!("Variable names cannot start with a digit: {}", name));
-//         }
-//         // Successfully parsed an identifier
-//         let expr = Expression {
-//           code : String::from(""),
-//           name : name.clone(),
-//         };
-//         return Ok(expr);
-//     }
-//     Token::Num(num) => {
-//         let expr = Expression {
-//           code : String::from(""),
-//           name : format!("{}", num),
-//         };
-//         return Ok(expr);
-//     }
-//     Token::LeftParen => {
-//       // Parse the expression inside the parentheses
-//       parse_expression(tokens, index)?;
-//       // Check if the next token is a right parenthesis
-//       if let Token::RightParen = next_result(tokens, index)? {
-//         let expr = Expression {
-//           code : String::from(""),
-//           name : format!("{}", "("),
-//         };
-//           return Ok(expr);
-//       } else {
-//           return Err(String::from("Expecting ')' after '('"));
-//       }
-//     }
-//     _ => {
-//         return Err(String::from("invalid expression"));
-//     }
-//   }
-// }
-
-// fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-//   match next_result(tokens, index)? {
-//       Token::Ident(name) => {
-//           let mut expr = Expression {
-//               code: String::new(),
-//               name: name.clone(),
-//           };
-//           if(matches!(peek_result(tokens, *index)?, Token::LeftParen)){
-//             println!("function call {}", name);
-//             *index += 1;
-//             let mut params: Vec<String> = vec![];
-//             loop{
-//               match peek_result(tokens, *index)? {
-//                 Token::RightParen => {
-//                   *index += 1;
-//                   break;
-//                 }
-//                 _ => {
-//                   let expr_param = parse_expression(tokens, index)?;
-//                   params.push(expr_param.name);
-//                   expr.code += &expr_param.code;
-//                   if(matches!(peek_result(tokens, *index)?, Token::Comma)){
-//                     *index += 1;
-//                   } else {
-//                      // pass the right paren
-//                     *index += 1;
-//                     break;
-//                   }
-//                 }
-//               }
-//             }
-//             // done with params
-//             // what we will write the return value to
-//             let t = create_temp();
-//             expr.code += &format!("%int {}\n", t);
-//             expr.code += &format!("%call {}, {}({})\n", t, name, params.join(","));
-//             expr.name = t;
-          
-//           }
-//           Ok(expr)
-//       },
-//       Token::Num(num) => {
-//           let expr = Expression {
-//               code: String::new(),
-//               name: format!("{}", num),
-//           };
-//           Ok(expr)
-//       },
-//       Token::LeftParen => {
-//           let expr = parse_expression(tokens, index)?;
-//           if !matches!(next_result(tokens, index)?, Token::RightParen) {
-//               Err(String::from("Expecting ')' after '('"))
-//           } else {
-//               Ok(expr)
-//           }
-//       },
-//       _ => Err(String::from("invalid expression")),
-//   }
-// }
-
-fn parse_term(tokens: &Vec<Token>, index: &mut usize) -> Result<Expression, String> {
-  match next_result(tokens, index)? {
-      Token::Num(num) => {
-          let expr = Expression {
-              code: String::from(""),
-              name: format!("{}", num),
-          };
-          Ok(expr)
-      }
-      Token::Ident(name) => {
-          let mut expr = Expression {
-              code: String::new(),
-              name: name.clone(),
-          };
-          // Check for function call or array indexing
-          match peek_result(tokens, *index)? {
-              Token::LeftParen => {
-                  *index += 1;
-                  let mut params: Vec<String> = vec![];
-                  loop {
-                      match peek_result(tokens, *index)? {
-                          Token::RightParen => {
-                              *index += 1;
-                              break;
-                          }
-                          _ => {
-                              let expr_param = parse_expression(tokens, index)?;
-                              params.push(expr_param.name);
-                              expr.code += &expr_param.code;
-                              if matches!(peek_result(tokens, *index)?, Token::Comma) {
-                                  *index += 1;
-                              } else {
-                                  *index += 1;
-                                  break;
-                              }
-                          }
-                      }
-                  }
-                  let temp_var = create_temp();
-                  expr.code += &format!("%int {}\n", temp_var);
-                  expr.code += &format!("%call {}, {}({})\n", temp_var, name, params.join(","));
-                  expr.name = temp_var;
-              }
-              Token::LeftBracket => {
-                  *index += 1;
-                  let index_expr = parse_expression(tokens, index)?;
-                  if !matches!(next_result(tokens, index)?, Token::RightBracket) {
-                      return Err(String::from("Expecting ']' after '['"));
-                  }
-                  let temp_var = create_temp();
-                  expr.code += &format!("%int {}\n", temp_var);
-                  expr.code += &format!("%mov {}, [{} + {}]\n", temp_var, name, index_expr.name);
-                  expr.name = temp_var;
-              }
-              _ => {}
-          }
-          Ok(expr)
-      }
-      Token::LeftParen => {
-          let expr = parse_expression(tokens, index)?;
-          if !matches!(next_result(tokens, index)?, Token::RightParen) {
-              Err(String::from("Expecting ')' after '('"))
-          } else {
-              Ok(expr)
-          }
-      }
-      _ => Err(String::from("invalid expression")),
+// the name and the source of the file a diagnostic is in. a file other than the one compiled is read again.
+fn source_of<'a>(file: Option<&'a str>, filename: &'a str, code: &str) -> (&'a str, String) {
+  match file {
+    Some(file) if file != filename => (file, fs::read_to_string(file).unwrap_or_default()),
+    _ => (filename, code.to_string()),
   }
 }
 
-
-// writing tests!
-// testing shows robustness in software, and is good for spotting regressions
-// to run a test, type "cargo test" in the terminal.
-// Rust will then run all the functions annotated with the "#[test]" keyword.
-#[cfg(test)]
-mod tests {
-    use crate::Token;
-    use crate::lex;
-
-    #[test]
-    fn lexer_test() {
-
-        let toks = lex("1 <2").unwrap();
-        assert!(toks.len() == 3);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::Less));
-        assert!(matches!(toks[2], Token::Num(2)));
-
-        let toks = lex("1 > 2").unwrap();
-        assert!(toks.len() == 3);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::Greater));
-        assert!(matches!(toks[2], Token::Num(2)));
-
-        let toks = lex("1 <= 2").unwrap();
-        assert!(toks.len() == 3);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::LessEqual));
-        assert!(matches!(toks[2], Token::Num(2)));
-
-        let toks = lex("1>=2").unwrap();
-        assert!(toks.len() == 3);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::GreaterEqual));
-        assert!(matches!(toks[2], Token::Num(2)));
-
-        let toks = lex("1==2").unwrap();
-        assert!(toks.len() == 3);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::Equality));
-        assert!(matches!(toks[2], Token::Num(2)));
-
-        let toks = lex("1!=2").unwrap();
-        assert!(toks.len() == 3);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::NotEqual));
-        assert!(matches!(toks[2], Token::Num(2)));
-
-        // test that lexer works on correct cases
-        let toks = lex("1 + 2 + 3").unwrap();
-        assert!(toks.len() == 5);
-        assert!(matches!(toks[0], Token::Num(1)));
-        assert!(matches!(toks[1], Token::Plus));
-        assert!(matches!(toks[2], Token::Num(2)));
-        assert!(matches!(toks[3], Token::Plus));
-        assert!(matches!(toks[4], Token::Num(3)));
-
-
-        let toks = lex("3 + 215 +-").unwrap();
-        assert!(toks.len() == 5);
-        assert!(matches!(toks[0], Token::Num(3)));
-        assert!(matches!(toks[1], Token::Plus));
-        assert!(matches!(toks[2], Token::Num(215)));
-        assert!(matches!(toks[3], Token::Plus));
-        assert!(matches!(toks[4], Token::Subtract));
-
-        // test that the lexer catches invalid tokens
-        assert!(matches!(lex("^^^"), Err(_)));
-
-        //test that lexer identifies left paren and right paren
-        let toks = lex("( ( ( ( ) ) ) )").unwrap();
-        assert!(toks.len() == 8);
-        assert!(matches!(toks[0], Token::LeftParen));
-        assert!(matches!(toks[1], Token::LeftParen));
-        assert!(matches!(toks[2], Token::LeftParen));
-        assert!(matches!(toks[3], Token::LeftParen));
-        assert!(matches!(toks[4], Token::RightParen));
-        assert!(matches!(toks[5], Token::RightParen));
-        assert!(matches!(toks[6], Token::RightParen));
-        assert!(matches!(toks[7], Token::RightParen));
-
-
-        // test for print
-        let toks = lex("print ").unwrap();
-        assert!(toks.len() == 1);
-        assert!(matches!(toks[0], Token::Print));
-
-         // test for else
-         let toks = lex("else ").unwrap();
-         assert!(toks.len() == 1);
-         assert!(matches!(toks[0], Token::Else));
-
-          // test for break
-        let toks = lex("break ").unwrap();
-        assert!(toks.len() == 1);
-        assert!(matches!(toks[0], Token::Break));
-
-         // test for continue
-        let toks = lex("continue ").unwrap();
-        assert!(toks.len() == 1);
-        assert!(matches!(toks[0], Token::Continue));
-
-         //test for comments
-        let toks = lex("#Hello \n 1").unwrap();
-        assert!(toks.len() == 1);
-        assert!(matches!(toks[0], Token::Num(1)));
-    }
-
-}
-
 #[cfg(test)]
-mod parser_tests {
-    use crate::{lex, parse_statement, Token};
+mod cli_tests {
+    use crate::{exit, interpreter, parse_args, Emit, Level, Mode, Stage};
+    use rustcompiler::diagnostics::{codes, Diagnostic};
 
-    #[test]
-    fn test_assignment() {
-        // Test valid assignments
-        let tokens = lex("a = 1 + 2;").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
-
-        // Test assignment with a boolean expression
-        let tokens = lex("b = a > 5;").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
-
-        // Test assignment with a parenthesized expression
-        let tokens = lex("c = (a * 3);").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
-
-        // Test assignment with multiple operators
-        let tokens = lex("d = (a + b) / (c - 1);").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
     }
 
     #[test]
-    fn test_error_handling() {
-        // Test missing semicolon
-        let tokens = lex("e = a + b").unwrap();
-        assert!(matches!(parse_statement(&tokens, &mut 0), Err(_)));
-
-        // Test invalid expression
-        let tokens = lex("f = a * ;").unwrap();
-        assert!(matches!(parse_statement(&tokens, &mut 0), Err(_)));
-
-        // Test assignment with an invalid identifier
-        let tokens = lex("3 = a + b;").unwrap();
-        assert!(matches!(parse_statement(&tokens, &mut 0), Err(_)));
+    fn subcommands_and_emit() {
+        let options = parse_args(&args("a.tt")).unwrap().unwrap();
+        assert!(options.stage == Stage::Run && options.emit.is_empty());
+
+        let options = parse_args(&args("emit-ir a.tt -o a.ir")).unwrap().unwrap();
+        assert!(options.stage == Stage::Parse);
+        assert!(options.emit == vec![(Emit::Ir, Some(String::from("a.ir")))]);
+
+        let options = parse_args(&args("run --emit tokens,ir=a.ir a.tt")).unwrap().unwrap();
+        assert!(options.emit == vec![(Emit::Tokens, None), (Emit::Ir, Some(String::from("a.ir")))]);
+
+        assert!(parse_args(&args("lex a.tt --emit ir")).is_err());
+        assert!(parse_args(&args("check a.tt -o out")).is_err());
+        assert!(parse_args(&args("run --emit tokens,ir -o out a.tt")).is_err());
+        assert!(parse_args(&args("run --emit tree a.tt")).is_err());
+
+        let options = parse_args(&args("run-ir a.ir")).unwrap().unwrap();
+        assert!(options.mode == Mode::RunIr && options.stage == Stage::Run);
+        assert!(parse_args(&args("run-ir a.ir --emit ir")).is_err());
+
+        let options = parse_args(&args("debug a.tt")).unwrap().unwrap();
+        assert!(options.mode == Mode::Debug && options.stage == Stage::Run);
+
+        assert!(parse_args(&args("repl")).unwrap().unwrap().mode == Mode::Repl);
+        assert!(parse_args(&args("repl a.tt")).is_err());
+
+        let options = parse_args(&args("fmt --check a.tt")).unwrap().unwrap();
+        assert!(options.mode == Mode::Fmt { check: true } && options.output.is_none());
+        assert!(parse_args(&args("fmt a.tt -o a.tt")).unwrap().unwrap().output == Some(String::from("a.tt")));
+        assert!(parse_args(&args("fmt --check a.tt -o b.tt")).is_err());
+        assert!(parse_args(&args("fmt a.tt --emit ir")).is_err());
+        assert!(parse_args(&args("run --check a.tt")).is_err());
+
+        assert!(parse_args(&args("a.tt")).unwrap().unwrap().limits.call_depth == interpreter::DEFAULT_CALL_DEPTH);
+        assert!(parse_args(&args("run-ir --max-call-depth 50 a.ir")).unwrap().unwrap().limits.call_depth == 50);
+        assert!(parse_args(&args("a.tt --max-call-depth 0")).is_err());
+        assert!(parse_args(&args("a.tt --max-call-depth")).is_err());
+        let limits = parse_args(&args("a.tt --max-instructions 1000 --max-memory 4096 --max-output 80")).unwrap().unwrap().limits;
+        assert!(limits.instructions == Some(1000) && limits.array_memory == Some(4096) && limits.output == Some(80));
+        assert!(parse_args(&args("a.tt --max-output -1")).is_err());
+        assert!(parse_args(&args("a.tt")).unwrap().unwrap().limits.overflow == interpreter::Overflow::Wrap);
+        assert!(parse_args(&args("a.tt --overflow trap")).unwrap().unwrap().limits.overflow == interpreter::Overflow::Trap);
+        assert!(parse_args(&args("a.tt --overflow saturate")).unwrap().unwrap().limits.overflow == interpreter::Overflow::Saturate);
+        assert!(parse_args(&args("a.tt --overflow panic")).is_err());
+
+        assert!(parse_args(&args("a.tt")).unwrap().unwrap().trace.is_none());
+        let trace = parse_args(&args("a.tt --trace-exec=main,add --trace-steps 20")).unwrap().unwrap().trace.unwrap();
+        assert!(trace.functions == vec![String::from("main"), String::from("add")] && trace.max_steps == Some(20));
+        assert!(parse_args(&args("run-ir --trace-exec a.ir")).unwrap().unwrap().trace == Some(interpreter::ExecTrace::default()));
+        assert!(parse_args(&args("a.tt --trace-steps 0")).is_err());
+
+        let options = parse_args(&args("a.tt --profile --profile-stacks a.stacks")).unwrap().unwrap();
+        assert!(options.profile && options.profile_stacks == Some(String::from("a.stacks")));
+        assert!(!parse_args(&args("a.tt")).unwrap().unwrap().profile);
+        assert!(parse_args(&args("a.tt --profile-stacks a.stacks")).is_err());
+
+        assert!(parse_args(&args("run-ir a.ir --input numbers.txt")).unwrap().unwrap().input == Some(String::from("numbers.txt")));
+        assert!(parse_args(&args("debug a.tt --input numbers.txt")).is_err());
     }
 
     #[test]
-    fn test_control_flow() {
-        // Test if statement
-        let tokens = lex("if a > 5 { b = 10; } else { b = 5; }").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
-
-        // Test while loop
-        let tokens = lex("while a < 10 { a = a + 1; }").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
-
-        // Test for loop
-        let tokens = lex("for i = 0; i < 10; i = i + 1 { println(i); }").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
+    fn exit_codes() {
+        let code = |code| exit::failure(&Diagnostic::new(code, ""));
+        assert!(code(codes::CANNOT_READ_FILE) == exit::IO && code(codes::UNRECOGNIZED_SYMBOL) == exit::LEX);
+        assert!(code(codes::MISSING_SEMICOLON) == exit::PARSE && code(codes::MISSING_RETURN) == exit::SEMANTIC);
+        assert!(code(codes::IR_MISSING_MAIN) == exit::IR && code(codes::DIVIDE_BY_ZERO) == exit::RUNTIME);
+        assert!(code(codes::IMPORT_NOT_FOUND) == exit::IMPORT && code(codes::IMPORT_CYCLE) == exit::IMPORT);
+        assert!(exit::program(42) == 42 && exit::program(-1) == 255 && exit::program(256) == 0);
     }
 
     #[test]
-    fn test_function_definition() {
-        // Test function definition
-        let tokens = lex("fn add(x, y) { return x + y; }").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
-
-        // Test function call
-        let tokens = lex("result = add(3, 5);").unwrap();
-        parse_statement(&tokens, &mut 0).unwrap();
+    fn verbosity() {
+        assert!(parse_args(&args("a.tt")).unwrap().unwrap().log_level == Level::Error);
+        assert!(parse_args(&args("--diagnostics=json a.tt")).unwrap().unwrap().log_level == Level::Off);
+        assert!(parse_args(&args("-v a.tt")).unwrap().unwrap().log_level == Level::Info);
+        assert!(parse_args(&args("-v -v a.tt")).unwrap().unwrap().log_level == Level::Trace);
+        assert!(parse_args(&args("run --trace a.tt")).unwrap().unwrap().log_level == Level::Trace);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,205 @@
+// the profile of a run: 'rustcompiler --profile <file>'.
+// counts every instruction that runs, and prints where they went once the program ends:
+//
+//   Profile: 1285 instructions, 101 calls.
+//
+//   function       calls      self     total
+//   fib              100      1184      1184
+//   main               1       101      1285
+//   ...
+//
+// a function's self count has only its own instructions, its total count those of the calls it made as well.
+// a recursive function counts the instructions of a call once, in the outermost call.
+// '--profile-stacks <file>' writes the instructions of every chain of calls in the collapsed format of flame graph tools:
+//   main;fib;fib 24
+
+use std::collections::HashMap;
+
+use crate::interpreter::FunctionBytecode;
+
+#[derive(Debug, Default)]
+pub struct Profile {
+    total: u64,
+    // by function, in the order the IR defines them.
+    names: Vec<String>,
+    calls: Vec<u64>,
+    inclusive: Vec<u64>,
+    // the instructions that ran, by function and instruction.
+    counts: Vec<Vec<u64>>,
+    // the calls running, with the number of instructions when each started, and how many calls of each function that is.
+    open: Vec<(usize, u64)>,
+    running: Vec<usize>,
+    // the chain of calls running, as in 'main;fib;fib', with where each call starts in it.
+    chain: String,
+    chain_starts: Vec<usize>,
+    stacks: HashMap<String, u64>,
+    // what finish() made of the counts, sorted by count.
+    opcodes: Vec<(&'static str, u64)>,
+    lines: Vec<(String, u64)>,
+}
+
+impl Profile {
+    // makes room for the functions of a program, about to run main.
+    pub(crate) fn start(&mut self, calls: &[FunctionBytecode], main: usize) {
+        *self = Profile::default();
+        self.names = calls.iter().map(|function| function.name().to_string()).collect();
+        self.calls = vec![0; calls.len()];
+        self.inclusive = vec![0; calls.len()];
+        self.running = vec![0; calls.len()];
+        self.counts = calls.iter().map(|function| vec![0; function.instructions()]).collect();
+        self.call(main);
+    }
+
+    // the running call is about to run an instruction.
+    pub(crate) fn count(&mut self, instruction: usize) {
+        let function = self.open.last().unwrap().0;
+        self.counts[function][instruction] += 1;
+        self.total += 1;
+        match self.stacks.get_mut(&self.chain) {
+            Some(count) => *count += 1,
+            None => {
+                self.stacks.insert(self.chain.clone(), 1);
+            }
+        }
+    }
+
+    pub(crate) fn call(&mut self, function: usize) {
+        self.calls[function] += 1;
+        self.running[function] += 1;
+        self.open.push((function, self.total));
+        self.chain_starts.push(self.chain.len());
+        if !self.chain.is_empty() {
+            self.chain.push(';');
+        }
+        self.chain.push_str(&self.names[function]);
+    }
+
+    pub(crate) fn ret(&mut self) {
+        let (function, start) = self.open.pop().unwrap();
+        self.running[function] -= 1;
+        if self.running[function] == 0 {
+            self.inclusive[function] += self.total - start;
+        }
+        self.chain.truncate(self.chain_starts.pop().unwrap());
+    }
+
+    // ends the calls still running, main and the calls of a runtime error, and sums the counts up.
+    pub(crate) fn finish(&mut self, calls: &[FunctionBytecode]) {
+        while !self.open.is_empty() {
+            self.ret();
+        }
+        let mut opcodes: HashMap<&'static str, u64> = HashMap::new();
+        let mut lines: Vec<(String, u64)> = vec![];
+        for (function, counts) in calls.iter().zip(&self.counts) {
+            let mut by_line: Vec<(String, u64)> = vec![];
+            for (instruction, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+                *opcodes.entry(function.opcode(instruction)).or_default() += count;
+                let location = function.location(instruction);
+                let line = match location.source {
+                    Some(line) => format!("{}, line {}", function.name(), line),
+                    None => format!("{}, IR line {}", function.name(), location.ir),
+                };
+                match by_line.iter_mut().find(|(l, _)| *l == line) {
+                    Some((_, total)) => *total += count,
+                    None => by_line.push((line, *count)),
+                }
+            }
+            lines.extend(by_line);
+        }
+        self.opcodes = opcodes.into_iter().collect();
+        self.opcodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        // lines with the same count stay in the order of the program.
+        lines.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        self.lines = lines;
+    }
+
+    // whether the program started, so there is something to report.
+    pub fn ran(&self) -> bool {
+        !self.names.is_empty()
+    }
+
+    // the instructions a function ran itself.
+    fn exclusive(&self, function: usize) -> u64 {
+        self.counts[function].iter().sum()
+    }
+
+    // the report of a finished profile, with the busiest functions, opcodes and lines first.
+    pub fn report(&self) -> String {
+        let percent = |count: u64| if self.total == 0 { 0.0 } else { count as f64 * 100.0 / self.total as f64 };
+        let calls: u64 = self.calls.iter().sum();
+        let mut report = format!("Profile: {} instructions, {} calls.\n\n", self.total, calls);
+
+        let width = self.names.iter().map(|name| name.len()).max().unwrap_or(0).max("function".len());
+        report += &format!("{:width$}  {:>8}  {:>8}  {:>8}\n", "function", "calls", "self", "total");
+        let mut functions: Vec<usize> = (0..self.names.len()).filter(|f| self.calls[*f] > 0).collect();
+        functions.sort_by(|a, b| self.exclusive(*b).cmp(&self.exclusive(*a)).then(self.inclusive[*b].cmp(&self.inclusive[*a])));
+        for f in functions {
+            report += &format!("{:width$}  {:>8}  {:>8}  {:>8}\n", self.names[f], self.calls[f], self.exclusive(f), self.inclusive[f]);
+        }
+
+        let width = self.opcodes.iter().map(|(opcode, _)| opcode.len()).max().unwrap_or(0).max("opcode".len());
+        report += &format!("\n{:width$}  {:>8}\n", "opcode", "count");
+        for (opcode, count) in &self.opcodes {
+            report += &format!("{:width$}  {:>8}  {:5.1}%\n", opcode, count, percent(*count));
+        }
+
+        let width = self.lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0).max("line".len());
+        report += &format!("\n{:width$}  {:>8}\n", "line", "count");
+        for (line, count) in &self.lines {
+            report += &format!("{:width$}  {:>8}  {:5.1}%\n", line, count, percent(*count));
+        }
+        report
+    }
+
+    // the instructions run by every chain of calls, one 'main;fib;fib 24' per line.
+    pub fn collapsed_stacks(&self) -> String {
+        let mut stacks: Vec<(&String, &u64)> = self.stacks.iter().collect();
+        stacks.sort();
+        stacks.iter().map(|(chain, count)| format!("{chain} {count}\n")).collect()
+    }
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use crate::interpreter::{execute_ir, Limits};
+    use crate::profile::*;
+
+    #[test]
+    fn counts() {
+        let code = "%func fib(%int n)
+%int small
+%lt small, n, 2
+%branch_ifn small, :big
+%ret n
+:big
+%int a
+%int b
+%sub a, n, 1
+%call a, fib(a)
+%sub b, n, 2
+%call b, fib(b)
+%add a, a, b
+%ret a
+%endfunc
+
+%func main
+%int r
+%call r, fib(4)
+%ret r
+%endfunc
+";
+        let mut profile = Profile::default();
+        assert!(execute_ir(code, &Limits::default(), None, Some(&mut profile), std::io::empty(), std::io::sink()) == Ok(3));
+        // fib(4) makes 9 calls: 4 of them with n >= 2, which run 12 instructions, and 5 that run 4.
+        let report = profile.report();
+        assert!(report.starts_with("Profile: 71 instructions, 10 calls.\n"));
+        assert!(report.contains("\nfib              9        68        68\nmain             1         3        71\n"));
+        assert!(report.contains("\n%int               18   25.4%\n"));
+        assert!(report.contains("\nfib, IR line 2           9   12.7%\n"));
+
+        let stacks = profile.collapsed_stacks();
+        assert!(stacks.starts_with("main 3\nmain;fib 12\nmain;fib;fib 24\n"));
+        let total: u64 = stacks.lines().map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap()).sum();
+        assert!(total == 71);
+    }
+}
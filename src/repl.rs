@@ -0,0 +1,299 @@
+// the REPL: 'rustcompiler repl'.
+// statements run as soon as they are entered, in a main function that is rebuilt for every input:
+// it declares the variables of the earlier inputs, sets them to the values they had, then runs the new statements.
+// functions are kept and compiled along with every input, so a function can call the ones defined before it.
+// an expression without a ';' prints its value.
+//
+//   >> int a;
+//   >> a = 6 * 7;
+//   >> a
+//   42
+//   >> :ir
+//   %mov a, ...
+
+use std::io::{self, BufRead, Write};
+
+use crate::diagnostics::{codes, Diagnostic};
+use crate::interpreter::{self, Local};
+use crate::{compile_to_ir, lex, Token};
+
+const HELP: &str = "\
+statements run right away, and variables keep their values between inputs.
+'func name(...) { ... }' defines a function, replacing an earlier one with the same name.
+an expression without a ';' prints its value.
+
+:ir     show the IR generated for the last input
+:help   show this text
+:quit   leave the REPL
+";
+
+#[derive(Default)]
+pub struct Session {
+    // the functions defined so far as source, in the order they were first defined.
+    functions: Vec<(String, String)>,
+    // the variables of main and their values after the last input that ran.
+    variables: Vec<(String, Local)>,
+    // the IR generated for the last input.
+    last_ir: String,
+}
+
+impl Session {
+    pub fn last_ir(&self) -> &str {
+        &self.last_ir
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Local> {
+        self.variables.iter().find(|(n, _)| n == name).map(|(_, local)| local)
+    }
+
+    // compiles and runs one input: a function definition, or statements for main,
+    // which take what they read from program_input and write what they print to output.
+    pub fn eval(&mut self, input: &str, program_input: impl BufRead, output: impl Write) -> Result<(), Vec<Diagnostic>> {
+        let tokens = lex(input).map_err(|error| vec![error])?;
+        match tokens.as_slice() {
+            [] => Ok(()),
+            [Token::Func, Token::Ident(name), ..] => self.define(name, input),
+            _ => self.run(input, &tokens, program_input, output),
+        }
+    }
+
+    fn define(&mut self, name: &str, input: &str) -> Result<(), Vec<Diagnostic>> {
+        if name == "main" {
+            return Err(vec![Diagnostic::new(codes::DUPLICATE_FUNCTION, "main is where the REPL runs statements. Enter them without a function around them.")]);
+        }
+        let mut functions = self.functions.clone();
+        match functions.iter_mut().find(|(n, _)| n == name) {
+            Some(function) => function.1 = input.to_string(),
+            None => functions.push((name.to_string(), input.to_string())),
+        }
+        // main is only there because every program needs one, and is not run.
+        let compiled = compile_to_ir(&format!("{}func main() {{\n}}\n", program(&functions)))?;
+        self.last_ir = function_ir(&compiled.ir, name).join("\n");
+        self.functions = functions;
+        Ok(())
+    }
+
+    fn run(&mut self, input: &str, tokens: &[Token], program_input: impl BufRead, output: impl Write) -> Result<(), Vec<Diagnostic>> {
+        let trimmed = input.trim_end();
+        let (statements, value) = if trimmed.ends_with(';') || trimmed.ends_with('}') {
+            (input.to_string(), None)
+        } else {
+            // an expression: print its value through a variable that is not kept, a long so that it holds either type.
+            let name = self.unused_name(tokens);
+            (format!("long {name};\n{name} = long({trimmed});\nprint({name});\n"), Some(name))
+        };
+
+        let preamble = self.preamble();
+        let main = |body: &str| format!("{}func main() {{\n{}{}}}\n", program(&self.functions), preamble, body);
+        let compiled = compile_to_ir(&main(&statements))?;
+
+        // the IR of the input is what follows the IR of the preamble in main.
+        let before = compile_to_ir(&main("")).map(|compiled| function_ir(&compiled.ir, "main").len()).unwrap_or(0);
+        let ir = function_ir(&compiled.ir, "main");
+        self.last_ir = ir[before.saturating_sub(1).min(ir.len())..ir.len().saturating_sub(1)].join("\n");
+
+        let (_, locals) = interpreter::execute_keeping_locals(&compiled.ir, program_input, output).map_err(|error| vec![error])?;
+        self.variables = locals.into_iter().filter(|(name, _)| !name.starts_with("_temp") && Some(name) != value.as_ref()).collect();
+        Ok(())
+    }
+
+    // declares the variables of the earlier inputs and gives them their values back.
+    fn preamble(&self) -> String {
+        let mut code = String::new();
+        for (name, local) in &self.variables {
+            match local {
+                Local::Int(value) => {
+                    code += &format!("int {name};\n");
+                    if *value != 0 {
+                        code += &format!("{name} = {};\n", literal(*value));
+                    }
+                }
+                Local::Long(value) => {
+                    code += &format!("long {name};\n");
+                    if *value != 0 {
+                        code += &format!("{name} = {};\n", literal_long(*value));
+                    }
+                }
+                Local::Array(values) => {
+                    code += &format!("int [{}] {name};\n", values.len());
+                    for (i, value) in values.iter().enumerate() {
+                        if *value != 0 {
+                            code += &format!("{name}[{i}] = {};\n", literal(*value));
+                        }
+                    }
+                }
+            }
+        }
+        code
+    }
+
+    // a name for the value of an expression, that is neither a variable nor used in the expression.
+    fn unused_name(&self, tokens: &[Token]) -> String {
+        let used = |name: &str| {
+            self.variable(name).is_some() || tokens.iter().any(|t| matches!(t, Token::Ident(ident) if ident == name))
+        };
+        let mut name = String::from("value");
+        let mut n = 0;
+        while used(&name) {
+            n += 1;
+            name = format!("value{n}");
+        }
+        name
+    }
+}
+
+fn program(functions: &[(String, String)]) -> String {
+    functions.iter().map(|(_, source)| format!("{source}\n")).collect()
+}
+
+// the language has no negative literals.
+fn literal(value: i32) -> String {
+    match value {
+        i32::MIN => format!("0 - {} - 1", i32::MAX),
+        value if value < 0 => format!("0 - {}", -value),
+        value => value.to_string(),
+    }
+}
+
+// a number beyond the ints is a long.
+fn literal_long(value: i64) -> String {
+    match value {
+        i64::MIN => format!("0 - {} - 1", i64::MAX),
+        value if value < 0 => format!("0 - {}", -value),
+        value => value.to_string(),
+    }
+}
+
+// the lines of one function in the IR, from '%func' to '%endfunc'.
+fn function_ir<'a>(ir: &'a str, name: &str) -> Vec<&'a str> {
+    let header = format!("%func {name}");
+    ir.lines()
+        .skip_while(|line| *line != header && !line.starts_with(&format!("{header}(")))
+        .scan(false, |done, line| {
+            if *done {
+                return None;
+            }
+            *done = line == "%endfunc";
+            Some(line)
+        })
+        .collect()
+}
+
+// reads inputs from stdin until ':quit' or the end of the input.
+// an input ends at the end of a line, unless a '{' on it is still open.
+pub fn run() {
+    let stdin = io::stdin();
+    let mut session = Session::default();
+    println!("rustcompiler REPL. ':help' for help, ':quit' to leave.");
+    loop {
+        let mut input = String::new();
+        let mut depth = 0;
+        loop {
+            print!("{}", if input.is_empty() { ">> " } else { ".. " });
+            let _ = io::stdout().flush();
+            let mut line = String::new();
+            match stdin.lock().read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+            input += &line;
+            if depth <= 0 {
+                break;
+            }
+        }
+
+        match input.trim() {
+            "" => {}
+            ":quit" | ":q" => return,
+            ":help" => print!("{HELP}"),
+            ":ir" => println!("{}", session.last_ir()),
+            command if command.starts_with(':') => eprintln!("Unknown command '{command}'. ':help' lists the commands."),
+            _ => {
+                if let Err(errors) = session.eval(&input, stdin.lock(), io::stdout()) {
+                    for error in errors {
+                        eprintln!("{error}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod repl_tests {
+    use crate::interpreter::Local;
+    use crate::repl::*;
+
+    #[test]
+    fn variables_persist() {
+        let mut session = Session::default();
+        let mut output = vec![];
+        session.eval("int a;", io::empty(), &mut output).unwrap();
+        session.eval("int [3] arr;", io::empty(), &mut output).unwrap();
+        session.eval("a = 0 - 5;", io::empty(), &mut output).unwrap();
+        session.eval("arr[1] = a * 2;", io::empty(), &mut output).unwrap();
+        assert!(session.variable("a") == Some(&Local::Int(-5)));
+        assert!(session.variable("arr") == Some(&Local::Array(vec![0, -10, 0])));
+        assert!(session.last_ir().contains("%mov [arr + 1]"));
+        assert!(!session.last_ir().contains("%int a"));
+
+        // an expression is printed, and its variable is not kept.
+        session.eval("a + 1", io::empty(), &mut output).unwrap();
+        assert!(output == b"-4\n");
+        assert!(session.variable("value").is_none());
+
+        // a failed input leaves the session as it was.
+        assert!(session.eval("b = 1;", io::empty(), &mut output).is_err());
+        assert!(session.eval("a = a / 0;", io::empty(), &mut output).is_err());
+        assert!(session.variable("a") == Some(&Local::Int(-5)));
+    }
+
+    #[test]
+    fn functions_incrementally() {
+        let mut session = Session::default();
+        let mut output = vec![];
+        session.eval("func double(int x) {\n  return x * 2;\n}", io::empty(), &mut output).unwrap();
+        assert!(session.last_ir().starts_with("%func double(%int x)"));
+        session.eval("func quad(int x) {\n  return double(double(x));\n}", io::empty(), &mut output).unwrap();
+        session.eval("int a;", io::empty(), &mut output).unwrap();
+        session.eval("a = quad(3);", io::empty(), &mut output).unwrap();
+        assert!(session.variable("a") == Some(&Local::Int(12)));
+
+        session.eval("func double(int x) {\n  return x + x + x;\n}", io::empty(), &mut output).unwrap();
+        session.eval("a = quad(1);", io::empty(), &mut output).unwrap();
+        assert!(session.variable("a") == Some(&Local::Int(9)));
+
+        assert!(session.eval("func main() {\n}", io::empty(), &mut output).is_err());
+        assert!(session.eval("func broken(int x) {\n  return y;\n}", io::empty(), &mut output).is_err());
+        assert!(session.eval("a = broken(1);", io::empty(), &mut output).is_err());
+    }
+
+    #[test]
+    fn literals() {
+        assert!(literal(5) == "5" && literal(-5) == "0 - 5");
+        assert!(literal(i32::MIN) == "0 - 2147483647 - 1");
+        assert!(literal_long(-5) == "0 - 5");
+        assert!(literal_long(i64::MAX) == "9223372036854775807");
+        assert!(literal_long(i64::MIN) == "0 - 9223372036854775807 - 1");
+    }
+
+    #[test]
+    fn longs() {
+        let mut session = Session::default();
+        let mut output = vec![];
+        session.eval("long big;", io::empty(), &mut output).unwrap();
+        for value in [i64::MAX, i64::MIN, -3_000_000_000, i32::MIN as i64, 5] {
+            let input = format!("big = {};", literal_long(value));
+            session.eval(&input, io::empty(), &mut output).unwrap();
+            assert!(session.variable("big") == Some(&Local::Long(value)));
+        }
+        // the values of longs survive the next input.
+        session.eval("int small;", io::empty(), &mut output).unwrap();
+        assert!(session.variable("big") == Some(&Local::Long(5)));
+        session.eval("big", io::empty(), &mut output).unwrap();
+        assert!(output == b"5\n");
+        assert!(!session.last_ir().contains("%widen"));
+        assert!(session.eval("small = big;", io::empty(), &mut output).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+// "did you mean" suggestions for misspelled names.
+// names are compared with the edit distance: the number of single character
+// insertions, deletions, substitutions or swaps of two neighbouring characters
+// needed to turn one into the other.
+
+pub const KEYWORDS: [&str; 12] = ["func", "return", "int", "long", "read", "while", "if", "print", "else", "break", "continue", "import"];
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // dist[i][j] is the distance between the first i letters of a and the first j letters of b.
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (dist[i - 1][j] + 1).min(dist[i][j - 1] + 1).min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(dist[i - 2][j - 2] + 1);
+            }
+            dist[i][j] = best;
+        }
+    }
+    dist[a.len()][b.len()]
+}
+
+// the candidate closest to name, if it is close enough to be a likely typo.
+// the typos allowed grow with the longer of the two names, so 'cnt' is close to 'count'.
+// a name of one character is close to every other one, so it gets no suggestion.
+pub fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let length = name.chars().count();
+    if length < 2 {
+        return None;
+    }
+    let mut best: Option<(usize, &str)> = None;
+    for candidate in candidates {
+        if candidate == name {
+            continue;
+        }
+        let limit = ((length.max(candidate.chars().count()) + 1) / 3).max(1);
+        let distance = edit_distance(name, candidate);
+        // ties go to the alphabetically first name, so the suggestion does not depend on symbol table order.
+        if distance <= limit && best.is_none_or(|(d, b)| distance < d || (distance == d && candidate < b)) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
+// a keyword the identifier is one typo away from, such as 'whle' for 'while'.
+pub fn keyword(ident: &str) -> Option<&'static str> {
+    if ident.chars().count() < 2 {
+        return None;
+    }
+    KEYWORDS.iter().find(|k| edit_distance(ident, k) == 1).copied()
+}
+
+// formats a suggestion to be appended to an error message.
+pub fn did_you_mean(suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(name) => format!(". Did you mean '{name}'?"),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod suggest_tests {
+    use crate::suggest::*;
+
+    #[test]
+    fn distances() {
+        assert!(edit_distance("cnt", "cnt") == 0);
+        assert!(edit_distance("cnt", "count") == 2);
+        assert!(edit_distance("pritn", "print") == 1);
+        assert!(edit_distance("whle", "while") == 1);
+        assert!(edit_distance("", "abc") == 3);
+    }
+
+    #[test]
+    fn closest_name() {
+        let names = ["count", "total", "i"];
+        assert!(closest("cout", names.iter().copied()) == Some("count"));
+        assert!(closest("totl", names.iter().copied()) == Some("total"));
+        assert!(closest("xyz", names.iter().copied()).is_none());
+        assert!(closest("cnt", ["count"].into_iter()) == Some("count"));
+        // one letter is one typo away from any other letter.
+        assert!(closest("j", names.iter().copied()).is_none());
+        assert!(closest("c", ["a", "b"].into_iter()).is_none());
+    }
+
+    #[test]
+    fn keywords() {
+        assert!(keyword("whle") == Some("while"));
+        assert!(keyword("retrun") == Some("return"));
+        assert!(keyword("pirnt") == Some("print"));
+        assert!(keyword("imprt") == Some("import"));
+        assert!(keyword("count").is_none());
+        assert!(keyword("i").is_none());
+    }
+
+    #[test]
+    fn suggestions_in_errors() {
+        let message = |source: &str| crate::compile_to_ir(source).unwrap_err()[0].message.clone();
+        // a close variable wins over a keyword.
+        assert!(message("func main() {\n  int count;\n  cnt = 1;\n}") == "Undeclared variable used: cnt. Did you mean 'count'?");
+        assert!(message("func main() {\n  int a;\n  c = a;\n}") == "Undeclared variable used: c");
+    }
+}
@@ -0,0 +1,217 @@
+// where every variable and function of a program is declared and used, for the language server in lsp.rs.
+// the compiler goes from tokens straight to IR, which keeps no positions, so this walks the tokens again:
+// 'func name' declares a function, 'int name', 'long name' and 'int [n] name' a variable of the function they are in,
+// a name followed by '(' calls a function, and any other name uses a variable of the function it is in.
+// like the semantic checks, a variable has to be declared before it is used, but a function can be called
+// before its definition.
+
+use crate::diagnostics::Span;
+use crate::{DataType, Token};
+
+pub struct Symbol {
+    pub name: String,
+    pub data_type: DataType,
+    // the function a variable belongs to. empty for functions.
+    pub scope: String,
+    pub parameter: bool,
+    // the number of parameters of a function, the size of an array.
+    pub size: usize,
+    // the name in the declaration.
+    pub definition: Span,
+    // for a function, from 'func' to its closing '}'.
+    pub extent: Span,
+    pub references: Vec<Span>,
+}
+
+impl Symbol {
+    // what a hover shows.
+    pub fn describe(&self) -> String {
+        match self.data_type {
+            DataType::Function => {
+                let s = if self.size == 1 { "" } else { "s" };
+                format!("{}: {}, {} parameter{s}", self.name, self.data_type, self.size)
+            }
+            DataType::Array => format!("{}: {}, {} elements, in {}", self.name, self.data_type, self.size, self.scope),
+            DataType::Int | DataType::Long if self.parameter => format!("{}: {}, parameter of {}", self.name, self.data_type, self.scope),
+            DataType::Int | DataType::Long => format!("{}: {}, in {}", self.name, self.data_type, self.scope),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Symbols {
+    pub symbols: Vec<Symbol>,
+    // every name in the source, and the symbol it belongs to if it was found.
+    occurrences: Vec<(Span, Option<usize>)>,
+    // a name that was declared twice in the same scope, the second declaration.
+    pub duplicates: Vec<Span>,
+}
+
+impl Symbols {
+    pub fn new(tokens: &[Token], spans: &[Span]) -> Symbols {
+        let mut symbols = Symbols::default();
+        let mut calls: Vec<(String, Span)> = vec![];
+        let mut scope = String::new();
+        let mut function: Option<usize> = None;
+        let mut depth = 0;
+        let mut in_parameters = false;
+        let name = |i: usize| match tokens.get(i) {
+            Some(Token::Ident(name)) => Some(name.clone()),
+            _ => None,
+        };
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let span = spans[i];
+            match &tokens[i] {
+                Token::Func => {
+                    if let Some(name) = name(i + 1) {
+                        let size = tokens[i + 1..].iter().take_while(|t| !matches!(t, Token::RightParen)).filter(|t| matches!(t, Token::Int | Token::Long)).count();
+                        function = Some(symbols.declare(&name, DataType::Function, "", false, size, spans[i + 1]));
+                        symbols.symbols[function.unwrap()].extent = span;
+                        scope = name;
+                        in_parameters = true;
+                        i += 1;
+                    }
+                }
+                token @ (Token::Int | Token::Long) => {
+                    // 'int name', 'long name' or 'int [n] name'.
+                    let (declared, data_type, size) = match (tokens.get(i + 1), tokens.get(i + 2)) {
+                        (Some(Token::LeftBracket), Some(Token::Num(n))) => (i + 4, DataType::Array, (*n).max(0) as usize),
+                        _ if matches!(token, Token::Long) => (i + 1, DataType::Long, 1),
+                        _ => (i + 1, DataType::Int, 1),
+                    };
+                    if let Some(name) = name(declared) {
+                        let scope = scope.clone();
+                        symbols.declare(&name, data_type, &scope, in_parameters, size, spans[declared]);
+                        i = declared;
+                    }
+                }
+                Token::Ident(name) if matches!(tokens.get(i + 1), Some(Token::LeftParen)) => calls.push((name.clone(), span)),
+                Token::Ident(name) => {
+                    let symbol = symbols.variable(name, &scope);
+                    if let Some(symbol) = symbol {
+                        symbols.symbols[symbol].references.push(span);
+                    }
+                    symbols.occurrences.push((span, symbol));
+                }
+                Token::LeftCurly => {
+                    depth += 1;
+                    in_parameters = false;
+                }
+                Token::RightCurly => {
+                    depth -= 1;
+                    if depth == 0 {
+                        if let Some(function) = function.take() {
+                            symbols.symbols[function].extent.end = span.end;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        for (name, span) in calls {
+            let symbol = symbols.symbols.iter().position(|s| matches!(s.data_type, DataType::Function) && s.name == name);
+            if let Some(symbol) = symbol {
+                symbols.symbols[symbol].references.push(span);
+            }
+            symbols.occurrences.push((span, symbol));
+        }
+        symbols.occurrences.sort_by_key(|(span, _)| span.start);
+        symbols
+    }
+
+    fn declare(&mut self, name: &str, data_type: DataType, scope: &str, parameter: bool, size: usize, span: Span) -> usize {
+        let duplicate = match data_type {
+            DataType::Function => self.function(name).is_some(),
+            _ => self.variable(name, scope).is_some(),
+        };
+        if duplicate {
+            self.duplicates.push(span);
+        }
+        self.symbols.push(Symbol {
+            name: name.to_string(),
+            data_type,
+            scope: scope.to_string(),
+            parameter,
+            size,
+            definition: span,
+            extent: span,
+            references: vec![],
+        });
+        self.occurrences.push((span, Some(self.symbols.len() - 1)));
+        self.symbols.len() - 1
+    }
+
+    fn variable(&self, name: &str, scope: &str) -> Option<usize> {
+        self.symbols.iter().position(|s| !matches!(s.data_type, DataType::Function) && s.name == name && s.scope == scope)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| matches!(s.data_type, DataType::Function) && s.name == name)
+    }
+
+    // the symbol whose name is at a byte offset, declared or used there.
+    pub fn at(&self, offset: usize) -> Option<&Symbol> {
+        let (_, symbol) = self.occurrences.iter().find(|(span, _)| span.start <= offset && offset <= span.end)?;
+        symbol.map(|symbol| &self.symbols[symbol])
+    }
+
+    // the names that were used without being declared, in the order they appear.
+    pub fn undeclared(&self) -> impl Iterator<Item = Span> + '_ {
+        self.occurrences.iter().filter(|(_, symbol)| symbol.is_none()).map(|(span, _)| *span)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter().filter(|s| matches!(s.data_type, DataType::Function))
+    }
+}
+
+#[cfg(test)]
+mod symbols_tests {
+    use crate::diagnostics::Span;
+    use crate::symbols::*;
+    use crate::lex_with_spans;
+
+    const SOURCE: &str = "func add(int a, int b) {
+    return a + b;
+}
+
+func main() {
+    int [4] array;
+    int a;
+    a = add(1, 2);
+    array[0] = a;
+    c = later(a);
+}
+";
+
+    fn at<'a>(symbols: &'a Symbols, text: &str, nth: usize) -> Option<&'a Symbol> {
+        let offset = SOURCE.match_indices(text).nth(nth).unwrap().0;
+        symbols.at(offset)
+    }
+
+    #[test]
+    fn declarations_and_uses() {
+        let (tokens, spans) = lex_with_spans(SOURCE).unwrap();
+        let symbols = Symbols::new(&tokens, &spans);
+
+        let add = symbols.function("add").unwrap();
+        assert!(add.describe() == "add: Function, 2 parameters");
+        assert!(add.references.len() == 1 && SOURCE[add.extent.start..add.extent.end].ends_with("a + b;\n}"));
+
+        // the 'a' of add and the 'a' of main are different variables.
+        let a = at(&symbols, "a + b", 0).unwrap();
+        assert!(a.describe() == "a: Int, parameter of add" && a.references.len() == 1);
+        let a = at(&symbols, "a = add", 0).unwrap();
+        assert!(a.describe() == "a: Int, in main" && a.references.len() == 3);
+        assert!(at(&symbols, "array", 1).unwrap().describe() == "array: Int[], 4 elements, in main");
+        assert!(at(&symbols, "add(1", 0).unwrap().name == "add");
+
+        let undeclared: Vec<Span> = symbols.undeclared().collect();
+        assert!(undeclared.iter().map(|span| &SOURCE[span.start..span.end]).eq(["c", "later"]));
+        assert!(symbols.duplicates.is_empty());
+    }
+}
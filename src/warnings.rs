@@ -0,0 +1,133 @@
+// compiler warnings.
+// a warning never stops compilation. every warning has a kind with a short name,
+// which is printed next to the message and is what '-A' / '--allow' takes to silence it:
+//   -A unused-variable        silences every unused variable warning
+//   -A unused-variable:tmp    silences the warning for the variable 'tmp' only
+
+use std::fmt;
+
+use crate::diagnostics::codes;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarningKind {
+    UninitializedRead,
+    UnusedVariable,
+    UnusedParameter,
+    UnusedFunction,
+    UnreachableCode,
+}
+
+const KINDS: [WarningKind; 5] = [
+    WarningKind::UninitializedRead,
+    WarningKind::UnusedVariable,
+    WarningKind::UnusedParameter,
+    WarningKind::UnusedFunction,
+    WarningKind::UnreachableCode,
+];
+
+impl WarningKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::UninitializedRead => "uninitialized",
+            WarningKind::UnusedVariable => "unused-variable",
+            WarningKind::UnusedParameter => "unused-parameter",
+            WarningKind::UnusedFunction => "unused-function",
+            WarningKind::UnreachableCode => "unreachable-code",
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::UninitializedRead => codes::UNINITIALIZED,
+            WarningKind::UnusedVariable => codes::UNUSED_VARIABLE,
+            WarningKind::UnusedParameter => codes::UNUSED_PARAMETER,
+            WarningKind::UnusedFunction => codes::UNUSED_FUNCTION,
+            WarningKind::UnreachableCode => codes::UNREACHABLE_CODE,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WarningKind> {
+        KINDS.iter().find(|kind| kind.name() == name).copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    // the variable, parameter or function the warning is about.
+    // unreachable code warnings name the function the code is in.
+    pub symbol: String,
+    pub message: String,
+    // the file the warning is in, for programs of several files, and its line when the IR has the marks of the lines, like Diagnostic's.
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "Warning[{}] in {}: {} [{}]", self.kind.code(), file, self.message, self.kind.name()),
+            None => write!(f, "Warning[{}]: {} [{}]", self.kind.code(), self.message, self.kind.name()),
+        }
+    }
+}
+
+// the warnings silenced from the commandline.
+#[derive(Default)]
+pub struct Allowed {
+    entries: Vec<(WarningKind, Option<String>)>,
+}
+
+impl Allowed {
+    // takes 'kind' or 'kind:symbol'.
+    pub fn allow(&mut self, spec: &str) -> Result<(), String> {
+        let (name, symbol) = match spec.split_once(':') {
+            Some((name, symbol)) => (name, Some(symbol.to_string())),
+            None => (spec, None),
+        };
+        match WarningKind::from_name(name) {
+            Some(kind) => {
+                self.entries.push((kind, symbol));
+                Ok(())
+            }
+            None => {
+                let names: Vec<&str> = KINDS.iter().map(|kind| kind.name()).collect();
+                Err(format!("Unknown warning '{}'. Expected one of: {}", name, names.join(", ")))
+            }
+        }
+    }
+
+    pub fn is_allowed(&self, warning: &Warning) -> bool {
+        self.entries.iter().any(|(kind, symbol)| {
+            *kind == warning.kind && symbol.as_ref().is_none_or(|s| *s == warning.symbol)
+        })
+    }
+}
+
+#[cfg(test)]
+mod warnings_tests {
+    use crate::warnings::*;
+
+    #[test]
+    fn allow_kind_and_symbol() {
+        let unused = |symbol: &str| Warning {
+            kind: WarningKind::UnusedVariable,
+            symbol: String::from(symbol),
+            message: String::new(),
+            file: None,
+            line: None,
+        };
+
+        let mut allowed = Allowed::default();
+        assert!(!allowed.is_allowed(&unused("a")));
+
+        allowed.allow("unused-variable:a").unwrap();
+        assert!(allowed.is_allowed(&unused("a")));
+        assert!(!allowed.is_allowed(&unused("b")));
+
+        allowed.allow("unused-variable").unwrap();
+        assert!(allowed.is_allowed(&unused("b")));
+
+        assert!(allowed.allow("unused-everything").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+// runs the rustcompiler binary the way a user or an editor does, for what the unit tests cannot reach:
+// the real stdin and stdout, and what ends up on stderr.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+// writes a file into a directory of its own, and returns its path.
+fn file(test: &str, name: &str, text: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("rustcompiler-cli-{}-{test}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, text).unwrap();
+    path
+}
+
+// runs rustcompiler with the arguments, giving it stdin.
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rustcompiler"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn bad_input_is_logged_at_the_error_level() {
+    let program = file("input", "echo.tt", "func main() {\n  int a;\n  read(a);\n  print(a);\n}\n");
+    let output = run(&[program.to_str().unwrap()], "x\n7\n");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("'x' is not a valid number"));
+    // json diagnostics turn the log off, so stderr holds only json.
+    let output = run(&["--diagnostics=json", program.to_str().unwrap()], "x\n7\n");
+    assert!(output.stderr.is_empty() && String::from_utf8(output.stdout).unwrap().trim() == "7");
+}
+
+#[test]
+fn run_ir_reports_malformed_files() {
+    for (name, text) in [("unfinished.ir", "%func main\n%out 1"), ("label.ir", "%func main\n:end"), ("big.ir", "%func main\n%out 99999999999999999999\n%endfunc\n")] {
+        let path = file("run-ir", name, text);
+        let output = run(&["run-ir", path.to_str().unwrap()], "");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        // an error of its own, not a panic, and nothing on stdout.
+        assert!(output.status.code() == Some(13) && output.stdout.is_empty(), "{name}: {stderr}");
+        assert!(stderr.contains("Error[E0300]"), "{name}: {stderr}");
+    }
+}
+
+#[test]
+fn warnings_in_json_name_their_file() {
+    let lib = file("warnings", "lib.tt", "func helper() {\n    int x;\n    return 1;\n}\n");
+    let main = file("warnings", "main.tt", "import \"lib.tt\";\nfunc main() {\n    print(helper());\n}\n");
+    let output = run(&["check", "--diagnostics=json", main.to_str().unwrap()], "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!(r#""file":"{}""#, lib.display())), "{stderr}");
+}
+
+#[test]
+fn runtime_errors_in_json_point_at_their_line() {
+    let program = file("json", "divide.tt", "func main() {\n  int a;\n  a = 0;\n  a = 1 / a;\n}\n");
+    let output = run(&["--diagnostics=json", program.to_str().unwrap()], "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(r#""code":"E0401""#), "{stderr}");
+    assert!(stderr.contains(r#""span":{"line":4,"column":3,"end_line":4,"end_column":13}"#), "{stderr}");
+}
+
+#[test]
+fn semantic_errors_and_warnings_in_json_point_at_their_line() {
+    let program = file("json-lines", "undeclared.tt", "func main() {\n  int a;\n  int b;\n  b = 1;\n  a = c;\n}\n");
+    let output = run(&["check", "--diagnostics=json", program.to_str().unwrap()], "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(r#""code":"E0201""#) && stderr.contains(r#""span":{"line":5,"column":3,"end_line":5,"end_column":9}"#), "{stderr}");
+
+    let program = file("json-lines", "unused.tt", "func main() {\n  int a;\n  return 0;\n  print(1);\n}\n");
+    let output = run(&["check", "--diagnostics=json", program.to_str().unwrap()], "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains(r#""span":null"#), "{stderr}");
+    assert!(stderr.lines().any(|line| line.contains(r#""code":"W0002""#) && line.contains(r#""span":{"line":2,"#)), "{stderr}");
+    assert!(stderr.lines().any(|line| line.contains(r#""code":"W0005""#) && line.contains(r#""span":{"line":4,"#)), "{stderr}");
+}
+
+#[test]
+fn debug_reads_commands_from_a_pipe() {
+    let program = file("debug", "count.tt", "func main() {\n  int a;\n  a = 1;\n  a = a + 1;\n  print(a);\n}\n");
+    let path = program.to_str().unwrap().to_string();
+    // a debugger waiting on stdin never ends, so the test gives up on it instead.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || sender.send(run(&["debug", &path], "step\nstep\ncontinue\n")));
+    let output = receiver.recv_timeout(Duration::from_secs(10)).expect("the debugger stopped reading commands");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.matches("(debug) ").count() == 3, "{stdout}");
+    assert!(stdout.contains("main, line 4 ") && stdout.ends_with("2\nThe program returned 0.\n"), "{stdout}");
+}